@@ -0,0 +1,250 @@
+use bencher::{benchmark_group, benchmark_main, black_box, Bencher};
+use chrono::{DateTime, TimeZone, Utc};
+use rand::Rng;
+use std::collections::VecDeque;
+use std::time::Duration;
+use ta::indicators::{
+    AverageTrueRange, BollingerBands, BucketPolicy, ChandelierExit, CommodityChannelIndex,
+    EfficiencyRatio, ExponentialMovingAverage, FastStochastic, KaufmanAdaptiveMovingAverage,
+    KeltnerChannel, MaxDrawdown, MaxDrawup, Maximum, MeanAbsoluteDeviation, Minimum,
+    MoneyFlowIndex, MovingAverageConvergenceDivergence, OnBalanceVolume, PercentagePriceOscillator,
+    RateOfChange, RelativeStrengthIndex, SimpleMovingAverage, SlowStochastic, StandardDeviation,
+    TrueRange, WeightedMovingAverage,
+};
+use ta::{Close, DataItem, Next, Reset};
+
+const ITEMS_COUNT: usize = 5_000;
+
+fn rand_data_item() -> DataItem {
+    let mut rng = rand::thread_rng();
+
+    let low = rng.gen_range(0.0, 500.0);
+    let high = rng.gen_range(500.0, 1000.0);
+    let open = rng.gen_range(low, high);
+    let close = rng.gen_range(low, high);
+    let volume = rng.gen_range(0.0, 10_000.0);
+
+    DataItem::builder()
+        .open(open)
+        .high(high)
+        .low(low)
+        .close(close)
+        .volume(volume)
+        .build()
+        .unwrap()
+}
+
+// Points are 4 hours apart so that every item opens a new slot for the default
+// 14 day indicators instead of replacing the previous one.
+fn rand_data_items() -> Vec<(DateTime<Utc>, DataItem)> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..ITEMS_COUNT)
+        .map(|i| {
+            (
+                start + chrono::Duration::hours(4 * i as i64),
+                rand_data_item(),
+            )
+        })
+        .collect()
+}
+
+macro_rules! bench_indicators {
+    ($($indicator:ident), *) => {
+        $(
+            #[allow(non_snake_case)]
+            fn $indicator(bench: &mut Bencher) {
+                let items = rand_data_items();
+                let mut indicator = $indicator::default();
+
+                bench.iter(|| {
+                    indicator.reset();
+                    for (timestamp, item) in items.iter() {
+                        black_box(indicator.next((*timestamp, item)));
+                    }
+                })
+            }
+        )*
+
+        benchmark_group!(indicators, $($indicator,)*);
+    }
+}
+
+bench_indicators!(
+    SimpleMovingAverage,
+    WeightedMovingAverage,
+    ExponentialMovingAverage,
+    KaufmanAdaptiveMovingAverage,
+    StandardDeviation,
+    MeanAbsoluteDeviation,
+    CommodityChannelIndex,
+    BollingerBands,
+    KeltnerChannel,
+    ChandelierExit,
+    RelativeStrengthIndex,
+    MoneyFlowIndex,
+    OnBalanceVolume,
+    MovingAverageConvergenceDivergence,
+    PercentagePriceOscillator,
+    FastStochastic,
+    SlowStochastic,
+    TrueRange,
+    AverageTrueRange,
+    Maximum,
+    Minimum,
+    MaxDrawdown,
+    MaxDrawup,
+    RateOfChange,
+    EfficiencyRatio
+);
+
+// A one day window of minute buckets holds 1440 values of minute data, which is where
+// scanning the whole window on every update used to hurt. The buckets are fixed, as the
+// heuristic would bucket a one day window by day.
+const LARGE_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+const MINUTE: Duration = Duration::from_secs(60);
+
+fn minute_data_items() -> Vec<(DateTime<Utc>, DataItem)> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..ITEMS_COUNT)
+        .map(|i| {
+            (
+                start + chrono::Duration::minutes(i as i64),
+                rand_data_item(),
+            )
+        })
+        .collect()
+}
+
+fn maximum_large_window(bench: &mut Bencher) {
+    let items = minute_data_items();
+    let mut indicator = Maximum::with_policy(LARGE_WINDOW, BucketPolicy::Fixed(MINUTE)).unwrap();
+
+    bench.iter(|| {
+        indicator.reset();
+        for (timestamp, item) in items.iter() {
+            black_box(indicator.next((*timestamp, item)));
+        }
+    })
+}
+
+fn minimum_large_window(bench: &mut Bencher) {
+    let items = minute_data_items();
+    let mut indicator = Minimum::with_policy(LARGE_WINDOW, BucketPolicy::Fixed(MINUTE)).unwrap();
+
+    bench.iter(|| {
+        indicator.reset();
+        for (timestamp, item) in items.iter() {
+            black_box(indicator.next((*timestamp, item)));
+        }
+    })
+}
+
+fn max_drawdown_large_window(bench: &mut Bencher) {
+    let items = minute_data_items();
+    let mut indicator =
+        MaxDrawdown::with_policy(LARGE_WINDOW, BucketPolicy::Fixed(MINUTE)).unwrap();
+
+    bench.iter(|| {
+        indicator.reset();
+        for (timestamp, item) in items.iter() {
+            black_box(indicator.next((*timestamp, item)));
+        }
+    })
+}
+
+fn max_drawup_large_window(bench: &mut Bencher) {
+    let items = minute_data_items();
+    let mut indicator = MaxDrawup::with_policy(LARGE_WINDOW, BucketPolicy::Fixed(MINUTE)).unwrap();
+
+    bench.iter(|| {
+        indicator.reset();
+        for (timestamp, item) in items.iter() {
+            black_box(indicator.next((*timestamp, item)));
+        }
+    })
+}
+
+// A steady decline: the peak of the maximum drawdown is the oldest value of the window and
+// leaves it on every update.
+fn max_drawdown_declining(bench: &mut Bencher) {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let ticks: Vec<(DateTime<Utc>, f64)> = (0..ITEMS_COUNT)
+        .map(|i| {
+            (
+                start + chrono::Duration::minutes(i as i64),
+                (ITEMS_COUNT - i) as f64,
+            )
+        })
+        .collect();
+    let mut indicator =
+        MaxDrawdown::with_policy(LARGE_WINDOW, BucketPolicy::Fixed(MINUTE)).unwrap();
+
+    bench.iter(|| {
+        indicator.reset();
+        for tick in ticks.iter() {
+            black_box(indicator.next(*tick));
+        }
+    })
+}
+
+// Two rising ticks per one second bucket: every tick revises the newest value of a window
+// that only ever holds a single candidate.
+fn maximum_trending_revisions(bench: &mut Bencher) {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let ticks: Vec<(DateTime<Utc>, f64)> = (0..ITEMS_COUNT)
+        .map(|i| {
+            (
+                start + chrono::Duration::milliseconds(i as i64 * 500),
+                i as f64,
+            )
+        })
+        .collect();
+    let policy = BucketPolicy::Fixed(Duration::from_secs(1));
+    let mut indicator = Maximum::with_policy(LARGE_WINDOW * 30, policy).unwrap();
+
+    bench.iter(|| {
+        indicator.reset();
+        for tick in ticks.iter() {
+            black_box(indicator.next(*tick));
+        }
+    })
+}
+
+// Reference point: the previous implementation folded over the window on every update
+fn window_scan_large_window(bench: &mut Bencher) {
+    let items = minute_data_items();
+    let cutoff = chrono::Duration::from_std(LARGE_WINDOW).unwrap();
+    let mut window: VecDeque<(DateTime<Utc>, f64)> = VecDeque::new();
+
+    bench.iter(|| {
+        window.clear();
+        for (timestamp, item) in items.iter() {
+            while window
+                .front()
+                .is_some_and(|(time, _)| *time <= *timestamp - cutoff)
+            {
+                window.pop_front();
+            }
+            window.push_back((*timestamp, item.close()));
+            black_box(
+                window
+                    .iter()
+                    .map(|&(_, val)| val)
+                    .fold(f64::NEG_INFINITY, f64::max),
+            );
+        }
+    })
+}
+
+benchmark_group!(
+    windows,
+    maximum_large_window,
+    minimum_large_window,
+    max_drawdown_large_window,
+    max_drawup_large_window,
+    max_drawdown_declining,
+    maximum_trending_revisions,
+    window_scan_large_window
+);
+
+benchmark_main!(indicators, windows);
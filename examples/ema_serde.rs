@@ -0,0 +1,29 @@
+// Persists an EMA between two runs with serde and bincode.
+//
+// Run with: cargo run --example ema_serde --features serde
+use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+use std::time::Duration;
+use ta::indicators::ExponentialMovingAverage;
+use ta::state::Versioned;
+use ta::Next;
+
+fn main() {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let mut ema = ExponentialMovingAverage::new(Duration::from_secs(3 * 86400)).unwrap();
+    ema.next((start, 2.0));
+    ema.next((start + ChronoDuration::days(1), 5.0));
+
+    // The state carries the version of its layout, so a later release can migrate it
+    let bytes = bincode::serialize(&ema).unwrap();
+    println!(
+        "saved EMA state version {} in {} bytes",
+        ExponentialMovingAverage::VERSION,
+        bytes.len()
+    );
+
+    let mut restored: ExponentialMovingAverage = bincode::deserialize(&bytes).unwrap();
+    let next = (start + ChronoDuration::days(2), 1.0);
+    let value = restored.next(next);
+    assert_eq!(value, ema.next(next));
+    println!("restored {} continues at {}", restored, value);
+}
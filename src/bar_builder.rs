@@ -0,0 +1,479 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::{Result, TaError};
+use crate::helpers::format_duration;
+use crate::indicators::{AdaptiveTimeDetector, BucketPolicy};
+use crate::{DataItem, Next, Reset};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+enum BarKind {
+    Time {
+        duration: Duration,
+        detector: AdaptiveTimeDetector,
+    },
+    Ticks(usize),
+    Volume(f64),
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+struct PartialBar {
+    time: DateTime<Utc>,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    ticks: usize,
+}
+
+impl PartialBar {
+    fn new(time: DateTime<Utc>, price: f64, size: f64) -> Self {
+        Self {
+            time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: size,
+            ticks: 1,
+        }
+    }
+
+    fn add(&mut self, time: DateTime<Utc>, price: f64, size: f64) {
+        self.time = time;
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += size;
+        self.ticks += 1;
+    }
+
+    fn item(&self) -> (DateTime<Utc>, DataItem) {
+        let item = DataItem::builder()
+            .open(self.open)
+            .high(self.high)
+            .low(self.low)
+            .close(self.close)
+            .volume(self.volume)
+            .build()
+            .expect("bars of validated ticks are consistent");
+        (self.time, item)
+    }
+}
+
+/// Result of adding a tick to a [BarBuilder](struct.BarBuilder.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BarUpdate {
+    /// The bar completed by this tick, if any.
+    pub completed: Option<(DateTime<Utc>, DataItem)>,
+    /// The bar in progress after this tick, `None` right after a tick or volume bar filled up.
+    pub current: Option<(DateTime<Utc>, DataItem)>,
+}
+
+/// Aggregates `(price, size)` ticks into [DataItem](../struct.DataItem.html) bars.
+///
+/// Three kinds of bars are available:
+///
+/// * [time](#method.time) / [with_policy](#method.with_policy) - one bar per time bucket
+/// * [ticks](#method.ticks) - one bar per `count` ticks
+/// * [volume](#method.volume) - one bar per `threshold` of traded size
+///
+/// Time bars use the bucketing of [AdaptiveTimeDetector](indicators/struct.AdaptiveTimeDetector.html):
+/// built with the same duration and [BucketPolicy](indicators/enum.BucketPolicy.html) as an
+/// indicator, a bar covers exactly the updates that indicator would merge into one period. A
+/// time bar is completed by the first tick of the next bucket.
+///
+/// Tick and volume bars are completed by the tick that fills them. A tick is never split
+/// between bars, so a volume bar may end up above the threshold.
+///
+/// A tick with a price that is not finite or not positive, or a size that is not finite or
+/// negative, is rejected with [TaError::NotFinite](errors/enum.TaError.html) or
+/// [TaError::NotPositive](errors/enum.TaError.html) and leaves the bars as they were.
+///
+/// Bars are stamped with the time of their last tick. Feeding the in-progress bar to an
+/// indicator on every tick therefore revises its current period the same way feeding the ticks
+/// would.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::{BarBuilder, Close, High, Next, Volume};
+///
+/// let mut bars = BarBuilder::time(Duration::from_secs(60)).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+///
+/// bars.next((start, (100.0, 5.0))).unwrap();
+/// bars.next((start + ChronoDuration::seconds(20), (101.5, 2.0))).unwrap();
+/// let update = bars.next((start + ChronoDuration::seconds(60), (100.5, 1.0))).unwrap();
+///
+/// let (_, bar) = update.completed.unwrap();
+/// assert_eq!(bar.high(), 101.5);
+/// assert_eq!(bar.close(), 101.5);
+/// assert_eq!(bar.volume(), 7.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BarBuilder {
+    kind: BarKind,
+    bar: Option<PartialBar>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "BarBuilder")]
+struct StateV1 {
+    kind: BarKind,
+    bar: Option<PartialBar>,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(BarBuilder, StateV1, 1);
+
+impl BarBuilder {
+    /// Time bars of a fixed `width` aligned to the Unix epoch, e.g. 1 minute bars.
+    pub fn time(width: Duration) -> Result<Self> {
+        Self::with_policy(width, BucketPolicy::Fixed(width))
+    }
+
+    /// Time bars following the buckets of indicators built with the same `duration` and `policy`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
+            return Err(TaError::InvalidParameter);
+        }
+        Ok(Self {
+            kind: BarKind::Time {
+                duration,
+                detector: AdaptiveTimeDetector::with_policy(duration, policy)?,
+            },
+            bar: None,
+        })
+    }
+
+    /// Bars of `count` ticks each.
+    pub fn ticks(count: usize) -> Result<Self> {
+        if count == 0 {
+            return Err(TaError::InvalidParameter);
+        }
+        Ok(Self {
+            kind: BarKind::Ticks(count),
+            bar: None,
+        })
+    }
+
+    /// Bars of at least `threshold` traded size each.
+    pub fn volume(threshold: f64) -> Result<Self> {
+        if !(threshold > 0.0 && threshold.is_finite()) {
+            return Err(TaError::InvalidParameter);
+        }
+        Ok(Self {
+            kind: BarKind::Volume(threshold),
+            bar: None,
+        })
+    }
+
+    /// The bar in progress, if any.
+    pub fn current(&self) -> Option<(DateTime<Utc>, DataItem)> {
+        self.bar.as_ref().map(PartialBar::item)
+    }
+
+    /// Completes the bar in progress, e.g. at the end of a session.
+    pub fn flush(&mut self) -> Option<(DateTime<Utc>, DataItem)> {
+        self.bar.take().map(|bar| bar.item())
+    }
+}
+
+impl BarBuilder {
+    fn check(&self, timestamp: DateTime<Utc>, price: f64, size: f64) -> Result<()> {
+        for value in [price, size] {
+            if !value.is_finite() {
+                return Err(TaError::NotFinite {
+                    indicator: self.to_string(),
+                    timestamp,
+                    value,
+                });
+            }
+        }
+        if price <= 0.0 || size < 0.0 {
+            return Err(TaError::NotPositive {
+                indicator: self.to_string(),
+                timestamp,
+                value: if price <= 0.0 { price } else { size },
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Next<(f64, f64)> for BarBuilder {
+    type Output = Result<BarUpdate>;
+
+    fn next(&mut self, (timestamp, (price, size)): (DateTime<Utc>, (f64, f64))) -> Self::Output {
+        self.check(timestamp, price, size)?;
+
+        let starts_bar = match &mut self.kind {
+            BarKind::Time { detector, .. } => !detector.should_replace(timestamp),
+            BarKind::Ticks(_) | BarKind::Volume(_) => false,
+        };
+        let mut completed = if starts_bar { self.flush() } else { None };
+
+        let bar = match &mut self.bar {
+            Some(bar) => {
+                bar.add(timestamp, price, size);
+                bar
+            }
+            None => self.bar.insert(PartialBar::new(timestamp, price, size)),
+        };
+
+        let is_full = match self.kind {
+            BarKind::Time { .. } => false,
+            BarKind::Ticks(count) => bar.ticks >= count,
+            BarKind::Volume(threshold) => bar.volume >= threshold,
+        };
+        if is_full {
+            completed = self.flush();
+        }
+
+        Ok(BarUpdate {
+            completed,
+            current: self.current(),
+        })
+    }
+}
+
+impl Reset for BarBuilder {
+    fn reset(&mut self) {
+        self.bar = None;
+        if let BarKind::Time { detector, .. } = &mut self.kind {
+            detector.reset();
+        }
+    }
+}
+
+impl Default for BarBuilder {
+    fn default() -> Self {
+        Self::time(Duration::from_secs(60)).unwrap()
+    }
+}
+
+impl fmt::Display for BarBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            BarKind::Time { duration, .. } => {
+                write!(f, "TIME_BARS({})", format_duration(*duration))
+            }
+            BarKind::Ticks(count) => write!(f, "TICK_BARS({})", count),
+            BarKind::Volume(threshold) => write!(f, "VOLUME_BARS({})", threshold),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::SimpleMovingAverage;
+    use crate::{Close, High, Low, Open, Volume};
+    use chrono::TimeZone;
+
+    fn ohlcv(item: &DataItem) -> (f64, f64, f64, f64, f64) {
+        (
+            item.open(),
+            item.high(),
+            item.low(),
+            item.close(),
+            item.volume(),
+        )
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(BarBuilder::time(Duration::from_secs(0)).is_err());
+        assert!(BarBuilder::time(Duration::from_millis(500)).is_err());
+        assert!(BarBuilder::time(Duration::from_secs(60)).is_ok());
+        assert!(BarBuilder::ticks(0).is_err());
+        assert!(BarBuilder::ticks(100).is_ok());
+        assert!(BarBuilder::volume(0.0).is_err());
+        assert!(BarBuilder::volume(f64::NAN).is_err());
+        assert!(BarBuilder::volume(1000.0).is_ok());
+    }
+
+    #[test]
+    fn test_time_bars() {
+        let mut bars = BarBuilder::time(Duration::from_secs(60)).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+
+        let update = bars.next((at(5), (10.0, 1.0))).unwrap();
+        assert_eq!(update.completed, None);
+        let (time, bar) = update.current.unwrap();
+        assert_eq!((time, ohlcv(&bar)), (at(5), (10.0, 10.0, 10.0, 10.0, 1.0)));
+
+        bars.next((at(10), (12.0, 2.0))).unwrap();
+        let update = bars.next((at(59), (9.0, 3.0))).unwrap();
+        assert_eq!(update.completed, None);
+        let (time, bar) = update.current.unwrap();
+        assert_eq!((time, ohlcv(&bar)), (at(59), (10.0, 12.0, 9.0, 9.0, 6.0)));
+
+        // The first tick of the next minute completes the bar
+        let update = bars.next((at(61), (11.0, 4.0))).unwrap();
+        let (time, bar) = update.completed.unwrap();
+        assert_eq!((time, ohlcv(&bar)), (at(59), (10.0, 12.0, 9.0, 9.0, 6.0)));
+        let (time, bar) = update.current.unwrap();
+        assert_eq!((time, ohlcv(&bar)), (at(61), (11.0, 11.0, 11.0, 11.0, 4.0)));
+
+        // Empty minutes produce no bars
+        let update = bars.next((at(300), (11.5, 1.0))).unwrap();
+        assert_eq!(ohlcv(&update.completed.unwrap().1).4, 4.0);
+    }
+
+    #[test]
+    fn test_tick_bars() {
+        let mut bars = BarBuilder::ticks(3).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+
+        assert_eq!(bars.next((at(0), (10.0, 1.0))).unwrap().completed, None);
+        assert_eq!(bars.next((at(0), (11.0, 1.0))).unwrap().completed, None);
+
+        let update = bars.next((at(1), (10.5, 1.0))).unwrap();
+        let (time, bar) = update.completed.unwrap();
+        assert_eq!((time, ohlcv(&bar)), (at(1), (10.0, 11.0, 10.0, 10.5, 3.0)));
+        assert_eq!(update.current, None);
+
+        let update = bars.next((at(2), (10.0, 1.0))).unwrap();
+        assert_eq!(update.completed, None);
+        assert_eq!(ohlcv(&update.current.unwrap().1).0, 10.0);
+    }
+
+    #[test]
+    fn test_volume_bars() {
+        let mut bars = BarBuilder::volume(100.0).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+
+        assert_eq!(bars.next((at(0), (10.0, 40.0))).unwrap().completed, None);
+        assert_eq!(bars.next((at(1), (10.2, 50.0))).unwrap().completed, None);
+
+        // The tick crossing the threshold stays whole in the bar it completes
+        let update = bars.next((at(2), (10.1, 30.0))).unwrap();
+        let (time, bar) = update.completed.unwrap();
+        assert_eq!(
+            (time, ohlcv(&bar)),
+            (at(2), (10.0, 10.2, 10.0, 10.1, 120.0))
+        );
+        assert_eq!(update.current, None);
+        assert_eq!(bars.current(), None);
+    }
+
+    #[test]
+    fn test_invalid_ticks() {
+        let mut bars = BarBuilder::volume(100.0).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+
+        bars.next((start, (10.0, 40.0))).unwrap();
+        assert!(matches!(
+            bars.next((start, (f64::NAN, 1.0))),
+            Err(TaError::NotFinite { .. })
+        ));
+        assert!(matches!(
+            bars.next((start, (10.0, f64::INFINITY))),
+            Err(TaError::NotFinite { .. })
+        ));
+        assert!(matches!(
+            bars.next((start, (-1.0, 1.0))),
+            Err(TaError::NotPositive { value, .. }) if value == -1.0
+        ));
+        assert!(matches!(
+            bars.next((start, (10.0, -70.0))),
+            Err(TaError::NotPositive { value, .. }) if value == -70.0
+        ));
+
+        // Rejected ticks leave the bar as it was
+        assert_eq!(
+            ohlcv(&bars.current().unwrap().1),
+            (10.0, 10.0, 10.0, 10.0, 40.0)
+        );
+        assert!(bars
+            .next((start, (10.0, 60.0)))
+            .unwrap()
+            .completed
+            .is_some());
+    }
+
+    #[test]
+    fn test_bars_line_up_with_indicator_buckets() {
+        let day = Duration::from_secs(86400);
+        let policy = BucketPolicy::Heuristic;
+        let mut bars = BarBuilder::with_policy(day * 5, policy.clone()).unwrap();
+        let mut from_ticks = SimpleMovingAverage::with_policy(day * 5, policy.clone()).unwrap();
+        let mut from_bars = SimpleMovingAverage::with_policy(day * 5, policy).unwrap();
+        let open = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+
+        let mut completed = 0;
+        for session in 0..4 {
+            for minute in 0..390 {
+                let time =
+                    open + chrono::Duration::days(session) + chrono::Duration::minutes(minute);
+                let price = 100.0 + ((minute * 37 + session * 11) % 17) as f64;
+
+                let update = bars.next((time, (price, 1.0))).unwrap();
+                completed += update.completed.iter().count();
+                let (bar_time, bar) = update.current.unwrap();
+
+                assert_eq!(
+                    from_bars.next((bar_time, &bar)),
+                    from_ticks.next((time, price))
+                );
+            }
+        }
+
+        // Two slots per session with the 3.4 hour gap rule
+        assert_eq!(completed, 7);
+    }
+
+    #[test]
+    fn test_flush_and_reset() {
+        let mut bars = BarBuilder::time(Duration::from_secs(60)).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+
+        bars.next((start, (10.0, 1.0))).unwrap();
+        assert_eq!(ohlcv(&bars.flush().unwrap().1).3, 10.0);
+        assert_eq!(bars.flush(), None);
+
+        bars.next((start, (10.0, 1.0))).unwrap();
+        bars.reset();
+        assert_eq!(bars.current(), None);
+        let update = bars
+            .next((start + chrono::Duration::seconds(1), (12.0, 1.0)))
+            .unwrap();
+        assert_eq!(update.completed, None);
+    }
+
+    #[test]
+    fn test_default() {
+        BarBuilder::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let day = Duration::from_secs(86400);
+        assert_eq!(
+            format!("{}", BarBuilder::time(day).unwrap()),
+            "TIME_BARS(1 days)"
+        );
+        assert_eq!(
+            format!("{}", BarBuilder::ticks(500).unwrap()),
+            "TICK_BARS(500)"
+        );
+        assert_eq!(
+            format!("{}", BarBuilder::volume(2500.0).unwrap()),
+            "VOLUME_BARS(2500)"
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use crate::Next;
+use chrono::{DateTime, Utc};
+
+/// Feeds a whole series to an indicator, e.g. for a backtest, instead of calling
+/// [next](trait.Next.html#tymethod.next) in a loop. Implemented for every [Next].
+///
+/// The inputs can be a slice or any iterator, of pairs or of references to pairs. Values like
+/// `f64` are fed with [batch](#method.batch) and [outputs](#method.outputs), bars like
+/// [DataItem](struct.DataItem.html) are fed by reference with [batch_bars](#method.batch_bars) and
+/// [bar_outputs](#method.bar_outputs), so an iterator of owned bars does not need to be collected
+/// first.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::{ExponentialMovingAverage, Maximum};
+/// use ta::{Batch, DataItem};
+///
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+/// let day = |n| start + ChronoDuration::days(n);
+/// let prices = [(day(0), 2.0), (day(1), 5.0), (day(2), 1.0), (day(3), 6.25)];
+///
+/// let mut ema = ExponentialMovingAverage::new(Duration::from_secs(3 * 86400)).unwrap();
+/// assert_eq!(ema.batch(&prices), vec![2.0, 3.5, 2.25, 4.25]);
+///
+/// let bars = (0..3).map(|n| {
+///     let close = 10.0 + n as f64;
+///     let bar = DataItem::builder()
+///         .open(close)
+///         .high(close)
+///         .low(close)
+///         .close(close)
+///         .volume(100.0)
+///         .build()
+///         .unwrap();
+///     (day(n), bar)
+/// });
+/// let mut max = Maximum::new(Duration::from_secs(2 * 86400)).unwrap();
+/// let highest: Vec<f64> = max.bar_outputs(bars).skip(1).collect();
+/// assert_eq!(highest, vec![11.0, 12.0]);
+/// ```
+pub trait Batch: Sized {
+    /// Feeds `inputs` in order and returns the output of each.
+    fn batch<T, I>(&mut self, inputs: I) -> Vec<<Self as Next<T>>::Output>
+    where
+        Self: Next<T>,
+        T: Clone,
+        I: IntoIterator,
+        I::Item: Borrow<(DateTime<Utc>, T)>,
+    {
+        self.outputs(inputs).collect()
+    }
+
+    /// Like [batch](#method.batch), with the bars fed by reference.
+    fn batch_bars<B, O, I>(&mut self, inputs: I) -> Vec<O>
+    where
+        Self: for<'b> Next<&'b B, Output = O>,
+        I: IntoIterator,
+        I::Item: Borrow<(DateTime<Utc>, B)>,
+    {
+        self.bar_outputs(inputs).collect()
+    }
+
+    /// Lazily feeds `inputs`: each output is computed when the iterator is advanced.
+    fn outputs<T, I>(&mut self, inputs: I) -> Outputs<'_, Self, I::IntoIter, T>
+    where
+        Self: Next<T>,
+        T: Clone,
+        I: IntoIterator,
+        I::Item: Borrow<(DateTime<Utc>, T)>,
+    {
+        Outputs {
+            indicator: self,
+            inputs: inputs.into_iter(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Like [outputs](#method.outputs), with the bars fed by reference.
+    fn bar_outputs<B, O, I>(&mut self, inputs: I) -> BarOutputs<'_, Self, I::IntoIter, B>
+    where
+        Self: for<'b> Next<&'b B, Output = O>,
+        I: IntoIterator,
+        I::Item: Borrow<(DateTime<Utc>, B)>,
+    {
+        BarOutputs {
+            indicator: self,
+            inputs: inputs.into_iter(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<N> Batch for N {}
+
+/// Iterator over the outputs of an indicator fed with values, see [Batch::outputs].
+#[derive(Debug)]
+pub struct Outputs<'a, N, I, T> {
+    indicator: &'a mut N,
+    inputs: I,
+    marker: PhantomData<T>,
+}
+
+impl<N, I, T> Iterator for Outputs<'_, N, I, T>
+where
+    N: Next<T>,
+    T: Clone,
+    I: Iterator,
+    I::Item: Borrow<(DateTime<Utc>, T)>,
+{
+    type Item = N::Output;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.inputs.next()?;
+        let (timestamp, value) = input.borrow();
+        Some(self.indicator.next((*timestamp, value.clone())))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inputs.size_hint()
+    }
+}
+
+/// Iterator over the outputs of an indicator fed with bars, see [Batch::bar_outputs].
+#[derive(Debug)]
+pub struct BarOutputs<'a, N, I, B> {
+    indicator: &'a mut N,
+    inputs: I,
+    marker: PhantomData<B>,
+}
+
+impl<N, O, I, B> Iterator for BarOutputs<'_, N, I, B>
+where
+    N: for<'b> Next<&'b B, Output = O>,
+    I: Iterator,
+    I::Item: Borrow<(DateTime<Utc>, B)>,
+{
+    type Item = O;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.inputs.next()?;
+        let (timestamp, bar) = input.borrow();
+        Some(self.indicator.next((*timestamp, bar)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inputs.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::{
+        MovingAverageConvergenceDivergence as Macd, RelativeStrengthIndex, SimpleMovingAverage,
+        TrueRange,
+    };
+    use crate::test_helper::*;
+    use crate::{Ordered, OutOfOrderPolicy, Peek, Reset, WarmUp};
+
+    fn prices() -> Vec<(DateTime<Utc>, f64)> {
+        [10.0, 11.0, 10.5, 11.5, 11.0]
+            .iter()
+            .enumerate()
+            .map(|(n, &price)| (day(n as i64), price))
+            .collect()
+    }
+
+    #[test]
+    fn test_batch() {
+        let mut rsi = RelativeStrengthIndex::wilder(DAY * 3).unwrap();
+        let mut one_by_one = rsi.clone();
+
+        let inputs = prices();
+        let expected: Vec<_> = inputs.iter().map(|&input| one_by_one.next(input)).collect();
+        assert_eq!(rsi.batch(&inputs), expected);
+        assert_eq!(rsi.current(), one_by_one.current());
+
+        rsi.reset();
+        assert_eq!(rsi.batch(inputs), expected);
+    }
+
+    #[test]
+    fn test_batch_bars() {
+        let mut tr = TrueRange::new(DAY).unwrap();
+        let bars = vec![
+            (day(0), Bar::new().high(10).low(7.5).close(9)),
+            (day(1), Bar::new().high(11).low(9).close(9.5)),
+            (day(2), Bar::new().high(9).low(5).close(8)),
+        ];
+
+        assert_eq!(tr.batch_bars(&bars), vec![2.5, 2.0, 4.5]);
+        tr.reset();
+        assert_eq!(tr.batch_bars(bars), vec![2.5, 2.0, 4.5]);
+    }
+
+    #[test]
+    fn test_outputs() {
+        let mut sma = SimpleMovingAverage::new(DAY * 2).unwrap();
+
+        let mut outputs = sma.outputs(prices());
+        assert_eq!(outputs.size_hint(), (5, Some(5)));
+        assert_eq!(outputs.next(), Some(10.0));
+        assert_eq!(outputs.next(), Some(10.5));
+        // Nothing else is fed until the iterator is advanced
+        assert_eq!(sma.sample_count(), 2);
+
+        let rest: Vec<_> = sma.outputs(&prices()[2..]).collect();
+        assert_eq!(rest, vec![10.75, 11.0, 11.25]);
+    }
+
+    #[test]
+    fn test_wrappers() {
+        let mut macd = WarmUp::new(Macd::new(DAY, DAY * 2, DAY).unwrap());
+        let outputs = macd.batch(prices());
+        assert!(outputs[0].is_none());
+        assert!(outputs[4].is_some());
+
+        let mut sma = Ordered::new(
+            SimpleMovingAverage::new(DAY * 2).unwrap(),
+            Default::default(),
+        );
+        let late = [(day(1), 2.0), (day(0), 4.0), (day(2), 4.0)];
+        let outputs: Vec<_> = sma.outputs(late).map(|output| output.is_ok()).collect();
+        assert_eq!(outputs, vec![true, false, true]);
+        assert_eq!(sma.policy(), OutOfOrderPolicy::Reject);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::errors::*;
+use crate::{Close, High, Low, Open, Volume};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -19,6 +20,36 @@ impl DataItem {
     }
 }
 
+impl Open for DataItem {
+    fn open(&self) -> f64 {
+        self.open
+    }
+}
+
+impl High for DataItem {
+    fn high(&self) -> f64 {
+        self.high
+    }
+}
+
+impl Low for DataItem {
+    fn low(&self) -> f64 {
+        self.low
+    }
+}
+
+impl Close for DataItem {
+    fn close(&self) -> f64 {
+        self.close
+    }
+}
+
+impl Volume for DataItem {
+    fn volume(&self) -> f64 {
+        self.volume
+    }
+}
+
 pub struct DataItemBuilder {
     open: Option<f64>,
     high: Option<f64>,
@@ -147,4 +178,22 @@ mod tests {
             assert_invalid(record)
         }
     }
+
+    #[test]
+    fn test_accessors() {
+        let item = DataItem::builder()
+            .open(20.0)
+            .high(25.0)
+            .low(15.0)
+            .close(21.0)
+            .volume(7500.0)
+            .build()
+            .unwrap();
+
+        assert_eq!(item.open(), 20.0);
+        assert_eq!(item.high(), 25.0);
+        assert_eq!(item.low(), 15.0);
+        assert_eq!(item.close(), 21.0);
+        assert_eq!(item.volume(), 7500.0);
+    }
 }
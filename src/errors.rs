@@ -1,21 +1,90 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
+use chrono::{DateTime, Utc};
+
 pub type Result<T> = std::result::Result<T, TaError>;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
 pub enum TaError {
     InvalidParameter,
     DataItemIncomplete,
     DataItemInvalid,
+    /// An input that is NaN or infinite.
+    NotFinite {
+        indicator: String,
+        timestamp: DateTime<Utc>,
+        value: f64,
+    },
+    /// A price that is zero or negative, fed to an indicator taking ratios of prices or to a
+    /// [BarBuilder](../struct.BarBuilder.html), or a negative tick size.
+    NotPositive {
+        indicator: String,
+        timestamp: DateTime<Utc>,
+        value: f64,
+    },
+    /// An update older than the latest one fed to the indicator.
+    OutOfOrder {
+        indicator: String,
+        timestamp: DateTime<Utc>,
+        latest: DateTime<Utc>,
+    },
+    /// A session calendar with an invalid line, or without a required key (`line` is `None`).
+    InvalidCalendar {
+        line: Option<usize>,
+        key: String,
+    },
+    /// A file that could not be read, e.g. a missing session calendar, with the kind and the
+    /// message of the I/O error.
+    Io {
+        path: String,
+        kind: std::io::ErrorKind,
+        message: String,
+    },
 }
 
 impl Display for TaError {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        match *self {
+        match self {
             TaError::InvalidParameter => write!(f, "invalid parameter"),
             TaError::DataItemIncomplete => write!(f, "data item is incomplete"),
             TaError::DataItemInvalid => write!(f, "data item is invalid"),
+            TaError::NotFinite {
+                indicator,
+                timestamp,
+                value,
+            } => write!(
+                f,
+                "{}: input {} at {} is not finite",
+                indicator, value, timestamp
+            ),
+            TaError::NotPositive {
+                indicator,
+                timestamp,
+                value,
+            } => write!(
+                f,
+                "{}: input {} at {} is not positive",
+                indicator, value, timestamp
+            ),
+            TaError::OutOfOrder {
+                indicator,
+                timestamp,
+                latest,
+            } => write!(
+                f,
+                "{}: update at {} is older than the latest one at {}",
+                indicator, timestamp, latest
+            ),
+            TaError::InvalidCalendar {
+                line: Some(line),
+                key,
+            } => write!(f, "session calendar: invalid `{}` on line {}", key, line),
+            TaError::InvalidCalendar { line: None, key } => {
+                write!(f, "session calendar: missing `{}`", key)
+            }
+            TaError::Io { path, message, .. } => write!(f, "cannot read {}: {}", path, message),
         }
     }
 }
@@ -26,6 +95,11 @@ impl Error for TaError {
             TaError::InvalidParameter => None,
             TaError::DataItemIncomplete => None,
             TaError::DataItemInvalid => None,
+            TaError::NotFinite { .. } => None,
+            TaError::NotPositive { .. } => None,
+            TaError::OutOfOrder { .. } => None,
+            TaError::InvalidCalendar { .. } => None,
+            TaError::Io { .. } => None,
         }
     }
 }
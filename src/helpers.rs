@@ -1,8 +1,170 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// Returns the largest of 3 given numbers.
 pub fn max3(a: f64, b: f64, c: f64) -> f64 {
     a.max(b).max(c)
 }
 
+/// Formats a window duration the way indicators display it: whole days as
+/// `N days`, anything else in seconds.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs > 0 && secs % 86400 == 0 {
+        format!("{} days", secs / 86400)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Returns 0 for a `deviation` of `len` values within the rounding of their sum, where
+/// `scale` is the magnitude of the values: summing rounds the mean of equal values by up to
+/// `len` units in the last place.
+pub fn round_deviation(deviation: f64, len: usize, scale: f64) -> f64 {
+    if deviation <= len as f64 * f64::EPSILON * scale.abs() {
+        0.0
+    } else {
+        deviation
+    }
+}
+
+/// Which extreme a [MonotonicDeque] keeps track of.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Extreme {
+    Max,
+    Min,
+}
+
+impl Extreme {
+    /// Whether `a` makes `b` useless as a future extreme when `a` is newer.
+    pub fn dominates(self, a: f64, b: f64) -> bool {
+        match self {
+            Extreme::Max => a >= b,
+            Extreme::Min => a <= b,
+        }
+    }
+}
+
+/// Sliding window extreme in amortised O(1).
+///
+/// Mirrors a window `VecDeque` owned by the indicator and keeps only the values that can still
+/// become the extreme: every value is newer and strictly less extreme than the one before it,
+/// so the extreme of the window is always at the front.
+///
+/// Values are identified by their absolute position in the stream, so the deque needs to be told
+/// about every change of the window: [push](#method.push) after `push_back`,
+/// [evict](#method.evict) after `pop_front` and [pop_back](#method.pop_back) after `pop_back`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct MonotonicDeque {
+    extreme: Extreme,
+    deque: VecDeque<(u64, f64)>,
+    next_index: u64,
+    // The candidates the last push removed, newest first, and the position of the pushed value.
+    // Not serialized, to keep the layout of version 1: a restored deque rescans the window on
+    // its first pop_back instead.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pushed_out: Vec<(u64, f64)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pushed_out_by: Option<u64>,
+}
+
+impl MonotonicDeque {
+    pub fn new(extreme: Extreme) -> Self {
+        Self {
+            extreme,
+            deque: VecDeque::new(),
+            next_index: 0,
+            pushed_out: Vec::new(),
+            pushed_out_by: None,
+        }
+    }
+
+    /// The extreme of the window, `None` while it is empty.
+    pub fn value(&self) -> Option<f64> {
+        self.deque.front().map(|&(_, value)| value)
+    }
+
+    /// Adds the value that was pushed to the back of the window.
+    pub fn push(&mut self, value: f64) {
+        self.pushed_out.clear();
+        while let Some(&(index, last)) = self.deque.back() {
+            if !self.extreme.dominates(value, last) {
+                break;
+            }
+            self.deque.pop_back();
+            self.pushed_out.push((index, last));
+        }
+        self.pushed_out_by = Some(self.next_index);
+        self.deque.push_back((self.next_index, value));
+        self.next_index += 1;
+    }
+
+    /// Drops the values that are no longer part of a window of `window_len` values.
+    pub fn evict(&mut self, window_len: usize) {
+        let first_index = self.next_index - window_len as u64;
+        while self.deque.front().is_some_and(|&(i, _)| i < first_index) {
+            self.deque.pop_front();
+        }
+    }
+
+    /// Removes the newest value after it was popped from the back of `window`.
+    ///
+    /// The values the removed one had pushed out are put back from what [push](#method.push)
+    /// kept aside, which costs as many steps as there are such values. Only the newest value
+    /// keeps them; popping a value before that one rescans the window back to the last
+    /// remaining candidate instead.
+    pub fn pop_back(&mut self, window: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.next_index -= 1;
+        // The newest value is always the last candidate
+        if self
+            .deque
+            .back()
+            .is_some_and(|&(i, _)| i == self.next_index)
+        {
+            self.deque.pop_back();
+        }
+
+        let first_index = self.next_index - window.len() as u64;
+        if self.pushed_out_by.take() == Some(self.next_index) {
+            // Values evicted from the front since are no longer part of the window
+            let restored = self.pushed_out.drain(..).rev();
+            self.deque
+                .extend(restored.filter(|&(index, _)| index >= first_index));
+            return;
+        }
+
+        let restore_from = self.deque.back().map_or(first_index, |&(i, _)| i + 1);
+
+        let mut restored = Vec::new();
+        for (offset, &(_, value)) in window.iter().enumerate().rev() {
+            let index = first_index + offset as u64;
+            if index < restore_from {
+                break;
+            }
+            if restored
+                .last()
+                .map_or(true, |&(_, newer)| !self.extreme.dominates(newer, value))
+            {
+                restored.push((index, value));
+            }
+        }
+        self.deque.extend(restored.into_iter().rev());
+    }
+
+    pub fn clear(&mut self) {
+        self.deque.clear();
+        self.next_index = 0;
+        self.pushed_out.clear();
+        self.pushed_out_by = None;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -13,4 +175,111 @@ mod tests {
         assert_eq!(max3(2.0, 3.0, 1.0), 3.0);
         assert_eq!(max3(2.0, 1.0, 3.0), 3.0);
     }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(7 * 86400)), "7 days");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_secs(86400 + 1)), "86401s");
+    }
+
+    #[test]
+    fn test_round_deviation() {
+        let mean = [0.1, 0.1, 0.1].iter().sum::<f64>() / 3.0;
+        assert_ne!(mean, 0.1);
+        assert_eq!(round_deviation((0.1 - mean).abs(), 3, mean), 0.0);
+        assert_eq!(round_deviation(1e-9, 3, mean), 1e-9);
+    }
+
+    // Pushes to both a plain window and the deque, like an indicator does
+    fn push(window: &mut VecDeque<(DateTime<Utc>, f64)>, deque: &mut MonotonicDeque, value: f64) {
+        window.push_back((Utc::now(), value));
+        deque.push(value);
+    }
+
+    #[test]
+    fn test_monotonic_deque_sliding() {
+        let mut window = VecDeque::new();
+        let mut max = MonotonicDeque::new(Extreme::Max);
+
+        for value in [4.0, 1.2, 5.0, 3.0, 4.0, 0.0] {
+            push(&mut window, &mut max, value);
+            if window.len() > 3 {
+                window.pop_front();
+                max.evict(window.len());
+            }
+        }
+        // Window: 3.0, 4.0, 0.0
+        assert_eq!(max.value(), Some(4.0));
+
+        window.pop_front();
+        max.evict(window.len());
+        window.pop_front();
+        max.evict(window.len());
+        assert_eq!(max.value(), Some(0.0));
+    }
+
+    #[test]
+    fn test_monotonic_deque_pop_back_restores() {
+        let mut window = VecDeque::new();
+        let mut min = MonotonicDeque::new(Extreme::Min);
+
+        for value in [5.0, 3.0, 4.0, 6.0] {
+            push(&mut window, &mut min, value);
+        }
+        // A new low pushes out 3.0, 4.0 and 6.0 ...
+        push(&mut window, &mut min, 1.0);
+        assert_eq!(min.value(), Some(1.0));
+
+        // ... which are back once it gets replaced
+        window.pop_back();
+        min.pop_back(&window);
+        assert_eq!(min.value(), Some(3.0));
+        push(&mut window, &mut min, 7.0);
+
+        window.pop_front();
+        min.evict(window.len());
+        window.pop_front();
+        min.evict(window.len());
+        assert_eq!(min.value(), Some(4.0));
+
+        // Replacing the only candidate left restores the whole window
+        window.clear();
+        min.clear();
+        push(&mut window, &mut min, 2.0);
+        push(&mut window, &mut min, 3.0);
+        push(&mut window, &mut min, 1.0);
+        window.pop_back();
+        min.pop_back(&window);
+        assert_eq!(min.value(), Some(2.0));
+        window.pop_front();
+        min.evict(window.len());
+        assert_eq!(min.value(), Some(3.0));
+    }
+
+    #[test]
+    fn test_monotonic_deque_pop_back_after_evict() {
+        let mut window = VecDeque::new();
+        let mut max = MonotonicDeque::new(Extreme::Max);
+
+        for value in [5.0, 4.0, 3.0] {
+            push(&mut window, &mut max, value);
+        }
+        push(&mut window, &mut max, 6.0);
+        window.pop_front();
+        max.evict(window.len());
+
+        // 5.0 left the window in the meantime and stays out
+        window.pop_back();
+        max.pop_back(&window);
+        assert_eq!(max.value(), Some(4.0));
+
+        // Popping again goes past what the last push kept aside
+        window.pop_back();
+        max.pop_back(&window);
+        assert_eq!(max.value(), Some(4.0));
+        window.pop_front();
+        max.evict(window.len());
+        assert_eq!(max.value(), None);
+    }
 }
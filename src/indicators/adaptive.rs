@@ -1,6 +1,10 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Utc};
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::errors::{Result, TaError};
+use crate::SessionCalendar;
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -13,7 +17,48 @@ pub enum DetectedFrequency {
     /// Daily mode: maintains 3.4 hour gap between points
     DailyOHLC,
     /// Intraday mode: minute-level bucketing
+    ///
+    /// Also used for fixed-width buckets of any length, aligned to the Unix epoch.
     Intraday(Duration),
+    /// One bucket per UTC calendar day
+    CalendarDay,
+    /// Like `DailyOHLC` with a custom gap: points closer than the gap to the start of the
+    /// current slot replace it
+    Gap(Duration),
+    /// Every point is a new slot
+    EveryUpdate,
+    /// One slot per session of an exchange calendar
+    Session(Arc<SessionCalendar>),
+}
+
+/// How an indicator groups its updates into time buckets.
+///
+/// Updates that fall into the same bucket as the previous one replace it instead of adding a
+/// new period to the indicator.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum BucketPolicy {
+    /// Picks the bucketing from the indicator duration, see
+    /// [AdaptiveTimeDetector::new](struct.AdaptiveTimeDetector.html#method.new).
+    #[default]
+    Heuristic,
+    /// Fixed-width buckets aligned to the Unix epoch, e.g. 4 hour bars.
+    /// The width must be a whole number of seconds.
+    Fixed(Duration),
+    /// One bucket per UTC calendar day, e.g. for markets trading around the clock.
+    CalendarDay,
+    /// A new bucket starts once an update is at least this far from the start of the current
+    /// one, e.g. for sessions that run overnight. The heuristic uses 3.4 hours for daily windows.
+    GapThreshold(Duration),
+    /// Never replace: every update is a new period.
+    NeverReplace,
+    /// One bucket per trading session of the calendar. Updates outside of the sessions revise
+    /// the session that closed last, see
+    /// [SessionCalendar::trading_day](../struct.SessionCalendar.html#method.trading_day).
+    ///
+    /// The calendar is shared: cloning the policy for every indicator of a strategy, and the
+    /// detectors and windows built from it, does not copy it.
+    Session(Arc<SessionCalendar>),
 }
 
 /// Handles time-based de-duplication logic for indicators
@@ -26,12 +71,66 @@ pub enum DetectedFrequency {
 /// - Half-day sessions (~3.5 hours): Captures both open and close as separate points
 /// - Full-day sessions (~6.5 hours): Captures morning and afternoon as separate points
 /// - Minutely updates during market hours: Continuously updates the current slot
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct AdaptiveTimeDetector {
     frequency: DetectedFrequency,
     last_minute_bucket: i64,
     last_timestamp: Option<DateTime<Utc>>,
+    // Since the last reset: the first and latest timestamps and the number of buckets
+    first_seen: Option<DateTime<Utc>>,
+    latest_seen: Option<DateTime<Utc>>,
+    buckets: usize,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "AdaptiveTimeDetector")]
+struct StateV1 {
+    frequency: DetectedFrequency,
+    last_minute_bucket: i64,
+    last_timestamp: Option<DateTime<Utc>>,
+    first_seen: Option<DateTime<Utc>>,
+    latest_seen: Option<DateTime<Utc>>,
+    buckets: usize,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(AdaptiveTimeDetector, StateV1, 1, 0 => LegacyDetector);
+
+/// The detector as ta 0.5 serialized it, also nested in the indicators of that version
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+pub(crate) struct LegacyDetector {
+    frequency: DetectedFrequency,
+    last_minute_bucket: i64,
+    last_timestamp: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "serde")]
+impl LegacyDetector {
+    /// The detector with the bucketing of the legacy one, having seen `history` since the last
+    /// reset. The legacy layout did not keep it, indicators pass the times of their window.
+    pub(crate) fn restore(
+        self,
+        history: impl IntoIterator<Item = DateTime<Utc>>,
+    ) -> AdaptiveTimeDetector {
+        let mut detector = AdaptiveTimeDetector::with_frequency(DetectedFrequency::EveryUpdate);
+        for timestamp in history {
+            detector.should_replace(timestamp);
+        }
+        detector.frequency = self.frequency;
+        detector.last_minute_bucket = self.last_minute_bucket;
+        detector.last_timestamp = self.last_timestamp;
+        detector
+    }
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<AdaptiveTimeDetector> for LegacyDetector {
+    fn migrate(self) -> Result<AdaptiveTimeDetector> {
+        let last_timestamp = self.last_timestamp;
+        Ok(self.restore(last_timestamp))
+    }
 }
 
 impl AdaptiveTimeDetector {
@@ -54,10 +153,44 @@ impl AdaptiveTimeDetector {
             DetectedFrequency::DailyOHLC
         };
 
+        Self::with_frequency(frequency)
+    }
+
+    /// Create a new detector that buckets updates according to `policy`
+    ///
+    /// Returns `TaError::InvalidParameter` for a `Fixed` width that is not a positive whole
+    /// number of seconds or a zero `GapThreshold`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        let frequency = match policy {
+            BucketPolicy::Heuristic => return Ok(Self::new(duration)),
+            BucketPolicy::Fixed(width) => {
+                if width.as_secs() == 0 || width.subsec_nanos() != 0 {
+                    return Err(TaError::InvalidParameter);
+                }
+                DetectedFrequency::Intraday(width)
+            }
+            BucketPolicy::CalendarDay => DetectedFrequency::CalendarDay,
+            BucketPolicy::GapThreshold(gap) => {
+                if gap.is_zero() || chrono::Duration::from_std(gap).is_err() {
+                    return Err(TaError::InvalidParameter);
+                }
+                DetectedFrequency::Gap(gap)
+            }
+            BucketPolicy::NeverReplace => DetectedFrequency::EveryUpdate,
+            BucketPolicy::Session(calendar) => DetectedFrequency::Session(calendar),
+        };
+
+        Ok(Self::with_frequency(frequency))
+    }
+
+    fn with_frequency(frequency: DetectedFrequency) -> Self {
         Self {
             frequency,
             last_minute_bucket: i64::MIN,
             last_timestamp: None,
+            first_seen: None,
+            latest_seen: None,
+            buckets: 0,
         }
     }
 
@@ -72,10 +205,56 @@ impl AdaptiveTimeDetector {
         &self.frequency
     }
 
+    /// The length of one period when counting the periods of an indicator duration: the width
+    /// of fixed-width buckets, one day for every other bucketing
+    pub fn period(&self) -> Duration {
+        match self.frequency {
+            DetectedFrequency::Intraday(width) => width,
+            _ => Duration::from_secs(86400),
+        }
+    }
+
+    /// Number of periods in `duration`, e.g. 14 for 14 minutes in 1 minute buckets or for
+    /// 14 days in daily buckets
+    pub fn periods(&self, duration: Duration) -> f64 {
+        duration.as_secs_f64() / self.period().as_secs_f64()
+    }
+
     /// Process a new timestamp and determine if it should replace the previous value
     /// Returns true if this is a duplicate within the same time bucket (should replace)
     /// Returns false if this is a new time period (should append)
     pub fn should_replace(&mut self, timestamp: DateTime<Utc>) -> bool {
+        let should_replace = self.same_bucket(timestamp);
+        if !should_replace {
+            self.buckets += 1;
+        }
+        self.first_seen.get_or_insert(timestamp);
+        self.latest_seen = Some(timestamp);
+        should_replace
+    }
+
+    /// Number of time buckets seen since the last reset
+    pub fn bucket_count(&self) -> usize {
+        self.buckets
+    }
+
+    /// The latest timestamp seen since the last reset
+    pub fn latest(&self) -> Option<DateTime<Utc>> {
+        self.latest_seen
+    }
+
+    /// Whether the first timestamp seen since the last reset is at least `duration` older than
+    /// the latest one, i.e. a window of `duration` has had all of its history
+    pub fn spans(&self, duration: Duration) -> bool {
+        match (self.first_seen, self.latest_seen) {
+            (Some(first), Some(latest)) => (latest - first)
+                .to_std()
+                .is_ok_and(|elapsed| elapsed >= duration),
+            _ => false,
+        }
+    }
+
+    fn same_bucket(&mut self, timestamp: DateTime<Utc>) -> bool {
         match &self.frequency {
             DetectedFrequency::Intraday(bucket_duration) => {
                 // Dynamic bucketing based on bucket_duration (second or minute level)
@@ -98,35 +277,61 @@ impl AdaptiveTimeDetector {
 
                 // 3.4 hours = 3 hours 24 minutes = 12,240 seconds
                 let min_gap = chrono::Duration::seconds(3 * 3600 + 24 * 60);
-
-                if let Some(last_ts) = self.last_timestamp {
-                    let time_diff = timestamp - last_ts;
-
-                    // If within 3.4 hours of the last point, replace it
-                    // This handles minutely data during market hours
-                    if time_diff > chrono::Duration::zero() && time_diff < min_gap {
-                        // Don't update last_timestamp here - we're replacing
-                        return true;
-                    }
-                }
-
-                // Either first point or more than 3.4 hours have passed
-                // This is a new slot, so update last_timestamp
-                self.last_timestamp = Some(timestamp);
-                false
+                self.replace_within_gap(timestamp, min_gap)
             }
-            DetectedFrequency::Unknown => {
-                // Shouldn't happen but default to not replacing
+            DetectedFrequency::Gap(gap) => {
+                let min_gap = chrono::Duration::from_std(*gap).unwrap();
+                self.replace_within_gap(timestamp, min_gap)
+            }
+            DetectedFrequency::CalendarDay => {
+                let current_day = timestamp.timestamp().div_euclid(86400);
+                let should_replace = current_day == self.last_minute_bucket;
+                self.last_minute_bucket = current_day;
+                should_replace
+            }
+            DetectedFrequency::Session(calendar) => {
+                let current_session = match calendar.trading_day(timestamp) {
+                    Some(date) => date.num_days_from_ce() as i64,
+                    None => i64::MIN,
+                };
+                let should_replace =
+                    current_session != i64::MIN && current_session == self.last_minute_bucket;
+                self.last_minute_bucket = current_session;
+                should_replace
+            }
+            DetectedFrequency::EveryUpdate | DetectedFrequency::Unknown => {
+                // Every point is a new slot (Unknown shouldn't happen, same default)
                 self.last_timestamp = Some(timestamp);
                 false
             }
         }
     }
 
+    fn replace_within_gap(&mut self, timestamp: DateTime<Utc>, min_gap: chrono::Duration) -> bool {
+        if let Some(last_ts) = self.last_timestamp {
+            let time_diff = timestamp - last_ts;
+
+            // If within the gap of the last slot, replace it
+            // This handles minutely data during market hours
+            if time_diff > chrono::Duration::zero() && time_diff < min_gap {
+                // Don't update last_timestamp here - we're replacing
+                return true;
+            }
+        }
+
+        // Either first point or the gap has passed
+        // This is a new slot, so update last_timestamp
+        self.last_timestamp = Some(timestamp);
+        false
+    }
+
     /// Reset the detector to initial state
     pub fn reset(&mut self) {
         self.last_minute_bucket = i64::MIN;
         self.last_timestamp = None;
+        self.first_seen = None;
+        self.latest_seen = None;
+        self.buckets = 0;
         // Keep frequency as it was set based on duration
     }
 
@@ -154,7 +359,7 @@ mod tests {
         let mut detector = AdaptiveTimeDetector::new(Duration::from_secs(2 * 86400)); // 2 days
         assert_eq!(detector.frequency(), &DetectedFrequency::DailyOHLC);
 
-        let base = Utc.ymd(2024, 1, 1).and_hms(9, 30, 0);
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
 
         // First point
         assert!(!detector.should_replace(base));
@@ -187,7 +392,7 @@ mod tests {
         assert_eq!(detector.frequency(), &DetectedFrequency::DailyOHLC);
 
         // Simulate half-day trading (market closes at 1:00 PM, ~3.5 hours)
-        let half_day_open = Utc.ymd(2024, 1, 2).and_hms(9, 30, 0);
+        let half_day_open = Utc.with_ymd_and_hms(2024, 1, 2, 9, 30, 0).unwrap();
         assert!(!detector.should_replace(half_day_open)); // New slot for open price
 
         // All updates during first 3.4 hours should replace the open price
@@ -220,7 +425,7 @@ mod tests {
             DetectedFrequency::Intraday(d) if d.as_secs() == 60
         ));
 
-        let base = Utc.ymd(2024, 1, 1).and_hms(9, 30, 0);
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
 
         // First data point
         assert!(!detector.should_replace(base));
@@ -244,7 +449,7 @@ mod tests {
         let mut detector = AdaptiveTimeDetector::new(Duration::from_secs(5 * 86400)); // 5 days
 
         // Full trading day: 9:30 AM to 4:00 PM (6.5 hours)
-        let market_open = Utc.ymd(2024, 1, 2).and_hms(9, 30, 0);
+        let market_open = Utc.with_ymd_and_hms(2024, 1, 2, 9, 30, 0).unwrap();
 
         // First data point at market open
         assert!(!detector.should_replace(market_open));
@@ -268,10 +473,27 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_periods() {
+        let detector = AdaptiveTimeDetector::new(Duration::from_secs(14 * 60));
+        assert_eq!(detector.periods(Duration::from_secs(14 * 60)), 14.0);
+
+        let detector = AdaptiveTimeDetector::new(Duration::from_secs(14 * 86400));
+        assert_eq!(detector.periods(Duration::from_secs(14 * 86400)), 14.0);
+
+        let four_hours = Duration::from_secs(4 * 3600);
+        let detector = AdaptiveTimeDetector::with_policy(
+            Duration::from_secs(2 * 86400),
+            BucketPolicy::Fixed(four_hours),
+        )
+        .unwrap();
+        assert_eq!(detector.periods(Duration::from_secs(2 * 86400)), 12.0);
+    }
+
     #[test]
     fn test_reset() {
         let mut detector = AdaptiveTimeDetector::new(Duration::from_secs(86400));
-        let base = Utc.ymd(2024, 1, 1).and_hms(9, 30, 0);
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
 
         // Use detector
         detector.should_replace(base);
@@ -288,6 +510,28 @@ mod tests {
         assert!(!detector.should_replace(base + chrono::Duration::days(1)));
     }
 
+    #[test]
+    fn test_bucket_count_and_span() {
+        let mut detector = AdaptiveTimeDetector::new(Duration::from_secs(2 * 86400));
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
+        assert_eq!(detector.bucket_count(), 0);
+        assert!(!detector.spans(Duration::from_secs(2 * 86400)));
+
+        detector.should_replace(base);
+        detector.should_replace(base + chrono::Duration::hours(1));
+        detector.should_replace(base + chrono::Duration::days(1));
+        assert_eq!(detector.bucket_count(), 2);
+        assert!(!detector.spans(Duration::from_secs(2 * 86400)));
+
+        detector.should_replace(base + chrono::Duration::days(2));
+        assert_eq!(detector.bucket_count(), 3);
+        assert!(detector.spans(Duration::from_secs(2 * 86400)));
+
+        detector.reset();
+        assert_eq!(detector.bucket_count(), 0);
+        assert!(!detector.spans(Duration::from_secs(2 * 86400)));
+    }
+
     #[test]
     fn test_memory_footprint_for_trading_sessions() {
         let mut detector = AdaptiveTimeDetector::new(Duration::from_secs(10 * 86400)); // 10 days
@@ -296,7 +540,7 @@ mod tests {
         let mut kept_timestamps = Vec::new();
 
         // Half-day: Should keep exactly 2 points (open and close)
-        let half_day_open = Utc.ymd(2024, 1, 2).and_hms(9, 30, 0);
+        let half_day_open = Utc.with_ymd_and_hms(2024, 1, 2, 9, 30, 0).unwrap();
         if !detector.should_replace(half_day_open) {
             kept_timestamps.push(half_day_open);
         }
@@ -326,7 +570,7 @@ mod tests {
         kept_timestamps.clear();
         detector.reset();
 
-        let full_day_open = Utc.ymd(2024, 1, 3).and_hms(9, 30, 0);
+        let full_day_open = Utc.with_ymd_and_hms(2024, 1, 3, 9, 30, 0).unwrap();
         if !detector.should_replace(full_day_open) {
             kept_timestamps.push(full_day_open);
         }
@@ -354,4 +598,121 @@ mod tests {
             "Gap between points should be >= 3.4 hours"
         );
     }
+
+    #[test]
+    fn test_heuristic_policy() {
+        let duration = Duration::from_secs(86400);
+        let detector = AdaptiveTimeDetector::with_policy(duration, BucketPolicy::Heuristic);
+        assert_eq!(
+            detector.unwrap().frequency(),
+            AdaptiveTimeDetector::new(duration).frequency()
+        );
+    }
+
+    #[test]
+    fn test_invalid_policy() {
+        let duration = Duration::from_secs(86400);
+        let invalid = [
+            BucketPolicy::Fixed(Duration::from_secs(0)),
+            BucketPolicy::Fixed(Duration::from_millis(1500)),
+            BucketPolicy::GapThreshold(Duration::from_secs(0)),
+        ];
+        for policy in invalid {
+            assert!(AdaptiveTimeDetector::with_policy(duration, policy).is_err());
+        }
+    }
+
+    #[test]
+    fn test_fixed_policy() {
+        let policy = BucketPolicy::Fixed(Duration::from_secs(4 * 3600));
+        let mut detector =
+            AdaptiveTimeDetector::with_policy(Duration::from_secs(10 * 86400), policy).unwrap();
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        // 4 hour bars aligned to midnight UTC, regardless of the daily window
+        assert!(!detector.should_replace(base + chrono::Duration::minutes(5)));
+        assert!(detector.should_replace(base + chrono::Duration::hours(3)));
+        assert!(!detector.should_replace(base + chrono::Duration::hours(4)));
+        assert!(detector.should_replace(base + chrono::Duration::minutes(479)));
+        assert!(!detector.should_replace(base + chrono::Duration::hours(8)));
+    }
+
+    #[test]
+    fn test_calendar_day_policy() {
+        let mut detector = AdaptiveTimeDetector::with_policy(
+            Duration::from_secs(7 * 86400),
+            BucketPolicy::CalendarDay,
+        )
+        .unwrap();
+        assert_eq!(detector.frequency(), &DetectedFrequency::CalendarDay);
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        // A 24/7 market updates all day long into a single daily slot
+        assert!(!detector.should_replace(base));
+        for hours in 1..24 {
+            assert!(detector.should_replace(base + chrono::Duration::hours(hours)));
+        }
+        assert!(detector.should_replace(base + chrono::Duration::seconds(86399)));
+        assert!(!detector.should_replace(base + chrono::Duration::days(1)));
+    }
+
+    #[test]
+    fn test_gap_threshold_policy() {
+        let policy = BucketPolicy::GapThreshold(Duration::from_secs(23 * 3600));
+        let mut detector =
+            AdaptiveTimeDetector::with_policy(Duration::from_secs(5 * 86400), policy).unwrap();
+
+        // Futures session from 18:00 to 17:00 New York time the next day
+        let session_open = Utc.with_ymd_and_hms(2024, 1, 1, 23, 0, 0).unwrap();
+        assert!(!detector.should_replace(session_open));
+        for hours in 1..=22 {
+            assert!(detector.should_replace(session_open + chrono::Duration::hours(hours)));
+        }
+
+        // The next session starts a new slot
+        assert!(!detector.should_replace(session_open + chrono::Duration::days(1)));
+    }
+
+    #[test]
+    fn test_never_replace_policy() {
+        let mut detector =
+            AdaptiveTimeDetector::with_policy(Duration::from_secs(60), BucketPolicy::NeverReplace)
+                .unwrap();
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
+
+        assert!(!detector.should_replace(base));
+        assert!(!detector.should_replace(base));
+        assert!(!detector.should_replace(base + chrono::Duration::milliseconds(1)));
+    }
+
+    #[test]
+    fn test_session_policy() {
+        let calendar: SessionCalendar = "
+            utc_offset = -05:00
+            dst = us
+            open = 09:30
+            close = 16:00
+            holiday = 2024-07-04
+            half_day = 2024-07-03 13:00
+        "
+        .parse()
+        .unwrap();
+        let policy = BucketPolicy::Session(Arc::new(calendar));
+        let mut detector =
+            AdaptiveTimeDetector::with_policy(Duration::from_secs(5 * 86400), policy).unwrap();
+        let at = |d: u32, h: u32, m: u32| Utc.with_ymd_and_hms(2024, 7, d, h, m, 0).unwrap();
+
+        // The half day is a single slot, where the 3.4 hour gap would have split it in two
+        assert!(!detector.should_replace(at(3, 13, 30)));
+        assert!(detector.should_replace(at(3, 16, 0)));
+        assert!(detector.should_replace(at(3, 16, 59)));
+
+        // After hours and the holiday still revise the last session
+        assert!(detector.should_replace(at(3, 21, 0)));
+        assert!(detector.should_replace(at(4, 15, 0)));
+
+        // The next open starts a new slot
+        assert!(!detector.should_replace(at(5, 13, 30)));
+        assert!(detector.should_replace(at(5, 19, 59)));
+    }
 }
@@ -0,0 +1,251 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, ExponentialMovingAverage as Ema, TrueRange};
+use crate::{Close, High, Low, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Average true range (ATR).
+///
+/// A technical analysis volatility indicator, originally developed by J. Welles Wilder.
+/// It is a moving average of the [TrueRange](struct.TrueRange.html) over a time window.
+///
+/// Two smoothings are available:
+///
+/// * [new](#method.new) - EMA with the factor `2 / (days + 1)`
+/// * [wilder](#method.wilder) - Wilder's smoothing with the factor `1 / periods`, seeded with the
+///   simple mean of the first `periods` time buckets, e.g. 14 for 14 minutes of 1 minute bars
+///
+/// Updates within the same time bucket revise the current value: the true range keeps
+/// measuring against the prior period's close and the average reverses its previous step.
+#[doc(alias = "ATR")]
+#[derive(Debug, Clone)]
+pub struct AverageTrueRange {
+    true_range: TrueRange,
+    ema: Ema,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "AverageTrueRange")]
+struct StateV1 {
+    true_range: TrueRange,
+    ema: Ema,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(AverageTrueRange, StateV1, 1);
+
+impl AverageTrueRange {
+    pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            true_range: TrueRange::with_policy(duration, policy.clone())?,
+            ema: Ema::with_policy(duration, policy)?,
+        })
+    }
+
+    /// Fails if the duration is shorter than one time bucket.
+    pub fn wilder(duration: Duration) -> Result<Self> {
+        Self::wilder_with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [wilder](#method.wilder), with the time buckets set by `policy` instead of `duration`.
+    pub fn wilder_with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            true_range: TrueRange::with_policy(duration, policy.clone())?,
+            ema: Ema::wilder_with_policy(duration, policy)?,
+        })
+    }
+}
+
+impl Next<f64> for AverageTrueRange {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        let true_range = self.true_range.next((timestamp, value));
+        self.ema.next((timestamp, true_range))
+    }
+}
+
+impl<T: High + Low + Close> Next<&T> for AverageTrueRange {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        let true_range = self.true_range.next((timestamp, input));
+        self.ema.next((timestamp, true_range))
+    }
+}
+
+impl Validate for AverageTrueRange {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.true_range.latest_time()
+    }
+}
+
+impl Peek for AverageTrueRange {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.ema.current()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.true_range.is_ready() && self.ema.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.ema.sample_count()
+    }
+}
+
+impl Reset for AverageTrueRange {
+    fn reset(&mut self) {
+        self.true_range.reset();
+        self.ema.reset();
+    }
+}
+
+impl Default for AverageTrueRange {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap()
+    }
+}
+
+impl fmt::Display for AverageTrueRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ATR({})", format_duration(self.ema.duration()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+    use chrono::TimeZone;
+
+    test_indicator!(AverageTrueRange);
+
+    #[test]
+    fn test_new() {
+        assert!(AverageTrueRange::new(Duration::from_secs(0)).is_err());
+        assert!(AverageTrueRange::new(DAY).is_ok());
+        assert!(AverageTrueRange::wilder(Duration::from_secs(0)).is_err());
+        assert!(AverageTrueRange::wilder(DAY).is_ok());
+    }
+
+    #[test]
+    fn test_next() {
+        let mut atr = AverageTrueRange::new(DAY * 3).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        let bar1 = Bar::new().high(10).low(7.5).close(9);
+        let bar2 = Bar::new().high(11).low(9).close(9.5);
+        let bar3 = Bar::new().high(9).low(5).close(8);
+
+        assert_eq!(atr.next((start, &bar1)), 2.5);
+        assert_eq!(atr.next((start + chrono::Duration::days(1), &bar2)), 2.25);
+        assert_eq!(atr.next((start + chrono::Duration::days(2), &bar3)), 3.375);
+    }
+
+    #[test]
+    fn test_next_wilder() {
+        let mut atr = AverageTrueRange::wilder(DAY * 3).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        let bar1 = Bar::new().high(10).low(7.5).close(9);
+        let bar2 = Bar::new().high(11).low(9).close(9.5);
+        let bar3 = Bar::new().high(9).low(5).close(8);
+
+        let bar4 = Bar::new().high(10).low(8).close(9);
+
+        assert_eq!(atr.next((start, &bar1)), 2.5);
+        assert_eq!(atr.next((start + chrono::Duration::days(1), &bar2)), 2.25);
+        assert_eq!(atr.next((start + chrono::Duration::days(2), &bar3)), 3.0);
+        assert_eq!(
+            round(atr.next((start + chrono::Duration::days(3), &bar4))),
+            2.667
+        );
+    }
+
+    #[test]
+    fn test_next_wilder_intraday() {
+        let mut atr = AverageTrueRange::wilder(Duration::from_secs(5 * 60)).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+        let bars = [
+            Bar::new().high(10).low(7.5).close(9),
+            Bar::new().high(11).low(9).close(9.5),
+            Bar::new().high(9).low(5).close(8),
+            Bar::new().high(10).low(8).close(9),
+            Bar::new().high(12).low(9).close(11.5),
+            Bar::new().high(11.8).low(10).close(10.2),
+            Bar::new().high(10.5).low(8.5).close(9),
+            Bar::new().high(9.8).low(8.8).close(9.6),
+        ];
+
+        // 5 one minute periods: the mean of the first 5 true ranges, then the factor 1 / 5
+        let expected = [2.5, 2.25, 3.0, 2.75, 2.8, 2.6, 2.48, 2.184];
+        for (minute, (bar, &value)) in bars.iter().zip(expected.iter()).enumerate() {
+            let time = start + chrono::Duration::minutes(minute as i64);
+            assert_eq!(round(atr.next((time, bar))), value);
+        }
+
+        assert!(AverageTrueRange::wilder_with_policy(
+            Duration::from_secs(30 * 60),
+            BucketPolicy::Fixed(Duration::from_secs(3600))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_replacement_within_bucket() {
+        let mut atr = AverageTrueRange::new(DAY * 3).unwrap();
+        let open = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
+        let next_open = open + chrono::Duration::days(1);
+
+        atr.next((open, &Bar::new().high(10).low(7.5).close(9)));
+        atr.next((next_open, &Bar::new().high(12).low(6).close(7)));
+
+        // The final revision of the session gives the same result as a single update
+        let revised = atr.next((
+            next_open + chrono::Duration::hours(1),
+            &Bar::new().high(11).low(9).close(9.5),
+        ));
+        assert_eq!(revised, 2.25);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut atr = AverageTrueRange::new(DAY * 9).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        let bar1 = Bar::new().high(10).low(7.5).close(9);
+        let bar2 = Bar::new().high(11).low(9).close(9.5);
+
+        atr.next((start, &bar1));
+        atr.next((start + chrono::Duration::days(1), &bar2));
+
+        atr.reset();
+        let bar3 = Bar::new().high(60).low(15).close(51);
+        assert_eq!(atr.next((start, &bar3)), 45.0);
+    }
+
+    #[test]
+    fn test_default() {
+        AverageTrueRange::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let indicator = AverageTrueRange::new(DAY * 8).unwrap();
+        assert_eq!(format!("{}", indicator), "ATR(8 days)");
+    }
+}
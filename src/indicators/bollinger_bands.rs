@@ -4,20 +4,33 @@ use std::fmt;
 use std::time::Duration; // Change: Use std::time::Duration
 
 use crate::errors::Result;
-use crate::indicators::{AdaptiveTimeDetector, StandardDeviation as Sd};
-use crate::{Next, Reset};
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, StandardDeviation as Sd};
+use crate::{Close, Next, Peek, Reset, Validate};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// Bollinger Bands (BB).
+///
+/// A moving average with an upper and a lower band placed `multiplier` population standard
+/// deviations away from it, all computed over a time window.
+///
+/// The average and the deviation come from the running sums of a single
+/// [StandardDeviation](struct.StandardDeviation.html), so each update is O(1) and updates within
+/// the same time bucket replace the bucket's value.
+///
+/// Besides the bands, the output carries:
+///
+/// * `percent_b` (%B) - where the input is relative to the bands: `(input - lower) / (upper - lower)`,
+///   `0.5` while the bands are collapsed
+/// * `bandwidth` - width of the bands relative to the average: `(upper - lower) / average`,
+///   `0.0` when the average is zero
 #[doc(alias = "BB")]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct BollingerBands {
     duration: Duration, // Now std::time::Duration
     multiplier: f64,
     sd: Sd,
-    window: VecDeque<(DateTime<Utc>, f64)>,
-    detector: AdaptiveTimeDetector,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -25,24 +38,70 @@ pub struct BollingerBandsOutput {
     pub average: f64,
     pub upper: f64,
     pub lower: f64,
+    pub percent_b: f64,
+    pub bandwidth: f64,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "BollingerBands")]
+struct StateV1 {
+    duration: Duration,
+    multiplier: f64,
+    sd: Sd,
+}
+
+// The unversioned layout of ta 0.5
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LegacyState {
+    duration: Duration,
+    multiplier: f64,
+    sd: super::standard_deviation::LegacyState,
+    // Copies of the window and bucketing of `sd`
+    #[allow(dead_code)]
+    window: VecDeque<(DateTime<Utc>, f64)>,
+    #[allow(dead_code)]
+    detector: super::adaptive::LegacyDetector,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<BollingerBands> for LegacyState {
+    fn migrate(self) -> Result<BollingerBands> {
+        Ok(BollingerBands {
+            duration: self.duration,
+            multiplier: self.multiplier,
+            sd: self.sd.migrate()?,
+        })
+    }
 }
 
+#[cfg(feature = "serde")]
+versioned_serde!(BollingerBands, StateV1, 1, 0 => LegacyState);
+
 impl BollingerBands {
     pub fn get_window(&self) -> VecDeque<(DateTime<Utc>, f64)> {
-        self.window.clone()
+        self.sd.get_window()
     }
 
+    /// Fails for a zero `duration` and a `multiplier` that is negative or not finite.
     pub fn new(duration: Duration, multiplier: f64) -> Result<Self> {
+        Self::with_policy(duration, multiplier, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, multiplier: f64, policy: BucketPolicy) -> Result<Self> {
         // Change: Check for zero duration (std::time::Duration can't be negative)
         if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
             return Err(crate::errors::TaError::InvalidParameter);
         }
+        if !multiplier.is_finite() || multiplier < 0.0 {
+            return Err(crate::errors::TaError::InvalidParameter);
+        }
         Ok(Self {
             duration,
             multiplier,
-            sd: Sd::new(duration)?, // Pass std::time::Duration
-            window: VecDeque::new(),
-            detector: AdaptiveTimeDetector::new(duration),
+            sd: Sd::with_policy(duration, policy)?, // Pass std::time::Duration
         })
     }
 
@@ -50,50 +109,73 @@ impl BollingerBands {
         self.multiplier
     }
 
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // Change: Convert std::time::Duration to chrono::Duration for date arithmetic
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time <= current_time - chrono_duration)
-        {
-            self.window.pop_front();
+    fn output(&self, value: f64) -> BollingerBandsOutput {
+        let (average, sd) = self.sd.mean_and_deviation();
+
+        let upper = average + sd * self.multiplier;
+        let lower = average - sd * self.multiplier;
+
+        let width = upper - lower;
+        let percent_b = if width == 0.0 {
+            0.5
+        } else {
+            (value - lower) / width
+        };
+        let bandwidth = if average == 0.0 { 0.0 } else { width / average };
+
+        BollingerBandsOutput {
+            average,
+            upper,
+            lower,
+            percent_b,
+            bandwidth,
         }
     }
 }
 
 impl Next<f64> for BollingerBands {
-    type Output = f64;
+    type Output = BollingerBandsOutput;
 
     fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
-        // Check if we should replace the last value (same time bucket)
-        let should_replace = self.detector.should_replace(timestamp);
+        // The standard deviation takes care of bucket replacement and old data removal
+        self.sd.push(timestamp, value);
+        self.output(value)
+    }
+}
 
-        // ALWAYS remove old data first, regardless of replace/add
-        self.remove_old_data(timestamp);
+impl<T: Close> Next<&T> for BollingerBands {
+    type Output = BollingerBandsOutput;
 
-        if should_replace && !self.window.is_empty() {
-            // Replace the last value in the same time bucket
-            self.window.pop_back();
-        }
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for BollingerBands {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.sd.latest_time()
+    }
+}
 
-        // Add the new data point
-        self.window.push_back((timestamp, value));
+impl Peek for BollingerBands {
+    type Output = BollingerBandsOutput;
 
-        // Calculate the mean and standard deviation based on the current window
-        let values: Vec<f64> = self.window.iter().map(|&(_, val)| val).collect();
-        let mean = values.iter().sum::<f64>() / values.len() as f64;
-        let sd = self.sd.next((timestamp, value));
-        mean + sd * self.multiplier
+    fn current(&self) -> Option<Self::Output> {
+        Some(self.output(self.sd.latest()?))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.sd.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.sd.sample_count()
     }
 }
 
 impl Reset for BollingerBands {
     fn reset(&mut self) {
         self.sd.reset();
-        self.window.clear();
-        self.detector.reset();
     }
 }
 
@@ -106,8 +188,12 @@ impl Default for BollingerBands {
 
 impl fmt::Display for BollingerBands {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Change: Display duration in seconds
-        write!(f, "BB({}s, {})", self.duration.as_secs(), self.multiplier)
+        write!(
+            f,
+            "BB({}, {})",
+            format_duration(self.duration),
+            self.multiplier
+        )
     }
 }
 
@@ -115,7 +201,7 @@ impl fmt::Display for BollingerBands {
 mod tests {
     use super::*;
     use crate::test_helper::*;
-    use chrono::Utc;
+    use chrono::{TimeZone, Utc};
 
     test_indicator!(BollingerBands);
 
@@ -126,6 +212,10 @@ mod tests {
         assert!(BollingerBands::new(Duration::from_secs(86400), 2_f64).is_ok()); // 1 day
         assert!(BollingerBands::new(Duration::from_secs(172800), 2_f64).is_ok());
         // 2 days
+        assert!(BollingerBands::new(Duration::from_secs(86400), 0.0).is_ok());
+        assert!(BollingerBands::new(Duration::from_secs(86400), -2.0).is_err());
+        assert!(BollingerBands::new(Duration::from_secs(86400), f64::NAN).is_err());
+        assert!(BollingerBands::new(Duration::from_secs(86400), f64::INFINITY).is_err());
     }
 
     #[test]
@@ -139,10 +229,67 @@ mod tests {
         let c = bb.next((now + chrono::Duration::days(2), 1.0));
         let d = bb.next((now + chrono::Duration::days(3), 6.25));
 
-        assert_eq!(round(a), 2.0);
-        assert_eq!(round(b), 6.5);
-        assert_eq!(round(c), 6.066);
-        assert_eq!(round(d), 8.562);
+        assert_eq!(round(a.average), 2.0);
+        assert_eq!(round(a.upper), 2.0);
+        assert_eq!(round(a.lower), 2.0);
+        assert_eq!(round(a.percent_b), 0.5);
+        assert_eq!(round(a.bandwidth), 0.0);
+
+        assert_eq!(round(b.average), 3.5);
+        assert_eq!(round(b.upper), 6.5);
+        assert_eq!(round(b.lower), 0.5);
+        assert_eq!(round(b.percent_b), 0.75);
+        assert_eq!(round(b.bandwidth), 1.714);
+
+        assert_eq!(round(c.average), 2.667);
+        assert_eq!(round(c.upper), 6.066);
+        assert_eq!(round(c.lower), -0.733);
+        assert_eq!(round(c.percent_b), 0.255);
+        assert_eq!(round(c.bandwidth), 2.55);
+
+        assert_eq!(round(d.average), 4.083);
+        assert_eq!(round(d.upper), 8.562);
+        assert_eq!(round(d.lower), -0.395);
+        assert_eq!(round(d.percent_b), 0.742);
+        assert_eq!(round(d.bandwidth), 2.193);
+    }
+
+    #[test]
+    fn test_replacement_within_bucket() {
+        let mut bb = BollingerBands::new(Duration::from_secs(3 * 86400), 2.0).unwrap(); // 3 days
+        let open = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
+
+        bb.next((open, 2.0));
+        bb.next((open + chrono::Duration::days(1), 9.0));
+        let out = bb.next((
+            open + chrono::Duration::days(1) + chrono::Duration::hours(1),
+            5.0,
+        ));
+
+        // Same as feeding 2.0 and 5.0 only
+        assert_eq!(round(out.average), 3.5);
+        assert_eq!(round(out.upper), 6.5);
+        assert_eq!(round(out.lower), 0.5);
+    }
+
+    #[test]
+    fn test_squeeze_after_spike() {
+        let mut bb = BollingerBands::new(Duration::from_secs(5 * 86400), 2.0).unwrap(); // 5 days
+        let now = Utc::now();
+        let values = [0.1, 0.7, 0.3, 1e6, 0.2];
+
+        for day in 0..20 {
+            let value = values.get(day).copied().unwrap_or(0.3);
+            let out = bb.next((now + chrono::Duration::days(day as i64), value));
+            // The bands collapse once only 0.3 is left in the window
+            if day >= 9 {
+                assert_eq!(round(out.average), 0.3);
+                assert_eq!(out.upper, out.average);
+                assert_eq!(out.lower, out.average);
+                assert_eq!(out.percent_b, 0.5);
+                assert_eq!(out.bandwidth, 0.0);
+            }
+        }
     }
 
     #[test]
@@ -152,7 +299,9 @@ mod tests {
 
         let out = bb.next((now, 3.0));
 
-        assert_eq!(out, 3.0);
+        assert_eq!(out.average, 3.0);
+        assert_eq!(out.upper, 3.0);
+        assert_eq!(out.lower, 3.0);
 
         bb.next((now + chrono::Duration::days(1), 2.5));
         bb.next((now + chrono::Duration::days(2), 3.5));
@@ -160,11 +309,16 @@ mod tests {
 
         let out = bb.next((now + chrono::Duration::days(4), 2.0));
 
-        assert_eq!(round(out), 4.414);
+        assert_eq!(out.average, 3.0);
+        assert_eq!(round(out.upper), 4.414);
+        assert_eq!(round(out.lower), 1.586);
+        assert_eq!(round(out.percent_b), 0.146);
 
         bb.reset();
         let out = bb.next((now, 3.0));
-        assert_eq!(out, 3.0);
+        assert_eq!(out.average, 3.0);
+        assert_eq!(out.upper, 3.0);
+        assert_eq!(out.lower, 3.0);
     }
 
     #[test]
@@ -176,6 +330,6 @@ mod tests {
     fn test_display() {
         let duration = Duration::from_secs(10 * 86400); // 10 days
         let bb = BollingerBands::new(duration, 3.0_f64).unwrap();
-        assert_eq!(format!("{}", bb), format!("BB({}s, 3)", 10 * 86400));
+        assert_eq!(format!("{}", bb), "BB(10 days, 3)");
     }
 }
@@ -0,0 +1,272 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::{Result, TaError};
+use crate::helpers::format_duration;
+use crate::indicators::{AverageTrueRange, BucketPolicy, Maximum, Minimum};
+use crate::{Close, High, Low, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Chandelier Exit (CE).
+///
+/// Trailing stops placed `multiplier` average true ranges away from the extremes of the time
+/// window:
+///
+/// * `long` - exit for long positions: `highest high - ATR * multiplier`
+/// * `short` - exit for short positions: `lowest low + ATR * multiplier`
+///
+/// The extremes are tracked by a time-windowed [Maximum](struct.Maximum.html) and
+/// [Minimum](struct.Minimum.html) and the range by an
+/// [AverageTrueRange](struct.AverageTrueRange.html), so updates within the same time bucket
+/// replace the high and low of that bucket. Fed with `f64` values, the value is used as the high,
+/// the low and the close.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::ChandelierExit;
+/// use ta::{DataItem, Next};
+///
+/// let mut ce = ChandelierExit::new(Duration::from_secs(3 * 86400), 2.0).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+/// let bar = |high: f64, low: f64, close: f64| {
+///     DataItem::builder()
+///         .open(close)
+///         .high(high)
+///         .low(low)
+///         .close(close)
+///         .volume(1000.0)
+///         .build()
+///         .unwrap()
+/// };
+///
+/// ce.next((start, &bar(12.0, 9.0, 10.0)));
+/// let output = ce.next((start + ChronoDuration::days(1), &bar(13.0, 10.0, 12.0)));
+/// assert_eq!(output.long, 7.0);
+/// assert_eq!(output.short, 15.0);
+/// ```
+#[doc(alias = "CE")]
+#[derive(Debug, Clone)]
+pub struct ChandelierExit {
+    duration: Duration,
+    multiplier: f64,
+    atr: AverageTrueRange,
+    maximum: Maximum,
+    minimum: Minimum,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChandelierExitOutput {
+    pub long: f64,
+    pub short: f64,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ChandelierExit")]
+struct StateV1 {
+    duration: Duration,
+    multiplier: f64,
+    atr: AverageTrueRange,
+    maximum: Maximum,
+    minimum: Minimum,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(ChandelierExit, StateV1, 1);
+
+impl ChandelierExit {
+    /// Fails for a zero `duration` and a `multiplier` that is negative or not finite.
+    pub fn new(duration: Duration, multiplier: f64) -> Result<Self> {
+        Self::with_policy(duration, multiplier, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, multiplier: f64, policy: BucketPolicy) -> Result<Self> {
+        if !multiplier.is_finite() || multiplier < 0.0 {
+            return Err(TaError::InvalidParameter);
+        }
+        Ok(Self {
+            duration,
+            multiplier,
+            atr: AverageTrueRange::with_policy(duration, policy.clone())?,
+            maximum: Maximum::with_policy(duration, policy.clone())?,
+            minimum: Minimum::with_policy(duration, policy)?,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    fn output(&self, atr: f64, highest: f64, lowest: f64) -> ChandelierExitOutput {
+        ChandelierExitOutput {
+            long: highest - atr * self.multiplier,
+            short: lowest + atr * self.multiplier,
+        }
+    }
+}
+
+impl Next<f64> for ChandelierExit {
+    type Output = ChandelierExitOutput;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        let atr = self.atr.next((timestamp, value));
+        let highest = self.maximum.next((timestamp, value));
+        let lowest = self.minimum.next((timestamp, value));
+        self.output(atr, highest, lowest)
+    }
+}
+
+impl<T: High + Low + Close> Next<&T> for ChandelierExit {
+    type Output = ChandelierExitOutput;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        let atr = self.atr.next((timestamp, input));
+        let highest = self.maximum.next((timestamp, input.high()));
+        let lowest = self.minimum.next((timestamp, input.low()));
+        self.output(atr, highest, lowest)
+    }
+}
+
+impl Validate for ChandelierExit {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.maximum.latest_time()
+    }
+}
+
+impl Peek for ChandelierExit {
+    type Output = ChandelierExitOutput;
+
+    fn current(&self) -> Option<Self::Output> {
+        Some(self.output(
+            self.atr.current()?,
+            self.maximum.current()?,
+            self.minimum.current()?,
+        ))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.atr.is_ready() && self.maximum.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.maximum.sample_count()
+    }
+}
+
+impl Reset for ChandelierExit {
+    fn reset(&mut self) {
+        self.atr.reset();
+        self.maximum.reset();
+        self.minimum.reset();
+    }
+}
+
+impl Default for ChandelierExit {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(22 * 24 * 60 * 60), 3.0).unwrap()
+    }
+}
+
+impl fmt::Display for ChandelierExit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CE({}, {})",
+            format_duration(self.duration),
+            self.multiplier
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+
+    test_indicator!(ChandelierExit);
+
+    fn rounded(output: ChandelierExitOutput) -> (f64, f64) {
+        (round(output.long), round(output.short))
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(ChandelierExit::new(Duration::from_secs(0), 3.0).is_err());
+        assert!(ChandelierExit::new(DAY, 3.0).is_ok());
+        assert!(ChandelierExit::new(DAY, 0.0).is_ok());
+        assert!(ChandelierExit::new(DAY, -1.0).is_err());
+        assert!(ChandelierExit::new(DAY, f64::NAN).is_err());
+        assert!(ChandelierExit::new(DAY, f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn test_next() {
+        let mut ce = ChandelierExit::new(DAY * 3, 2.0).unwrap();
+
+        let bars = [
+            ((12, 9, 10), (6.0, 15.0)),
+            ((13, 10, 12), (7.0, 15.0)),
+            ((15, 11, 14), (8.0, 16.0)),
+            // Day 0 has left the window
+            ((14, 12, 13), (9.5, 15.5)),
+        ];
+        for (n, ((high, low, close), expected)) in bars.into_iter().enumerate() {
+            let bar = Bar::new().high(high).low(low).close(close);
+            assert_eq!(rounded(ce.next((day(n as i64), &bar))), expected);
+        }
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut ce = ChandelierExit::new(DAY * 3, 2.0).unwrap();
+
+        ce.next((day(0), &Bar::new().high(12).low(9).close(10)));
+        ce.next((day(1), &Bar::new().high(20).low(5).close(12)));
+        // A revision of day 1 replaces its high and low
+        let revision = day(1) + chrono::Duration::hours(1);
+        let bar = Bar::new().high(13).low(10).close(12);
+        assert_eq!(rounded(ce.next((revision, &bar))), (7.0, 15.0));
+        assert_eq!(ce.sample_count(), 2);
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut ce = ChandelierExit::new(DAY * 2, 3.0).unwrap();
+        assert_eq!(ce.current(), None);
+
+        let output = ce.next((day(0), &Bar::new().high(12).low(9).close(10)));
+        assert_eq!(ce.current(), Some(output));
+        assert!(!ce.is_ready());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut ce = ChandelierExit::new(DAY * 3, 2.0).unwrap();
+
+        ce.next((day(0), 10.0));
+        ce.next((day(1), 14.0));
+        ce.reset();
+        assert_eq!(rounded(ce.next((day(2), 10.0))), (10.0, 10.0));
+    }
+
+    #[test]
+    fn test_default() {
+        ChandelierExit::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let ce = ChandelierExit::new(DAY * 22, 3.0).unwrap();
+        assert_eq!(format!("{}", ce), "CE(22 days, 3)");
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, MeanAbsoluteDeviation as Mad};
+use crate::{Close, High, Low, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Commodity Channel Index (CCI).
+///
+/// How far the typical price `(high + low + close) / 3` is from its mean over the time window,
+/// in units of its mean absolute deviation:
+///
+/// `CCI = (typical price - mean) / (0.015 * mean absolute deviation)`
+///
+/// The constant 0.015 puts most values between -100 and +100. The CCI is 0 while the deviation
+/// is 0, e.g. for the first value. Fed with `f64` values, the value is used as the typical price.
+///
+/// The mean and the deviation come from a [MeanAbsoluteDeviation](struct.MeanAbsoluteDeviation.html)
+/// of the typical prices, so updates within the same time bucket replace the bucket's price.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::CommodityChannelIndex;
+/// use ta::Next;
+///
+/// let mut cci = CommodityChannelIndex::new(Duration::from_secs(3 * 86400)).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+///
+/// assert_eq!(cci.next((start, 10.0)), 0.0);
+/// let value = cci.next((start + ChronoDuration::days(1), 12.0));
+/// assert!((value - 66.667).abs() < 1e-3);
+/// ```
+#[doc(alias = "CCI")]
+#[derive(Debug, Clone)]
+pub struct CommodityChannelIndex {
+    mad: Mad,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "CommodityChannelIndex")]
+struct StateV1 {
+    mad: Mad,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(CommodityChannelIndex, StateV1, 1);
+
+impl CommodityChannelIndex {
+    pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            mad: Mad::with_policy(duration, policy)?,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.mad.duration()
+    }
+
+    fn index(&self, typical_price: f64) -> f64 {
+        let (mean, mad) = self.mad.mean_and_deviation();
+        if mad == 0.0 {
+            0.0
+        } else {
+            (typical_price - mean) / (0.015 * mad)
+        }
+    }
+}
+
+impl Next<f64> for CommodityChannelIndex {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        self.mad.push(timestamp, value);
+        self.index(value)
+    }
+}
+
+impl<T: High + Low + Close> Next<&T> for CommodityChannelIndex {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        let typical_price = (input.high() + input.low() + input.close()) / 3.0;
+        self.next((timestamp, typical_price))
+    }
+}
+
+impl Validate for CommodityChannelIndex {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.mad.latest_time()
+    }
+}
+
+impl Peek for CommodityChannelIndex {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        Some(self.index(self.mad.latest()?))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.mad.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.mad.sample_count()
+    }
+}
+
+impl Reset for CommodityChannelIndex {
+    fn reset(&mut self) {
+        self.mad.reset();
+    }
+}
+
+impl Default for CommodityChannelIndex {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(20 * 24 * 60 * 60)).unwrap()
+    }
+}
+
+impl fmt::Display for CommodityChannelIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CCI({})", format_duration(self.duration()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+
+    test_indicator!(CommodityChannelIndex);
+
+    #[test]
+    fn test_new() {
+        assert!(CommodityChannelIndex::new(Duration::from_secs(0)).is_err());
+        assert!(CommodityChannelIndex::new(DAY).is_ok());
+    }
+
+    #[test]
+    fn test_next() {
+        let mut cci = CommodityChannelIndex::new(DAY * 3).unwrap();
+
+        let bars = [
+            ((12, 9, 10), 0.0),
+            ((13, 10, 12), 66.667),
+            ((15, 11, 14), 100.0),
+            // Day 0 has left the window
+            ((12, 10, 11), -75.0),
+        ];
+        for (n, ((high, low, close), expected)) in bars.into_iter().enumerate() {
+            let bar = Bar::new().high(high).low(low).close(close);
+            assert_eq!(round(cci.next((day(n as i64), &bar))), expected);
+        }
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut cci = CommodityChannelIndex::new(DAY * 3).unwrap();
+
+        cci.next((day(0), 10.0));
+        cci.next((day(1), 20.0));
+        let revision = day(1) + chrono::Duration::hours(1);
+        assert_eq!(round(cci.next((revision, 12.0))), 66.667);
+        assert_eq!(cci.sample_count(), 2);
+    }
+
+    #[test]
+    fn test_flat_window_after_large_value() {
+        let mut cci = CommodityChannelIndex::new(DAY * 5).unwrap();
+
+        for (n, value) in [0.1, 0.7, 0.3, 1e6, 0.2].into_iter().enumerate() {
+            cci.next((day(n as i64), value));
+        }
+        // Once the window only holds 0.3 it is flat, whatever passed through it before
+        for n in 5..20 {
+            let value = cci.next((day(n), 0.3));
+            if n >= 9 {
+                assert_eq!(value, 0.0);
+            }
+        }
+        assert_eq!(cci.current(), Some(0.0));
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut cci = CommodityChannelIndex::new(DAY * 2).unwrap();
+        assert_eq!(cci.current(), None);
+
+        cci.next((day(0), 10.0));
+        let value = cci.next((day(1), 12.0));
+        assert_eq!(cci.current(), Some(value));
+        assert!(!cci.is_ready());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut cci = CommodityChannelIndex::new(DAY * 3).unwrap();
+
+        cci.next((day(0), 10.0));
+        cci.next((day(1), 12.0));
+        cci.reset();
+        assert_eq!(cci.next((day(2), 14.0)), 0.0);
+    }
+
+    #[test]
+    fn test_default() {
+        CommodityChannelIndex::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let cci = CommodityChannelIndex::new(DAY * 20).unwrap();
+        assert_eq!(format!("{}", cci), "CCI(20 days)");
+    }
+}
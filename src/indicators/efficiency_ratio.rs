@@ -0,0 +1,283 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{Aggregate, BucketPolicy, TimeWindow};
+use crate::{Close, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Kaufman's Efficiency Ratio (ER).
+///
+/// How directly the price moved over the time window: the net change divided by the sum of the
+/// absolute changes along the way,
+///
+/// `ER = |latest - value duration ago| / sum of |value - previous value|`
+///
+/// It is 1 for a straight move and approaches 0 for noise. Like in
+/// [RateOfChange](struct.RateOfChange.html), the value `duration` ago is the latest one that has
+/// left the window, or the oldest value of the window until one has. The ER is 0 while there is
+/// no change.
+///
+/// Updates within the same time bucket replace the latest value, so the path runs through the
+/// last value of each bucket only.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::EfficiencyRatio;
+/// use ta::Next;
+///
+/// let mut er = EfficiencyRatio::new(Duration::from_secs(3 * 86400)).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+/// let day = |n| start + ChronoDuration::days(n);
+///
+/// assert_eq!(er.next((day(0), 10.0)), 0.0);
+/// assert_eq!(er.next((day(1), 11.0)), 1.0);
+/// // A net change of 1.5 along a path of 1 + 0.5 + 1
+/// er.next((day(2), 10.5));
+/// assert_eq!(er.next((day(3), 11.5)), 0.6);
+/// ```
+#[doc(alias = "ER")]
+#[derive(Debug, Clone)]
+pub struct EfficiencyRatio {
+    window: TimeWindow<Path>,
+}
+
+// The sum of the absolute changes from the latest value evicted from the window, or the oldest
+// value of the window, to the latest one
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+struct Path {
+    last_evicted: Option<f64>,
+    length: f64,
+}
+
+impl Path {
+    // The value before the back of `window`
+    fn before_back(&self, window: &VecDeque<(DateTime<Utc>, f64)>) -> Option<f64> {
+        match window.len() {
+            0 | 1 => self.last_evicted,
+            len => Some(window[len - 2].1),
+        }
+    }
+}
+
+impl Aggregate for Path {
+    fn push(&mut self, (_, value): (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        if let Some(prev) = self.before_back(window) {
+            self.length += (value - prev).abs();
+        }
+    }
+
+    fn evict(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        if let Some(prev) = self.last_evicted {
+            self.length -= (value - prev).abs();
+        }
+        self.last_evicted = Some(value);
+    }
+
+    fn pop_back(
+        &mut self,
+        (_, value): (DateTime<Utc>, f64),
+        window: &VecDeque<(DateTime<Utc>, f64)>,
+    ) {
+        let prev = window.back().map(|&(_, prev)| prev).or(self.last_evicted);
+        if let Some(prev) = prev {
+            self.length -= (value - prev).abs();
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "EfficiencyRatio")]
+struct StateV1 {
+    window: TimeWindow<Path>,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(EfficiencyRatio, StateV1, 1);
+
+impl EfficiencyRatio {
+    pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            window: TimeWindow::with_policy(duration, policy)?,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.window.duration()
+    }
+
+    fn ratio(&self) -> f64 {
+        let path = self.window.aggregate();
+        let reference = match path.last_evicted {
+            Some(evicted) => evicted,
+            None => self.window.values()[0].1,
+        };
+        let latest = self.window.latest().expect("Window has at least one item");
+
+        // The path drifts by rounding errors as changes leave the window
+        let change = (latest - reference).abs();
+        if change == 0.0 || path.length <= 0.0 {
+            0.0
+        } else {
+            (change / path.length).min(1.0)
+        }
+    }
+}
+
+impl Next<f64> for EfficiencyRatio {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        self.window.push(timestamp, value);
+        self.ratio()
+    }
+}
+
+impl<T: Close> Next<&T> for EfficiencyRatio {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for EfficiencyRatio {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.window.latest_time()
+    }
+}
+
+impl Peek for EfficiencyRatio {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        if self.window.is_empty() {
+            None
+        } else {
+            Some(self.ratio())
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.window.bucket_count()
+    }
+}
+
+impl Reset for EfficiencyRatio {
+    fn reset(&mut self) {
+        self.window.reset();
+    }
+}
+
+impl Default for EfficiencyRatio {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10 * 24 * 60 * 60)).unwrap()
+    }
+}
+
+impl fmt::Display for EfficiencyRatio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ER({})", format_duration(self.duration()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+
+    test_indicator!(EfficiencyRatio);
+
+    #[test]
+    fn test_new() {
+        assert!(EfficiencyRatio::new(Duration::from_secs(0)).is_err());
+        assert!(EfficiencyRatio::new(DAY).is_ok());
+    }
+
+    #[test]
+    fn test_next() {
+        let mut er = EfficiencyRatio::new(DAY * 3).unwrap();
+
+        let values = [
+            (10.0, 0.0),
+            (11.0, 1.0),
+            (10.5, 0.333),
+            // Measured from day 0, which has left the window
+            (11.5, 0.6),
+            (11.0, 0.0),
+            (12.0, 0.6),
+        ];
+        for (n, (value, expected)) in values.into_iter().enumerate() {
+            assert_eq!(round(er.next((day(n as i64), value))), expected);
+        }
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut er = EfficiencyRatio::new(DAY * 3).unwrap();
+
+        er.next((day(0), 10.0));
+        er.next((day(1), 11.0));
+        // The revision replaces the step to 11
+        let revision = day(1) + chrono::Duration::hours(1);
+        assert_eq!(er.next((revision, 9.0)), 1.0);
+        assert_eq!(er.next((day(2), 10.0)), 0.0);
+        assert_eq!(round(er.next((day(3), 12.0))), 0.5);
+        assert_eq!(er.sample_count(), 4);
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut er = EfficiencyRatio::new(DAY * 2).unwrap();
+        assert_eq!(er.current(), None);
+
+        er.next((day(0), 10.0));
+        let value = er.next((day(1), 12.0));
+        assert_eq!(er.current(), Some(value));
+        assert!(!er.is_ready());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut er = EfficiencyRatio::new(DAY * 3).unwrap();
+
+        er.next((day(0), 10.0));
+        er.next((day(1), 12.0));
+        er.reset();
+        assert_eq!(er.next((day(2), 14.0)), 0.0);
+        assert_eq!(er.next((day(3), 13.0)), 1.0);
+    }
+
+    #[test]
+    fn test_default() {
+        EfficiencyRatio::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let er = EfficiencyRatio::new(DAY * 10).unwrap();
+        assert_eq!(format!("{}", er), "ER(10 days)");
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use super::adaptive::LegacyDetector;
+use crate::errors::Result;
+use crate::helpers::Extreme;
+#[cfg(feature = "serde")]
+use crate::helpers::MonotonicDeque;
+use crate::indicators::{Aggregate, BucketPolicy, TimeWindow};
+use crate::Reset;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A value of the window.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Point {
+    pub time: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// The largest move of the window, from the running extreme `start` to `end`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Move {
+    pub start: Point,
+    pub end: Point,
+    pub size: f64,
+}
+
+// What a scan of consecutive values finds, by their positions in the stream
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    // Running extreme after the last value, the latest of equal ones
+    extreme: u64,
+    // The earliest of the values furthest from the extreme
+    furthest: u64,
+    // Largest move, the earliest to end of equal ones
+    start: u64,
+    end: u64,
+    size: f64,
+    // First value after the end of the largest move that got back to its start
+    recovery: Option<u64>,
+}
+
+// Which move of two runs is the largest move of both
+enum Largest {
+    Older,
+    Across,
+    Newer,
+}
+
+/// Largest move of a window, kept as two stacks of runs: the older values from each of them to
+/// the newest of the older part, and the newer values from the oldest of the newer part to each
+/// of them. A new value extends the newer part; evicting pops the older part, which is filled
+/// again from the newer part once empty, so every value moves between the parts only once.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub(super) struct Excursions {
+    extreme: Extreme,
+    next_index: u64,
+    // Oldest last
+    older: Vec<Run>,
+    // Oldest first
+    newer: Vec<Run>,
+    // Values of the newer part beyond all the values before them, to find where it gets back
+    // to a start
+    records: Vec<u64>,
+    total: Option<Run>,
+}
+
+impl Excursions {
+    pub fn new(extreme: Extreme) -> Self {
+        Self {
+            extreme,
+            next_index: 0,
+            older: Vec::new(),
+            newer: Vec::new(),
+            records: Vec::new(),
+            total: None,
+        }
+    }
+
+    fn value(&self, index: u64, window: &VecDeque<(DateTime<Utc>, f64)>) -> f64 {
+        let first_index = self.next_index - window.len() as u64;
+        window[(index - first_index) as usize].1
+    }
+
+    fn size(&self, start: f64, value: f64) -> f64 {
+        match self.extreme {
+            _ if start == value => 0.0,
+            Extreme::Max => (start - value) / start,
+            Extreme::Min => (value - start) / start,
+        }
+    }
+
+    fn single(&self, index: u64, window: &VecDeque<(DateTime<Utc>, f64)>) -> Run {
+        let value = self.value(index, window);
+        Run {
+            extreme: index,
+            furthest: index,
+            start: index,
+            end: index,
+            size: self.size(value, value),
+            recovery: None,
+        }
+    }
+
+    // The run of the values of `older` followed by those of `newer`. `first_back` finds the
+    // first value of `newer` that dominates a value.
+    fn combine(
+        &self,
+        older: &Run,
+        newer: &Run,
+        window: &VecDeque<(DateTime<Utc>, f64)>,
+        first_back: impl FnOnce(f64) -> Option<u64>,
+    ) -> Run {
+        let value = |index| self.value(index, window);
+        let extreme = if self
+            .extreme
+            .dominates(value(newer.extreme), value(older.extreme))
+        {
+            newer.extreme
+        } else {
+            older.extreme
+        };
+        let further = match self.extreme {
+            Extreme::Max => value(newer.furthest) < value(older.furthest),
+            Extreme::Min => value(newer.furthest) > value(older.furthest),
+        };
+        let furthest = if further {
+            newer.furthest
+        } else {
+            older.furthest
+        };
+
+        // A move from the extreme of `older` can only be larger than the ones of `newer` if it
+        // ends before `newer` got back there, and then it is largest to the furthest value
+        let across = self.size(value(older.extreme), value(newer.furthest));
+        let (size, largest) =
+            if across > newer.size || (across == newer.size && newer.furthest < newer.end) {
+                (across, Largest::Across)
+            } else {
+                (newer.size, Largest::Newer)
+            };
+        let largest = if size > older.size {
+            largest
+        } else {
+            Largest::Older
+        };
+
+        match largest {
+            Largest::Older => Run {
+                extreme,
+                furthest,
+                recovery: match older.recovery {
+                    None if older.size > 0.0 => first_back(value(older.start)),
+                    recovery => recovery,
+                },
+                ..*older
+            },
+            Largest::Across => Run {
+                extreme,
+                furthest,
+                start: older.extreme,
+                end: newer.furthest,
+                size: across,
+                recovery: if across > 0.0 {
+                    first_back(value(older.extreme))
+                } else {
+                    None
+                },
+            },
+            Largest::Newer => Run {
+                extreme,
+                furthest,
+                ..*newer
+            },
+        }
+    }
+
+    // Moves the values of the newer part but the newest to the older part
+    fn refill(&mut self, window: &VecDeque<(DateTime<Utc>, f64)>) {
+        let newest = self.next_index - 1;
+        let first_index = self.next_index - window.len() as u64;
+        self.newer.clear();
+        self.records.clear();
+
+        // The later values that no value between them and the current one dominates, the
+        // closest last: the first of them that dominates the current value is where it recovers
+        let mut ahead: Vec<u64> = Vec::new();
+        let mut run: Option<Run> = None;
+        for index in (first_index..newest).rev() {
+            let value = self.value(index, window);
+            while ahead
+                .last()
+                .is_some_and(|&later| !self.extreme.dominates(self.value(later, window), value))
+            {
+                ahead.pop();
+            }
+            let first = ahead.last().copied();
+            let single = self.single(index, window);
+            let combined = match run {
+                Some(run) => self.combine(&single, &run, window, |_| first),
+                None => single,
+            };
+            self.older.push(combined);
+            run = Some(combined);
+            ahead.push(index);
+        }
+        self.newer.push(self.single(newest, window));
+        self.records.push(newest);
+    }
+
+    // The first value of the newer part that dominates `value`
+    fn first_dominating(&self, value: f64, window: &VecDeque<(DateTime<Utc>, f64)>) -> Option<u64> {
+        let offset = self
+            .records
+            .partition_point(|&index| !self.extreme.dominates(self.value(index, window), value));
+        self.records.get(offset).copied()
+    }
+
+    fn largest(&self) -> Option<&Run> {
+        self.total.as_ref()
+    }
+
+    fn clear_parts(&mut self) {
+        self.older.clear();
+        self.newer.clear();
+        self.records.clear();
+        self.total = None;
+    }
+}
+
+impl Aggregate for Excursions {
+    fn push(&mut self, (_, value): (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        let index = self.next_index;
+        self.next_index += 1;
+        let single = self.single(index, window);
+        let run = match self.newer.last() {
+            Some(newer) => self.combine(newer, &single, window, |start| {
+                self.extreme.dominates(value, start).then_some(index)
+            }),
+            None => single,
+        };
+        if self.records.last().map_or(true, |&record| {
+            !self.extreme.dominates(self.value(record, window), value)
+        }) {
+            self.records.push(index);
+        }
+        self.newer.push(run);
+
+        self.total = Some(match self.older.last() {
+            Some(older) => self.combine(older, &run, window, |start| {
+                self.first_dominating(start, window)
+            }),
+            None => run,
+        });
+    }
+
+    fn evict(&mut self, _: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        if self.older.pop().is_none() {
+            if window.is_empty() {
+                self.clear_parts();
+            } else {
+                self.refill(window);
+            }
+        }
+    }
+
+    fn pop_back(&mut self, _: (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.next_index -= 1;
+        self.newer.pop();
+        if self.records.last() == Some(&self.next_index) {
+            self.records.pop();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.next_index = 0;
+        self.clear_parts();
+    }
+}
+
+/// Sliding window engine shared by [MaxDrawdown](struct.MaxDrawdown.html) (moves away from the
+/// running maximum) and [MaxDrawup](struct.MaxDrawup.html) (moves away from the running minimum).
+///
+/// Each value updates the largest move in amortised O(1), plus a binary search for where the
+/// window got back to its start, whichever values leave the window. Replacing the last value of
+/// a time bucket drops it from the newer runs before the new value is added.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub(super) struct ExcursionWindow {
+    window: TimeWindow<Excursions>,
+}
+
+impl ExcursionWindow {
+    pub fn new(duration: Duration, policy: BucketPolicy, extreme: Extreme) -> Result<Self> {
+        Ok(Self {
+            window: TimeWindow::with_aggregate(duration, policy, Excursions::new(extreme))?,
+        })
+    }
+
+    /// Rebuilds the window of a drawdown or drawup serialized by ta 0.5 from the values it kept.
+    #[cfg(feature = "serde")]
+    pub fn from_legacy(
+        duration: Duration,
+        extreme: Extreme,
+        values: VecDeque<(DateTime<Utc>, f64)>,
+        detector: LegacyDetector,
+    ) -> Result<Self> {
+        // The values are one per bucket already
+        let mut excursion = Self::new(duration, BucketPolicy::NeverReplace, extreme)?;
+        for &(time, value) in &values {
+            excursion.push(time, value);
+        }
+        excursion.window.restore_detector(detector);
+        Ok(excursion)
+    }
+
+    pub fn window(&self) -> &TimeWindow<Excursions> {
+        &self.window
+    }
+
+    /// Adds a value, evicting the old ones and replacing the last one of the same time bucket.
+    pub fn push(&mut self, time: DateTime<Utc>, value: f64) {
+        self.window.push(time, value);
+    }
+
+    /// The largest move of the window, `None` while it is empty.
+    pub fn largest(&self) -> Option<Move> {
+        self.window.aggregate().largest().map(|run| Move {
+            start: self.point(run.start),
+            end: self.point(run.end),
+            size: run.size,
+        })
+    }
+
+    /// When the value got back to the start of the largest move, if it did within the window.
+    pub fn recovery(&self) -> Option<Point> {
+        self.window
+            .aggregate()
+            .largest()
+            .and_then(|run| run.recovery)
+            .map(|index| self.point(index))
+    }
+
+    /// The move of the last value away from the extreme of the window.
+    pub fn current(&self) -> f64 {
+        let excursions = self.window.aggregate();
+        match (excursions.largest(), self.window.latest()) {
+            (Some(run), Some(value)) => excursions.size(self.point(run.extreme).value, value),
+            _ => 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.window.reset();
+    }
+
+    fn point(&self, index: u64) -> Point {
+        let (time, value) = self.window.values()[(index - self.first_index()) as usize];
+        Point { time, value }
+    }
+
+    fn first_index(&self) -> u64 {
+        self.window.aggregate().next_index - self.window.len() as u64
+    }
+}
+
+// The state of version 1, found again from the values
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct PointV1 {
+    index: u64,
+    time: DateTime<Utc>,
+    value: f64,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct MoveV1 {
+    start: PointV1,
+    end: PointV1,
+    size: f64,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct StateV1 {
+    largest: Option<MoveV1>,
+    recovery: Option<PointV1>,
+}
+
+/// The layout of version 1 of [MaxDrawdown](struct.MaxDrawdown.html) and
+/// [MaxDrawup](struct.MaxDrawup.html), which rescanned the window when the start of the largest
+/// move left it.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[allow(dead_code)] // only the values and the bucketing are kept
+pub(super) struct ExcursionWindowV1 {
+    extreme: Extreme,
+    window: TimeWindow<MonotonicDeque>,
+    next_index: u64,
+    state: StateV1,
+    before_last: StateV1,
+}
+
+#[cfg(feature = "serde")]
+impl ExcursionWindowV1 {
+    pub fn migrate(self) -> ExcursionWindow {
+        ExcursionWindow {
+            window: self.window.rebuild(Excursions::new(self.extreme)),
+        }
+    }
+}
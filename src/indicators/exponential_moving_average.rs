@@ -2,28 +2,144 @@ use std::fmt;
 use std::time::Duration;
 
 use crate::errors::{Result, TaError};
-use crate::indicators::AdaptiveTimeDetector;
-use crate::{Next, Reset};
+use crate::helpers::format_duration;
+use crate::indicators::{AdaptiveTimeDetector, BucketPolicy};
+use crate::{Close, Next, Peek, Reset, Validate};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
 use std::collections::VecDeque;
 
+/// Exponential moving average (EMA) over a time window.
+///
+/// Three smoothings are available:
+///
+/// * [new](#method.new) - the factor `2 / (days + 1)` applied once per time bucket
+/// * [wilder](#method.wilder) - Wilder's factor `1 / periods` applied once per time bucket, where
+///   `periods` is the number of time buckets in the duration
+/// * [time_decay](#method.time_decay) - the factor `1 - exp(-dt / duration)` from the time `dt`
+///   elapsed since the previous bucket, so the result does not depend on how often data arrives
+///
+/// An update within the same time bucket as the previous one replaces it.
 #[doc(alias = "EMA")]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct ExponentialMovingAverage {
-    duration: Duration, // Now std::time::Duration
+    duration: Duration,
+    k: f64,
+    current: f64,
+    is_new: bool,
+    detector: AdaptiveTimeDetector,
+    last_value: f64,
+    // Wilder only: how many buckets are averaged before switching to the factor `k`, and how
+    // many buckets have been seen
+    seed_periods: Option<usize>,
+    count: usize,
+    // Time decay only: the time constant in seconds and the times of the last updates of the
+    // previous and current buckets
+    tau: Option<f64>,
+    // The EMA before the current bucket, for Wilder and time decay
+    previous: f64,
+    previous_time: Option<DateTime<Utc>>,
+    last_time: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ExponentialMovingAverage")]
+struct StateV2 {
+    duration: Duration,
+    k: f64,
+    current: f64,
+    is_new: bool,
+    detector: AdaptiveTimeDetector,
+    last_value: f64,
+    seed_periods: Option<usize>,
+    count: usize,
+    tau: Option<f64>,
+    previous: f64,
+    previous_time: Option<DateTime<Utc>>,
+    last_time: Option<DateTime<Utc>>,
+}
+
+// The layout of version 1, with a window that was never filled
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct StateV1 {
+    duration: Duration,
     k: f64,
+    #[allow(dead_code)] // never filled
     window: VecDeque<(DateTime<Utc>, f64)>,
     current: f64,
     is_new: bool,
     detector: AdaptiveTimeDetector,
     last_value: f64,
+    seed_periods: Option<usize>,
+    count: usize,
+    tau: Option<f64>,
+    previous: f64,
+    previous_time: Option<DateTime<Utc>>,
+    last_time: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<ExponentialMovingAverage> for StateV1 {
+    fn migrate(self) -> Result<ExponentialMovingAverage> {
+        Ok(ExponentialMovingAverage {
+            duration: self.duration,
+            k: self.k,
+            current: self.current,
+            is_new: self.is_new,
+            detector: self.detector,
+            last_value: self.last_value,
+            seed_periods: self.seed_periods,
+            count: self.count,
+            tau: self.tau,
+            previous: self.previous,
+            previous_time: self.previous_time,
+            last_time: self.last_time,
+        })
+    }
 }
 
+// The unversioned layout of ta 0.5, also nested in its RSI
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+pub(super) struct LegacyState {
+    duration: Duration,
+    k: f64,
+    #[allow(dead_code)] // never filled
+    window: VecDeque<(DateTime<Utc>, f64)>,
+    current: f64,
+    is_new: bool,
+    detector: super::adaptive::LegacyDetector,
+    last_value: f64,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<ExponentialMovingAverage> for LegacyState {
+    fn migrate(self) -> Result<ExponentialMovingAverage> {
+        let mut ema = ExponentialMovingAverage::new(self.duration)?;
+        ema.k = self.k;
+        ema.current = self.current;
+        ema.is_new = self.is_new;
+        ema.last_value = self.last_value;
+        // Only the bucketing survives, the history since the start was not kept
+        ema.detector = self.detector.migrate()?;
+        Ok(ema)
+    }
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(ExponentialMovingAverage, StateV2, 2, 0 => LegacyState, 1 => StateV1);
+
 impl ExponentialMovingAverage {
     pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
         // std::time::Duration can't be negative, so just check if it's zero
         if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
             Err(TaError::InvalidParameter)
@@ -33,29 +149,111 @@ impl ExponentialMovingAverage {
             Ok(Self {
                 duration,
                 k: 2.0 / (days + 1.0),
-                window: VecDeque::new(),
                 current: 0.0,
                 is_new: true,
-                detector: AdaptiveTimeDetector::new(duration),
+                detector: AdaptiveTimeDetector::with_policy(duration, policy)?,
                 last_value: 0.0,
+                seed_periods: None,
+                count: 0,
+                tau: None,
+                previous: 0.0,
+                previous_time: None,
+                last_time: None,
             })
         }
     }
 
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // EMA doesn't actually need to remove old data
-        // It's a running average that only depends on the current state
-        // Keeping the window for potential debugging, but not removing data
-        // This was causing issues with RSI calculations
+    /// Creates an EMA with Wilder's smoothing factor `1 / periods` instead of `2 / (days + 1)`,
+    /// also known as a running moving average (RMA). `periods` is the duration over the width of
+    /// the time buckets, e.g. 14 for 14 minutes in 1 minute buckets, see
+    /// [AdaptiveTimeDetector::periods](struct.AdaptiveTimeDetector.html#method.periods).
+    ///
+    /// As in Wilder's definition, the first `periods` buckets are averaged with a simple mean
+    /// before the smoothing starts, which is what charting platforms do as well.
+    ///
+    /// Fails if the duration is shorter than one time bucket.
+    pub fn wilder(duration: Duration) -> Result<Self> {
+        Self::wilder_with_policy(duration, BucketPolicy::Heuristic)
+    }
 
-        // Original code commented out:
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time <= current_time - self.duration)
-        {
-            self.window.pop_front();
+    /// Like [wilder](#method.wilder), with the time buckets set by `policy` instead of `duration`.
+    pub fn wilder_with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        let mut ema = Self::with_policy(duration, policy)?;
+        let periods = ema.detector.periods(duration);
+        if periods < 1.0 {
+            return Err(TaError::InvalidParameter);
         }
+        ema.k = 1.0 / periods;
+        ema.seed_periods = Some(periods.round() as usize);
+        Ok(ema)
+    }
+
+    /// Creates an EMA that decays with elapsed time: each bucket moves the average towards the
+    /// new value by `1 - exp(-dt / duration)`, where `dt` is the time since the last update of
+    /// the previous bucket. Feeding the same signal every second or every minute gives the
+    /// same result.
+    pub fn time_decay(duration: Duration) -> Result<Self> {
+        Self::time_decay_with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [time_decay](#method.time_decay), with the time buckets set by `policy` instead of
+    /// `duration`.
+    pub fn time_decay_with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        let mut ema = Self::with_policy(duration, policy)?;
+        ema.tau = Some(duration.as_secs_f64());
+        Ok(ema)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn next_wilder(&mut self, value: f64, should_replace: bool, seed_periods: usize) -> f64 {
+        // Like time decay, replacing starts again from the state before the bucket
+        if !should_replace || self.is_new {
+            self.previous = self.current;
+            self.count += 1;
+        }
+
+        let k = if self.count <= seed_periods {
+            1.0 / self.count as f64
+        } else {
+            self.k
+        };
+        self.current = self.previous + k * (value - self.previous);
+
+        self.is_new = false;
+        self.last_value = value;
+        self.current
+    }
+
+    fn next_time_decay(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        value: f64,
+        should_replace: bool,
+        tau: f64,
+    ) -> f64 {
+        // Replacing goes back to the state before the bucket instead of reversing the step,
+        // which would divide by exp(-dt / tau) and lose all precision after long gaps
+        if !should_replace || self.is_new {
+            self.previous = self.current;
+            self.previous_time = self.last_time;
+        }
+
+        self.current = match self.previous_time {
+            Some(previous_time) => {
+                let dt = (timestamp - previous_time).num_milliseconds().max(0) as f64 / 1000.0;
+                let alpha = 1.0 - (-dt / tau).exp();
+                self.previous + alpha * (value - self.previous)
+            }
+            None => value,
+        };
+
+        self.is_new = false;
+        self.last_time = Some(timestamp);
+        self.last_value = value;
+        self.current
     }
 }
 
@@ -66,6 +264,13 @@ impl Next<f64> for ExponentialMovingAverage {
         // Check if we should replace the last value (same time bucket)
         let should_replace = self.detector.should_replace(timestamp);
 
+        if let Some(tau) = self.tau {
+            return self.next_time_decay(timestamp, value, should_replace, tau);
+        }
+        if let Some(seed_periods) = self.seed_periods {
+            return self.next_wilder(value, should_replace, seed_periods);
+        }
+
         if should_replace && !self.is_new {
             // Reverse the previous EMA calculation and apply new value
             // Previous: current = k * last_value + (1-k) * old_current
@@ -94,13 +299,50 @@ impl Next<f64> for ExponentialMovingAverage {
     }
 }
 
+impl<T: Close> Next<&T> for ExponentialMovingAverage {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for ExponentialMovingAverage {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.detector.latest()
+    }
+}
+
+impl Peek for ExponentialMovingAverage {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        if self.is_new {
+            None
+        } else {
+            Some(self.current)
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.detector.spans(self.duration)
+    }
+
+    fn sample_count(&self) -> usize {
+        self.detector.bucket_count()
+    }
+}
+
 impl Reset for ExponentialMovingAverage {
     fn reset(&mut self) {
-        self.window.clear();
         self.current = 0.0;
         self.is_new = true;
         self.detector.reset();
         self.last_value = 0.0;
+        self.count = 0;
+        self.previous = 0.0;
+        self.previous_time = None;
+        self.last_time = None;
     }
 }
 
@@ -113,11 +355,9 @@ impl Default for ExponentialMovingAverage {
 
 impl fmt::Display for ExponentialMovingAverage {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let days = self.duration.as_secs() / 86400;
-        if days > 0 && self.duration.as_secs() % 86400 == 0 {
-            write!(f, "EMA({} days)", days)
-        } else {
-            write!(f, "EMA({}s)", self.duration.as_secs())
+        match self.tau {
+            Some(_) => write!(f, "EMA({}, time decay)", format_duration(self.duration)),
+            None => write!(f, "EMA({})", format_duration(self.duration)),
         }
     }
 }
@@ -125,7 +365,8 @@ impl fmt::Display for ExponentialMovingAverage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
+    use crate::test_helper::*;
+    use chrono::{TimeZone, Utc};
 
     #[test]
     fn test_new() {
@@ -145,6 +386,99 @@ mod tests {
         assert_eq!(ema.next((now + chrono::Duration::days(3), 6.25)), 4.25);
     }
 
+    #[test]
+    fn test_wilder() {
+        assert!(ExponentialMovingAverage::wilder(Duration::from_secs(0)).is_err());
+
+        let mut ema = ExponentialMovingAverage::wilder(Duration::from_secs(4 * 86400)).unwrap(); // 4 days
+        let now = Utc::now();
+
+        // Simple mean of the first 4 days
+        assert_eq!(ema.next((now, 2.0)), 2.0);
+        assert_eq!(ema.next((now + chrono::Duration::days(1), 6.0)), 4.0);
+        assert_eq!(ema.next((now + chrono::Duration::days(2), 7.0)), 5.0);
+        assert_eq!(ema.next((now + chrono::Duration::days(3), 9.0)), 6.0);
+        // Then the factor 1 / 4
+        assert_eq!(ema.next((now + chrono::Duration::days(4), 2.0)), 5.0);
+        // Replacing within the bucket restarts from the previous day
+        assert_eq!(
+            ema.next((
+                now + chrono::Duration::days(4) + chrono::Duration::hours(1),
+                10.0
+            )),
+            7.0
+        );
+        assert_eq!(ema.next((now + chrono::Duration::days(5), 3.0)), 6.0);
+    }
+
+    #[test]
+    fn test_time_decay() {
+        assert!(ExponentialMovingAverage::time_decay(Duration::from_secs(0)).is_err());
+
+        let mut ema = ExponentialMovingAverage::time_decay(Duration::from_secs(86400)).unwrap();
+        let now = Utc::now();
+
+        assert_eq!(ema.next((now, 0.0)), 0.0);
+        // 1 - e^-1 of the way after one time constant
+        assert_eq!(
+            round(ema.next((now + chrono::Duration::days(1), 10.0))),
+            6.321
+        );
+        // A gap of two days moves 1 - e^-2 of the remaining way
+        assert_eq!(
+            round(ema.next((now + chrono::Duration::days(3), 10.0))),
+            9.502
+        );
+    }
+
+    #[test]
+    fn test_time_decay_sampling_invariance() {
+        let duration = Duration::from_secs(15 * 60);
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+        let signal = |secs: i64| if secs < 600 { 100.0 } else { 110.0 };
+
+        for policy in [BucketPolicy::Heuristic, BucketPolicy::NeverReplace] {
+            let mut every_second =
+                ExponentialMovingAverage::time_decay_with_policy(duration, policy.clone()).unwrap();
+            let mut every_minute =
+                ExponentialMovingAverage::time_decay_with_policy(duration, policy).unwrap();
+            let mut by_minute = 0.0;
+
+            for secs in 0..3600 {
+                let time = start + chrono::Duration::seconds(secs);
+                let by_second = every_second.next((time, signal(secs)));
+                if secs % 60 == 59 {
+                    by_minute = every_minute.next((time, signal(secs)));
+                    assert!((by_second - by_minute).abs() < 1e-9);
+                }
+            }
+
+            // 50 minutes after the last value before the step, with a time constant of 15 minutes
+            let expected = 110.0 - 10.0 * (-3000.0_f64 / 900.0).exp();
+            assert!((by_minute - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_time_decay_replacement() {
+        let day = Duration::from_secs(86400);
+        let mut ema = ExponentialMovingAverage::time_decay(day).unwrap();
+        let mut expected = ExponentialMovingAverage::time_decay(day).unwrap();
+        let open = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+
+        ema.next((open, 100.0));
+        expected.next((open, 100.0));
+
+        // Intraday updates of the next session revise the same slot; even after a gap long
+        // enough to forget the past entirely, nothing is lost when revising
+        let next = open + chrono::Duration::days(40);
+        ema.next((next, 300.0));
+        ema.next((next + chrono::Duration::minutes(30), 120.0));
+        let revised = ema.next((next + chrono::Duration::hours(1), 110.0));
+        let fresh = expected.next((next + chrono::Duration::hours(1), 110.0));
+        assert_eq!(revised, fresh);
+    }
+
     #[test]
     fn test_reset() {
         let mut ema = ExponentialMovingAverage::new(Duration::from_secs(5 * 86400)).unwrap(); // 5 days
@@ -169,5 +503,7 @@ mod tests {
     fn test_display() {
         let ema = ExponentialMovingAverage::new(Duration::from_secs(7 * 86400)).unwrap(); // 7 days
         assert_eq!(format!("{}", ema), "EMA(7 days)");
+        let ema = ExponentialMovingAverage::time_decay(Duration::from_secs(900)).unwrap();
+        assert_eq!(format!("{}", ema), "EMA(900s, time decay)");
     }
 }
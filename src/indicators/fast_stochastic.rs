@@ -0,0 +1,237 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, Maximum, Minimum};
+use crate::{Close, High, Low, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Fast stochastic oscillator (%K).
+///
+/// Shows where the close is relative to the highest high and the lowest low of the
+/// time window: `%K = (close - lowest low) / (highest high - lowest low) * 100`.
+///
+/// The highest high and the lowest low are tracked by a time-windowed
+/// [Maximum](struct.Maximum.html) and [Minimum](struct.Minimum.html), so updates within the
+/// same time bucket replace the previous high and low of that bucket.
+///
+/// When the highest high equals the lowest low the oscillator returns `50.0`.
+///
+/// # Parameters
+///
+/// * `duration` - time window of the highs and lows (e.g. 14 days)
+#[derive(Debug, Clone)]
+pub struct FastStochastic {
+    duration: Duration,
+    minimum: Minimum,
+    maximum: Maximum,
+    current: Option<f64>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "FastStochastic")]
+struct StateV1 {
+    duration: Duration,
+    minimum: Minimum,
+    maximum: Maximum,
+    current: Option<f64>,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(FastStochastic, StateV1, 1);
+
+impl FastStochastic {
+    pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            duration,
+            minimum: Minimum::with_policy(duration, policy.clone())?,
+            maximum: Maximum::with_policy(duration, policy)?,
+            current: None,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn stochastic(close: f64, highest: f64, lowest: f64) -> f64 {
+        if highest == lowest {
+            50.0
+        } else {
+            (close - lowest) / (highest - lowest) * 100.0
+        }
+    }
+}
+
+impl Next<f64> for FastStochastic {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        let lowest = self.minimum.next((timestamp, value));
+        let highest = self.maximum.next((timestamp, value));
+        let k = Self::stochastic(value, highest, lowest);
+        self.current = Some(k);
+        k
+    }
+}
+
+impl<T: High + Low + Close> Next<&T> for FastStochastic {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        let lowest = self.minimum.next((timestamp, input.low()));
+        let highest = self.maximum.next((timestamp, input.high()));
+        let k = Self::stochastic(input.close(), highest, lowest);
+        self.current = Some(k);
+        k
+    }
+}
+
+impl Validate for FastStochastic {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.minimum.latest_time()
+    }
+}
+
+impl Peek for FastStochastic {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.current
+    }
+
+    fn is_ready(&self) -> bool {
+        self.minimum.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.minimum.sample_count()
+    }
+}
+
+impl Reset for FastStochastic {
+    fn reset(&mut self) {
+        self.minimum.reset();
+        self.maximum.reset();
+        self.current = None;
+    }
+}
+
+impl Default for FastStochastic {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap()
+    }
+}
+
+impl fmt::Display for FastStochastic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FAST_STOCH({})", format_duration(self.duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+    use chrono::TimeZone;
+
+    test_indicator!(FastStochastic);
+
+    #[test]
+    fn test_new() {
+        assert!(FastStochastic::new(Duration::from_secs(0)).is_err());
+        assert!(FastStochastic::new(DAY).is_ok());
+    }
+
+    #[test]
+    fn test_next_with_f64() {
+        let mut stoch = FastStochastic::new(DAY * 3).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let day = |n: i64| start + chrono::Duration::days(n);
+
+        assert_eq!(stoch.next((day(0), 0.0)), 50.0);
+        assert_eq!(stoch.next((day(1), 200.0)), 100.0);
+        assert_eq!(stoch.next((day(2), 100.0)), 50.0);
+        // The 0 of day 0 is now 3 days old
+        assert_eq!(stoch.next((day(3), 120.0)), 20.0);
+        assert_eq!(stoch.next((day(4), 115.0)), 75.0);
+    }
+
+    #[test]
+    fn test_next_with_bars() {
+        let mut stoch = FastStochastic::new(DAY * 3).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let day = |n: i64| start + chrono::Duration::days(n);
+
+        // (high, low, close)
+        let bars = vec![
+            ((20.0, 20.0, 20.0), 50.0),
+            ((30.0, 10.0, 25.0), 75.0),
+            ((40.0, 20.0, 16.0), 20.0),
+            ((35.0, 15.0, 19.0), 30.0),
+        ];
+
+        for (i, ((high, low, close), expected)) in bars.into_iter().enumerate() {
+            let bar = Bar::new().high(high).low(low).close(close);
+            assert_eq!(round(stoch.next((day(i as i64), &bar))), expected);
+        }
+    }
+
+    #[test]
+    fn test_replacement_within_bucket() {
+        let mut stoch = FastStochastic::new(DAY * 3).unwrap();
+        let open = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
+
+        stoch.next((open, 10.0));
+        stoch.next((open + chrono::Duration::days(1), 20.0));
+
+        // A spike later in the same session replaces the session's value,
+        // so it is not kept as the highest value once revised
+        stoch.next((
+            open + chrono::Duration::days(1) + chrono::Duration::hours(1),
+            40.0,
+        ));
+        let revised = stoch.next((
+            open + chrono::Duration::days(1) + chrono::Duration::hours(2),
+            15.0,
+        ));
+        assert_eq!(revised, 100.0);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut stoch = FastStochastic::new(DAY * 3).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(stoch.next((start, 0.0)), 50.0);
+        assert_eq!(
+            stoch.next((start + chrono::Duration::days(1), 200.0)),
+            100.0
+        );
+        assert_eq!(stoch.next((start + chrono::Duration::days(2), 100.0)), 50.0);
+
+        stoch.reset();
+
+        assert_eq!(stoch.next((start, 10.0)), 50.0);
+    }
+
+    #[test]
+    fn test_default() {
+        FastStochastic::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let indicator = FastStochastic::new(DAY * 21).unwrap();
+        assert_eq!(format!("{}", indicator), "FAST_STOCH(21 days)");
+    }
+}
@@ -0,0 +1,402 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::{Result, TaError};
+use crate::helpers::format_duration;
+use crate::indicators::{AdaptiveTimeDetector, BucketPolicy, EfficiencyRatio};
+use crate::{Close, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Kaufman's adaptive moving average (KAMA).
+///
+/// An exponential moving average whose factor follows the
+/// [EfficiencyRatio](struct.EfficiencyRatio.html) over `duration`: close to the factor of a fast
+/// EMA while the price moves straight, close to the one of a slow EMA while it is noisy.
+///
+/// `SC = (ER * (fast factor - slow factor) + slow factor)^2`
+///
+/// `KAMA = previous KAMA + SC * (value - previous KAMA)`
+///
+/// The fast and slow factors are the `2 / (periods + 1)` of EMAs over `fast_duration` and
+/// `slow_duration`, where `periods` is the number of time buckets in the duration, e.g. 2 for
+/// 2 minutes of 1 minute bars, see
+/// [AdaptiveTimeDetector::periods](struct.AdaptiveTimeDetector.html#method.periods).
+///
+/// The smoothing constant is applied once per time bucket. An update within the same bucket
+/// steps again from the average before the bucket, like the EMA does, with the efficiency ratio
+/// of the revised window.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::KaufmanAdaptiveMovingAverage as Kama;
+/// use ta::Next;
+///
+/// let day = Duration::from_secs(86400);
+/// let mut kama = Kama::new(day * 3, day * 2, day * 6).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+///
+/// assert_eq!(kama.next((start, 10.0)), 10.0);
+/// // A straight move steps with the fast factor 2 / 3, squared
+/// let value = kama.next((start + ChronoDuration::days(1), 11.0));
+/// assert!((value - 10.444).abs() < 1e-3);
+/// ```
+#[doc(alias = "KAMA")]
+#[derive(Debug, Clone)]
+pub struct KaufmanAdaptiveMovingAverage {
+    fast_duration: Duration,
+    slow_duration: Duration,
+    fast_k: f64,
+    slow_k: f64,
+    er: EfficiencyRatio,
+    detector: AdaptiveTimeDetector,
+    current: f64,
+    // The average before the current bucket, where revisions of the bucket step from
+    previous: f64,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "KaufmanAdaptiveMovingAverage")]
+struct StateV2 {
+    fast_duration: Duration,
+    slow_duration: Duration,
+    fast_k: f64,
+    slow_k: f64,
+    er: EfficiencyRatio,
+    detector: AdaptiveTimeDetector,
+    current: f64,
+    previous: f64,
+}
+
+// The layout of version 1, which reversed the last step for a revision
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct StateV1 {
+    fast_duration: Duration,
+    slow_duration: Duration,
+    fast_k: f64,
+    slow_k: f64,
+    er: EfficiencyRatio,
+    detector: AdaptiveTimeDetector,
+    current: f64,
+    last_value: f64,
+    // The smoothing constant of the step to `current`
+    smoothing: f64,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<KaufmanAdaptiveMovingAverage> for StateV1 {
+    fn migrate(self) -> Result<KaufmanAdaptiveMovingAverage> {
+        // current = previous + smoothing * (last_value - previous), unless the step went all the
+        // way to the value and revisions step from it, as they did
+        let previous = if self.smoothing == 1.0 {
+            self.current
+        } else {
+            (self.current - self.smoothing * self.last_value) / (1.0 - self.smoothing)
+        };
+        Ok(KaufmanAdaptiveMovingAverage {
+            fast_duration: self.fast_duration,
+            slow_duration: self.slow_duration,
+            fast_k: self.fast_k,
+            slow_k: self.slow_k,
+            er: self.er,
+            detector: self.detector,
+            current: self.current,
+            previous,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(KaufmanAdaptiveMovingAverage, StateV2, 2, 1 => StateV1);
+
+impl KaufmanAdaptiveMovingAverage {
+    /// Creates a KAMA with the efficiency ratio over `duration` and the factors of EMAs over
+    /// `fast_duration` and `slow_duration`, e.g. 10, 2 and 30 days.
+    ///
+    /// Fails if `fast_duration` or `slow_duration` is shorter than one time bucket.
+    pub fn new(
+        duration: Duration,
+        fast_duration: Duration,
+        slow_duration: Duration,
+    ) -> Result<Self> {
+        Self::with_policy(
+            duration,
+            fast_duration,
+            slow_duration,
+            BucketPolicy::Heuristic,
+        )
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(
+        duration: Duration,
+        fast_duration: Duration,
+        slow_duration: Duration,
+        policy: BucketPolicy,
+    ) -> Result<Self> {
+        let detector = AdaptiveTimeDetector::with_policy(duration, policy.clone())?;
+        Ok(Self {
+            fast_duration,
+            slow_duration,
+            fast_k: Self::factor(&detector, fast_duration)?,
+            slow_k: Self::factor(&detector, slow_duration)?,
+            er: EfficiencyRatio::with_policy(duration, policy)?,
+            detector,
+            current: 0.0,
+            previous: 0.0,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.er.duration()
+    }
+
+    pub fn fast_duration(&self) -> Duration {
+        self.fast_duration
+    }
+
+    pub fn slow_duration(&self) -> Duration {
+        self.slow_duration
+    }
+
+    // The factor of an EMA over `duration` in the buckets of `detector`; durations shorter than
+    // one bucket would give a factor above 1
+    fn factor(detector: &AdaptiveTimeDetector, duration: Duration) -> Result<f64> {
+        let periods = detector.periods(duration);
+        if periods < 1.0 {
+            return Err(TaError::InvalidParameter);
+        }
+        Ok(2.0 / (periods + 1.0))
+    }
+}
+
+impl Next<f64> for KaufmanAdaptiveMovingAverage {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        let should_replace = self.detector.should_replace(timestamp);
+        let er = self.er.next((timestamp, value));
+
+        if self.detector.bucket_count() == 1 {
+            // The first bucket seeds the average, revisions of it too
+            self.current = value;
+        } else {
+            if !should_replace {
+                self.previous = self.current;
+            }
+            let smoothing = er * (self.fast_k - self.slow_k) + self.slow_k;
+            self.current = self.previous + smoothing * smoothing * (value - self.previous);
+        }
+
+        self.current
+    }
+}
+
+impl<T: Close> Next<&T> for KaufmanAdaptiveMovingAverage {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for KaufmanAdaptiveMovingAverage {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.detector.latest()
+    }
+}
+
+impl Peek for KaufmanAdaptiveMovingAverage {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        if self.detector.bucket_count() == 0 {
+            None
+        } else {
+            Some(self.current)
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.er.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.detector.bucket_count()
+    }
+}
+
+impl Reset for KaufmanAdaptiveMovingAverage {
+    fn reset(&mut self) {
+        self.er.reset();
+        self.detector.reset();
+        self.current = 0.0;
+        self.previous = 0.0;
+    }
+}
+
+impl Default for KaufmanAdaptiveMovingAverage {
+    fn default() -> Self {
+        let day = Duration::from_secs(24 * 60 * 60);
+        Self::new(day * 10, day * 2, day * 30).unwrap()
+    }
+}
+
+impl fmt::Display for KaufmanAdaptiveMovingAverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "KAMA({}, {}, {})",
+            format_duration(self.duration()),
+            format_duration(self.fast_duration),
+            format_duration(self.slow_duration)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+
+    type Kama = KaufmanAdaptiveMovingAverage;
+
+    test_indicator!(Kama);
+
+    #[test]
+    fn test_new() {
+        assert!(Kama::new(Duration::from_secs(0), DAY * 2, DAY * 30).is_err());
+        assert!(Kama::new(DAY * 10, Duration::from_secs(0), DAY * 30).is_err());
+        assert!(Kama::new(DAY * 10, DAY * 2, Duration::from_secs(0)).is_err());
+        assert!(Kama::new(DAY * 10, DAY * 2, DAY * 30).is_ok());
+    }
+
+    #[test]
+    fn test_next() {
+        let mut kama = Kama::new(DAY * 3, DAY * 2, DAY * 6).unwrap();
+
+        let values = [
+            (10.0, 10.0),
+            // ER 1, the fast factor
+            (11.0, 10.444),
+            (10.5, 10.454),
+            (11.5, 10.731),
+            // ER 0, the slow factor
+            (11.0, 10.753),
+            (12.0, 11.083),
+        ];
+        for (n, (value, expected)) in values.into_iter().enumerate() {
+            assert_eq!(round(kama.next((day(n as i64), value))), expected);
+        }
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut kama = Kama::new(DAY * 3, DAY * 2, DAY * 6).unwrap();
+        let mut expected = kama.clone();
+
+        // Revisions of the first bucket move the seed
+        kama.next((day(0), 12.0));
+        assert_eq!(kama.next((day(0) + chrono::Duration::hours(1), 10.0)), 10.0);
+        expected.next((day(0), 10.0));
+
+        kama.next((day(1), 11.0));
+        kama.next((day(1) + chrono::Duration::hours(1), 8.0));
+        let revised = kama.next((day(1) + chrono::Duration::hours(2), 9.0));
+        assert_eq!(round(revised), round(expected.next((day(1), 9.0))));
+        assert_eq!(round(revised), 9.556);
+        assert_eq!(kama.sample_count(), 2);
+
+        assert_eq!(
+            round(kama.next((day(2), 10.0))),
+            round(expected.next((day(2), 10.0)))
+        );
+    }
+
+    #[test]
+    fn test_short_durations() {
+        // Half a day is less than one daily bucket
+        assert!(Kama::new(DAY * 2, DAY / 2, DAY * 6).is_err());
+        assert!(Kama::new(DAY * 2, DAY, DAY / 2).is_err());
+    }
+
+    #[test]
+    fn test_intraday() {
+        let minute = Duration::from_secs(60);
+        let mut straight = Kama::new(minute * 10, minute * 2, minute * 30).unwrap();
+        let mut noisy = straight.clone();
+        let start = day(0);
+
+        // The same trend of 0.2 a minute, once straight and once with swings of 2 every other
+        // minute
+        let mut values = (0.0, 0.0);
+        for n in 0..20 {
+            let time = start + chrono::Duration::minutes(n);
+            values = (
+                straight.next((time, 10.0 + 0.2 * n as f64)),
+                noisy.next((time, 10.0 + 0.2 * n as f64 + 2.0 * (n % 2) as f64)),
+            );
+        }
+        // Close to the trend at 13.8 while the price moves straight, far behind it while the
+        // noise keeps the efficiency ratio low
+        assert_eq!(round(values.0), 13.55);
+        assert_eq!(round(values.1), 11.788);
+    }
+
+    #[test]
+    fn test_replacement_of_full_step() {
+        // ER 1 with a fast factor of 1 steps all the way to the value, revisions still apply
+        let mut kama = Kama::new(DAY * 3, DAY, DAY * 6).unwrap();
+        let mut expected = kama.clone();
+
+        for (n, value) in [10.0, 11.0, 12.0].into_iter().enumerate() {
+            kama.next((day(n as i64), value));
+        }
+        let revised = kama.next((day(2) + chrono::Duration::hours(1), 10.5));
+
+        for (n, value) in [10.0, 11.0, 10.5].into_iter().enumerate() {
+            expected.next((day(n as i64), value));
+        }
+        assert_eq!(round(revised), round(expected.current().unwrap()));
+        assert_eq!(round(revised), 10.863);
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut kama = Kama::new(DAY * 3, DAY * 2, DAY * 6).unwrap();
+        assert_eq!(kama.current(), None);
+
+        kama.next((day(0), 10.0));
+        let value = kama.next((day(1), 11.0));
+        assert_eq!(kama.current(), Some(value));
+        assert!(!kama.is_ready());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut kama = Kama::new(DAY * 3, DAY * 2, DAY * 6).unwrap();
+
+        kama.next((day(0), 10.0));
+        kama.next((day(1), 11.0));
+        kama.reset();
+        assert_eq!(kama.current(), None);
+        assert_eq!(kama.next((day(2), 14.0)), 14.0);
+    }
+
+    #[test]
+    fn test_default() {
+        Kama::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let kama = Kama::new(DAY * 10, DAY * 2, DAY * 30).unwrap();
+        assert_eq!(format!("{}", kama), "KAMA(10 days, 2 days, 30 days)");
+    }
+}
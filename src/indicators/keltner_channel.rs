@@ -0,0 +1,263 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::{Result, TaError};
+use crate::helpers::format_duration;
+use crate::indicators::{AverageTrueRange, BucketPolicy, ExponentialMovingAverage as Ema};
+use crate::{Close, High, Low, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Keltner Channel (KC).
+///
+/// An exponential moving average of the typical price `(high + low + close) / 3`, the middle
+/// line, with an upper and a lower band placed `multiplier` average true ranges away from it.
+/// Fed with `f64` values, the value is used as the typical price and the true range is the
+/// distance to the previous value.
+///
+/// The average is an [ExponentialMovingAverage](struct.ExponentialMovingAverage.html) and the
+/// range an [AverageTrueRange](struct.AverageTrueRange.html) over the same time window, so
+/// updates within the same time bucket revise the channel like they revise those.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::KeltnerChannel;
+/// use ta::{DataItem, Next};
+///
+/// let mut kc = KeltnerChannel::new(Duration::from_secs(3 * 86400), 2.0).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+/// let bar = |high: f64, low: f64, close: f64| {
+///     DataItem::builder()
+///         .open(close)
+///         .high(high)
+///         .low(low)
+///         .close(close)
+///         .volume(1000.0)
+///         .build()
+///         .unwrap()
+/// };
+///
+/// kc.next((start, &bar(12.0, 9.0, 12.0)));
+/// let output = kc.next((start + ChronoDuration::days(1), &bar(13.0, 10.0, 13.0)));
+/// assert_eq!(output.average, 11.5);
+/// assert_eq!(output.upper, 17.5);
+/// assert_eq!(output.lower, 5.5);
+/// ```
+#[doc(alias = "KC")]
+#[derive(Debug, Clone)]
+pub struct KeltnerChannel {
+    duration: Duration,
+    multiplier: f64,
+    ema: Ema,
+    atr: AverageTrueRange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeltnerChannelOutput {
+    /// The middle line.
+    pub average: f64,
+    pub upper: f64,
+    pub lower: f64,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "KeltnerChannel")]
+struct StateV1 {
+    duration: Duration,
+    multiplier: f64,
+    ema: Ema,
+    atr: AverageTrueRange,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(KeltnerChannel, StateV1, 1);
+
+impl KeltnerChannel {
+    /// Fails for a zero `duration` and a `multiplier` that is negative or not finite.
+    pub fn new(duration: Duration, multiplier: f64) -> Result<Self> {
+        Self::with_policy(duration, multiplier, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, multiplier: f64, policy: BucketPolicy) -> Result<Self> {
+        if !multiplier.is_finite() || multiplier < 0.0 {
+            return Err(TaError::InvalidParameter);
+        }
+        Ok(Self {
+            duration,
+            multiplier,
+            ema: Ema::with_policy(duration, policy.clone())?,
+            atr: AverageTrueRange::with_policy(duration, policy)?,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    fn output(&self, average: f64, atr: f64) -> KeltnerChannelOutput {
+        KeltnerChannelOutput {
+            average,
+            upper: average + atr * self.multiplier,
+            lower: average - atr * self.multiplier,
+        }
+    }
+}
+
+impl Next<f64> for KeltnerChannel {
+    type Output = KeltnerChannelOutput;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        let average = self.ema.next((timestamp, value));
+        let atr = self.atr.next((timestamp, value));
+        self.output(average, atr)
+    }
+}
+
+impl<T: High + Low + Close> Next<&T> for KeltnerChannel {
+    type Output = KeltnerChannelOutput;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        let typical_price = (input.high() + input.low() + input.close()) / 3.0;
+        let average = self.ema.next((timestamp, typical_price));
+        let atr = self.atr.next((timestamp, input));
+        self.output(average, atr)
+    }
+}
+
+impl Validate for KeltnerChannel {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.ema.latest_time()
+    }
+}
+
+impl Peek for KeltnerChannel {
+    type Output = KeltnerChannelOutput;
+
+    fn current(&self) -> Option<Self::Output> {
+        Some(self.output(self.ema.current()?, self.atr.current()?))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ema.is_ready() && self.atr.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.ema.sample_count()
+    }
+}
+
+impl Reset for KeltnerChannel {
+    fn reset(&mut self) {
+        self.ema.reset();
+        self.atr.reset();
+    }
+}
+
+impl Default for KeltnerChannel {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10 * 24 * 60 * 60), 2.0).unwrap()
+    }
+}
+
+impl fmt::Display for KeltnerChannel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "KC({}, {})",
+            format_duration(self.duration),
+            self.multiplier
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+
+    test_indicator!(KeltnerChannel);
+
+    fn rounded(output: KeltnerChannelOutput) -> (f64, f64, f64) {
+        (
+            round(output.average),
+            round(output.upper),
+            round(output.lower),
+        )
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(KeltnerChannel::new(Duration::from_secs(0), 2.0).is_err());
+        assert!(KeltnerChannel::new(DAY, 2.0).is_ok());
+        assert!(KeltnerChannel::new(DAY, 0.0).is_ok());
+        assert!(KeltnerChannel::new(DAY, -1.0).is_err());
+        assert!(KeltnerChannel::new(DAY, f64::NAN).is_err());
+        assert!(KeltnerChannel::new(DAY, f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn test_next() {
+        let mut kc = KeltnerChannel::new(DAY * 3, 2.0).unwrap();
+
+        let bar = Bar::new().high(12).low(9).close(10);
+        assert_eq!(rounded(kc.next((day(0), &bar))), (10.333, 16.333, 4.333));
+        let bar = Bar::new().high(13).low(10).close(12);
+        assert_eq!(rounded(kc.next((day(1), &bar))), (11.0, 17.0, 5.0));
+        let bar = Bar::new().high(15).low(11).close(14);
+        assert_eq!(rounded(kc.next((day(2), &bar))), (12.167, 19.167, 5.167));
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut kc = KeltnerChannel::new(DAY * 3, 2.0).unwrap();
+
+        kc.next((day(0), &Bar::new().high(12).low(9).close(10)));
+        kc.next((day(1), &Bar::new().high(14).low(8).close(13)));
+        // A revision of day 1 replaces its bar in the average and the true range
+        let revision = day(1) + chrono::Duration::hours(1);
+        let bar = Bar::new().high(13).low(10).close(12);
+        assert_eq!(rounded(kc.next((revision, &bar))), (11.0, 17.0, 5.0));
+        assert_eq!(kc.sample_count(), 2);
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut kc = KeltnerChannel::new(DAY * 2, 1.5).unwrap();
+        assert_eq!(kc.current(), None);
+
+        let output = kc.next((day(0), &Bar::new().high(12).low(9).close(10)));
+        assert_eq!(kc.current(), Some(output));
+        assert!(!kc.is_ready());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut kc = KeltnerChannel::new(DAY * 3, 2.0).unwrap();
+
+        kc.next((day(0), 10.0));
+        kc.next((day(1), 14.0));
+        kc.reset();
+        assert_eq!(rounded(kc.next((day(2), 10.0))), (10.0, 10.0, 10.0));
+    }
+
+    #[test]
+    fn test_default() {
+        KeltnerChannel::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let kc = KeltnerChannel::new(DAY * 20, 1.5).unwrap();
+        assert_eq!(format!("{}", kc), "KC(20 days, 1.5)");
+    }
+}
@@ -1,104 +1,217 @@
-use crate::errors::{Result, TaError};
-use crate::indicators::AdaptiveTimeDetector;
-use crate::{Next, Reset};
-use chrono::{DateTime, Utc}; // Remove Duration from here
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::{format_duration, Extreme};
+use crate::indicators::excursion::ExcursionWindow;
+#[cfg(feature = "serde")]
+use crate::indicators::excursion::ExcursionWindowV1;
+use crate::indicators::BucketPolicy;
+use crate::{Close, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::fmt;
-use std::time::Duration; // Change: use std::time::Duration
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Maximum drawdown over a time window.
+///
+/// The largest decline from a peak to a later trough within the window, in percent of the peak.
+///
+/// The result is updated incrementally in amortised O(1) per value, also while the peak of the
+/// maximum drawdown leaves the window, plus a binary search for the recovery. An update within
+/// the same time bucket as the previous one replaces the previous value.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::MaxDrawdown;
+/// use ta::Next;
+///
+/// let mut max_drawdown = MaxDrawdown::new(Duration::from_secs(10 * 86400)).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+///
+/// max_drawdown.next((start, 10.0));
+/// max_drawdown.next((start + ChronoDuration::days(1), 8.0));
+/// let out = max_drawdown.next((start + ChronoDuration::days(2), 9.0));
+///
+/// assert_eq!(out.max_drawdown, 20.0);
+/// assert_eq!(out.peak, 10.0);
+/// assert_eq!(out.trough_time, start + ChronoDuration::days(1));
+/// assert_eq!(out.current_drawdown, 10.0);
+/// assert_eq!(out.recovery_time, None);
+/// ```
 #[derive(Debug, Clone)]
 pub struct MaxDrawdown {
-    duration: Duration, // Now std::time::Duration
+    excursion: ExcursionWindow,
+}
+
+/// Maximum drawdown of the window and where it happened.
+///
+/// While the window has not declined, `peak` and `trough` are both its oldest value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaxDrawdownOutput {
+    /// Largest decline from a peak to a later trough, in percent of the peak.
+    pub max_drawdown: f64,
+    pub peak: f64,
+    pub peak_time: DateTime<Utc>,
+    pub trough: f64,
+    pub trough_time: DateTime<Utc>,
+    /// Decline of the latest value from the highest value of the window, in percent.
+    pub current_drawdown: f64,
+    /// Time from the trough until the value got back to the peak, `None` if it has not
+    /// recovered within the window.
+    pub recovery_time: Option<Duration>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MaxDrawdown")]
+struct StateV2 {
+    excursion: ExcursionWindow,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct StateV1 {
+    excursion: ExcursionWindowV1,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<MaxDrawdown> for StateV1 {
+    fn migrate(self) -> Result<MaxDrawdown> {
+        Ok(MaxDrawdown {
+            excursion: self.excursion.migrate(),
+        })
+    }
+}
+
+// The unversioned layout of ta 0.5
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LegacyState {
+    duration: Duration,
     window: VecDeque<(DateTime<Utc>, f64)>,
-    detector: AdaptiveTimeDetector,
+    detector: super::adaptive::LegacyDetector,
 }
 
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<MaxDrawdown> for LegacyState {
+    fn migrate(self) -> Result<MaxDrawdown> {
+        Ok(MaxDrawdown {
+            excursion: ExcursionWindow::from_legacy(
+                self.duration,
+                Extreme::Max,
+                self.window,
+                self.detector,
+            )?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(MaxDrawdown, StateV2, 2, 0 => LegacyState, 1 => StateV1);
+
 impl MaxDrawdown {
     pub fn get_window(&self) -> VecDeque<(DateTime<Utc>, f64)> {
-        self.window.clone()
+        self.excursion.window().values().clone()
     }
 
     pub fn new(duration: Duration) -> Result<Self> {
-        // Change: std::time::Duration can't be negative, so just check if it's zero
-        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-            Err(TaError::InvalidParameter)
-        } else {
-            Ok(Self {
-                duration,
-                window: VecDeque::new(),
-                detector: AdaptiveTimeDetector::new(duration),
-            })
-        }
+        Self::with_policy(duration, BucketPolicy::Heuristic)
     }
 
-    fn calculate_max_drawdown(&self) -> f64 {
-        // No changes needed here
-        let mut peak = f64::MIN;
-        let mut max_drawdown = 0.0;
-        for &(_, value) in &self.window {
-            if value > peak {
-                peak = value;
-            }
-            let drawdown = (peak - value) / peak;
-            if drawdown > max_drawdown {
-                max_drawdown = drawdown;
-            }
-        }
-        100.0 * max_drawdown
-    }
-
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // Change: Convert std::time::Duration to chrono::Duration for the subtraction
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time < current_time - chrono_duration)
-        {
-            self.window.pop_front();
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            excursion: ExcursionWindow::new(duration, policy, Extreme::Max)?,
+        })
+    }
+
+    fn output(&self) -> MaxDrawdownOutput {
+        let largest = self
+            .excursion
+            .largest()
+            .expect("the window holds the latest value");
+        MaxDrawdownOutput {
+            max_drawdown: 100.0 * largest.size,
+            peak: largest.start.value,
+            peak_time: largest.start.time,
+            trough: largest.end.value,
+            trough_time: largest.end.time,
+            current_drawdown: 100.0 * self.excursion.current(),
+            recovery_time: self.excursion.recovery().map(|recovery| {
+                (recovery.time - largest.end.time)
+                    .to_std()
+                    .unwrap_or_default()
+            }),
         }
     }
 }
 
 impl Next<f64> for MaxDrawdown {
-    type Output = f64;
+    type Output = MaxDrawdownOutput;
 
     fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
-        // Check if we should replace the last value (same time bucket)
-        let should_replace = self.detector.should_replace(timestamp);
+        // The window takes care of bucket replacement and old data removal
+        self.excursion.push(timestamp, value);
+        self.output()
+    }
+}
 
-        // ALWAYS remove old data first, regardless of replace/add
-        self.remove_old_data(timestamp);
+impl<T: Close> Next<&T> for MaxDrawdown {
+    type Output = MaxDrawdownOutput;
 
-        if should_replace && !self.window.is_empty() {
-            self.window.pop_back();
-        }
-        self.window.push_back((timestamp, value));
-        self.calculate_max_drawdown()
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for MaxDrawdown {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.excursion.window().latest_time()
+    }
+
+    fn requires_positive(&self) -> bool {
+        true
+    }
+}
+
+impl Peek for MaxDrawdown {
+    type Output = MaxDrawdownOutput;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.excursion.largest().map(|_| self.output())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.excursion.window().is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.excursion.window().bucket_count()
     }
 }
 
 impl Reset for MaxDrawdown {
     fn reset(&mut self) {
-        // No changes needed
-        self.window.clear();
-        self.detector.reset();
+        self.excursion.clear();
     }
 }
 
 impl fmt::Display for MaxDrawdown {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Change: Use as_secs() instead of num_seconds()
-        write!(f, "MaxDrawdown({}s)", self.duration.as_secs())
+        write!(
+            f,
+            "MaxDrawdown({})",
+            format_duration(self.excursion.window().duration())
+        )
     }
 }
 
 impl Default for MaxDrawdown {
     fn default() -> Self {
-        // Change: Use std::time::Duration constructor
         Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap() // 14 days in seconds
     }
 }
@@ -106,8 +219,11 @@ impl Default for MaxDrawdown {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_helper::*;
     use chrono::TimeZone;
 
+    test_indicator!(MaxDrawdown);
+
     #[test]
     fn test_new() {
         assert!(MaxDrawdown::new(Duration::from_secs(0)).is_err());
@@ -116,69 +232,213 @@ mod tests {
 
     #[test]
     fn test_next() {
-        let duration = Duration::from_secs(2);
+        // Holds 3 values: the one 3 seconds old has left the window
+        let duration = Duration::from_secs(3);
         let mut max = MaxDrawdown::new(duration).unwrap();
-        let start_time = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
 
         // Change: Use chrono::Duration for adding to DateTime
-        assert_eq!(max.next((start_time, 4.0)), 0.0);
+        assert_eq!(max.next((start_time, 4.0)).max_drawdown, 0.0);
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(1), 2.0)),
+            max.next((start_time + chrono::Duration::seconds(1), 2.0))
+                .max_drawdown,
             50.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(2), 1.0)),
+            max.next((start_time + chrono::Duration::seconds(2), 1.0))
+                .max_drawdown,
             75.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(3), 3.0)),
+            max.next((start_time + chrono::Duration::seconds(3), 3.0))
+                .max_drawdown,
             50.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(4), 4.0)),
+            max.next((start_time + chrono::Duration::seconds(4), 4.0))
+                .max_drawdown,
             0.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(5), 0.0)),
+            max.next((start_time + chrono::Duration::seconds(5), 0.0))
+                .max_drawdown,
             100.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(6), 2.0)),
+            max.next((start_time + chrono::Duration::seconds(6), 2.0))
+                .max_drawdown,
             100.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(7), 3.0)),
+            max.next((start_time + chrono::Duration::seconds(7), 3.0))
+                .max_drawdown,
             0.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(8), 1.5)),
+            max.next((start_time + chrono::Duration::seconds(8), 1.5))
+                .max_drawdown,
             50.0
         );
     }
 
+    #[test]
+    fn test_peak_trough_and_recovery() {
+        let mut max = MaxDrawdown::new(DAY * 10).unwrap();
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let day = |n: i64| start_time + chrono::Duration::days(n);
+
+        max.next((day(0), 10.0));
+        max.next((day(1), 12.0));
+        max.next((day(2), 9.0));
+        let out = max.next((day(3), 6.0));
+        assert_eq!(out.max_drawdown, 50.0);
+        assert_eq!((out.peak, out.peak_time), (12.0, day(1)));
+        assert_eq!((out.trough, out.trough_time), (6.0, day(3)));
+        assert_eq!(out.current_drawdown, 50.0);
+        assert_eq!(out.recovery_time, None);
+
+        let out = max.next((day(4), 8.0));
+        assert_eq!(round(out.current_drawdown), 33.333);
+        assert_eq!(out.recovery_time, None);
+
+        let out = max.next((day(5), 12.0));
+        assert_eq!(out.max_drawdown, 50.0);
+        assert_eq!(out.current_drawdown, 0.0);
+        assert_eq!(out.recovery_time, Some(DAY * 2));
+
+        // A new high does not move the recovery
+        let out = max.next((day(6), 13.0));
+        assert_eq!(out.recovery_time, Some(DAY * 2));
+        assert_eq!((out.trough, out.trough_time), (6.0, day(3)));
+    }
+
+    #[test]
+    fn test_replacement_within_bucket() {
+        let mut max = MaxDrawdown::new(DAY * 10).unwrap();
+        let open = Utc.with_ymd_and_hms(2020, 1, 1, 9, 30, 0).unwrap();
+        let next_open = open + chrono::Duration::days(1);
+
+        max.next((open, 10.0));
+        assert_eq!(max.next((next_open, 8.0)).max_drawdown, 20.0);
+
+        // A deeper intraday low is revised away by later updates of the same session
+        let out = max.next((next_open + chrono::Duration::hours(1), 5.0));
+        assert_eq!(out.max_drawdown, 50.0);
+        let out = max.next((next_open + chrono::Duration::hours(2), 9.0));
+        assert_eq!(out.max_drawdown, 10.0);
+        assert_eq!(out.trough, 9.0);
+
+        // Revising the recovering value away brings the recovery back out
+        let later_open = next_open + chrono::Duration::days(1);
+        let out = max.next((later_open, 11.0));
+        assert_eq!(out.recovery_time, Some(Duration::from_secs(22 * 3600)));
+        let out = max.next((later_open + chrono::Duration::hours(1), 9.5));
+        assert_eq!(out.recovery_time, None);
+        assert_eq!(out.max_drawdown, 10.0);
+        assert_eq!(max.get_window().len(), 3);
+    }
+
+    #[test]
+    fn test_matches_window_scan() {
+        let duration = Duration::from_secs(30 * 60); // minute buckets
+        let mut max = MaxDrawdown::new(duration).unwrap();
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+
+        for i in 0..2_000i64 {
+            let value = 1.0 + ((i * 7919) % 1000) as f64;
+            let out = max.next((start_time + chrono::Duration::seconds(i * 20), value));
+
+            let window = max.get_window();
+            let mut peak = f64::MIN;
+            let mut peak_time = None;
+            let mut expected = 0.0;
+            let mut largest = None;
+            for &(time, val) in window.iter() {
+                if val >= peak {
+                    peak = val;
+                    peak_time = Some(time);
+                }
+                let drawdown = (peak - val) / peak;
+                if drawdown > expected || largest.is_none() {
+                    expected = drawdown;
+                    largest = Some((peak, peak_time, time));
+                }
+            }
+            let (largest_peak, largest_peak_time, trough_time) = largest.unwrap();
+            let recovery = window
+                .iter()
+                .find(|&&(time, val)| expected > 0.0 && time > trough_time && val >= largest_peak);
+            assert_eq!(out.max_drawdown, 100.0 * expected);
+            assert_eq!(Some(out.peak_time), largest_peak_time);
+            assert_eq!(out.trough_time, trough_time);
+            assert_eq!(
+                out.recovery_time,
+                recovery.map(|&(time, _)| (time - trough_time).to_std().unwrap())
+            );
+            assert_eq!(out.current_drawdown, 100.0 * ((peak - value) / peak));
+        }
+    }
+
+    #[test]
+    fn test_declining() {
+        // The peak leaves the window with every new value
+        let duration = Duration::from_secs(100 * 60);
+        let mut max =
+            MaxDrawdown::with_policy(duration, BucketPolicy::Fixed(Duration::from_secs(60)))
+                .unwrap();
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+
+        for i in 0..5_000i64 {
+            let out = max.next((
+                start_time + chrono::Duration::minutes(i),
+                10_000.0 - i as f64,
+            ));
+
+            let oldest = (i - 99).max(0);
+            let peak = 10_000.0 - oldest as f64;
+            assert_eq!(out.peak, peak);
+            assert_eq!(
+                out.peak_time,
+                start_time + chrono::Duration::minutes(oldest)
+            );
+            assert_eq!(out.trough_time, start_time + chrono::Duration::minutes(i));
+            assert_eq!(out.max_drawdown, 100.0 * ((peak - out.trough) / peak));
+            assert_eq!(out.current_drawdown, out.max_drawdown);
+            assert_eq!(out.recovery_time, None);
+        }
+    }
+
     #[test]
     fn test_reset() {
         let duration = Duration::from_secs(100);
         let mut max = MaxDrawdown::new(duration).unwrap();
-        let start_time = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
 
         // Change: Use chrono::Duration for adding to DateTime
-        assert_eq!(max.next((start_time, 4.0)), 0.0);
+        assert_eq!(max.next((start_time, 4.0)).max_drawdown, 0.0);
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(50), 10.0)),
+            max.next((start_time + chrono::Duration::seconds(50), 10.0))
+                .max_drawdown,
             0.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(100), 2.0)),
+            max.next((start_time + chrono::Duration::seconds(100), 2.0))
+                .max_drawdown,
             80.0
         );
         max.reset();
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(150), 4.0)),
+            max.next((start_time + chrono::Duration::seconds(150), 4.0))
+                .max_drawdown,
             0.0
         );
     }
 
+    #[test]
+    fn test_default() {
+        MaxDrawdown::default();
+    }
+
     #[test]
     fn test_display() {
         let indicator = MaxDrawdown::new(Duration::from_secs(7)).unwrap();
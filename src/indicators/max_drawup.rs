@@ -1,119 +1,229 @@
-use std::collections::VecDeque;
 use std::fmt;
-use std::time::Duration; // Change: Use std::time::Duration
+use std::time::Duration;
 
-use crate::errors::{Result, TaError};
-use crate::indicators::AdaptiveTimeDetector;
-use crate::{Next, Reset};
+use crate::errors::Result;
+use crate::helpers::{format_duration, Extreme};
+use crate::indicators::excursion::ExcursionWindow;
+#[cfg(feature = "serde")]
+use crate::indicators::excursion::ExcursionWindowV1;
+use crate::indicators::BucketPolicy;
+use crate::{Close, Next, Peek, Reset, Validate};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Maximum drawup over a time window.
+///
+/// The largest rise from a trough to a later peak within the window, in percent of the trough.
+///
+/// Like [MaxDrawdown](struct.MaxDrawdown.html) the result is updated incrementally in amortised
+/// O(1) per value, also while the trough of the maximum drawup leaves the window. An update
+/// within the same time bucket as the previous one replaces the previous value.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::MaxDrawup;
+/// use ta::Next;
+///
+/// let mut max_drawup = MaxDrawup::new(Duration::from_secs(10 * 86400)).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+///
+/// max_drawup.next((start, 8.0));
+/// max_drawup.next((start + ChronoDuration::days(1), 10.0));
+/// let out = max_drawup.next((start + ChronoDuration::days(2), 9.0));
+///
+/// assert_eq!(out.max_drawup, 25.0);
+/// assert_eq!(out.trough, 8.0);
+/// assert_eq!(out.peak_time, start + ChronoDuration::days(1));
+/// assert_eq!(out.current_drawup, 12.5);
+/// assert_eq!(out.retracement_time, None);
+/// ```
 #[derive(Debug, Clone)]
 pub struct MaxDrawup {
-    duration: Duration, // Now std::time::Duration
+    excursion: ExcursionWindow,
+}
+
+/// Maximum drawup of the window and where it happened.
+///
+/// While the window has not risen, `trough` and `peak` are both its oldest value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaxDrawupOutput {
+    /// Largest rise from a trough to a later peak, in percent of the trough.
+    pub max_drawup: f64,
+    pub trough: f64,
+    pub trough_time: DateTime<Utc>,
+    pub peak: f64,
+    pub peak_time: DateTime<Utc>,
+    /// Rise of the latest value from the lowest value of the window, in percent.
+    pub current_drawup: f64,
+    /// Time from the peak until the value fell back to the trough, `None` if it has not
+    /// retraced within the window.
+    pub retracement_time: Option<Duration>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MaxDrawup")]
+struct StateV2 {
+    excursion: ExcursionWindow,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct StateV1 {
+    excursion: ExcursionWindowV1,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<MaxDrawup> for StateV1 {
+    fn migrate(self) -> Result<MaxDrawup> {
+        Ok(MaxDrawup {
+            excursion: self.excursion.migrate(),
+        })
+    }
+}
+
+// The unversioned layout of ta 0.5
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LegacyState {
+    duration: Duration,
     window: VecDeque<(DateTime<Utc>, f64)>,
-    detector: AdaptiveTimeDetector,
+    detector: super::adaptive::LegacyDetector,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<MaxDrawup> for LegacyState {
+    fn migrate(self) -> Result<MaxDrawup> {
+        Ok(MaxDrawup {
+            excursion: ExcursionWindow::from_legacy(
+                self.duration,
+                Extreme::Min,
+                self.window,
+                self.detector,
+            )?,
+        })
+    }
 }
 
+#[cfg(feature = "serde")]
+versioned_serde!(MaxDrawup, StateV2, 2, 0 => LegacyState, 1 => StateV1);
+
 impl MaxDrawup {
     pub fn get_window(&self) -> VecDeque<(DateTime<Utc>, f64)> {
-        self.window.clone()
+        self.excursion.window().values().clone()
     }
 
     pub fn new(duration: Duration) -> Result<Self> {
-        // Change: Check for zero duration (std::time::Duration can't be negative)
-        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-            Err(TaError::InvalidParameter)
-        } else {
-            Ok(Self {
-                duration,
-                window: VecDeque::new(),
-                detector: AdaptiveTimeDetector::new(duration),
-            })
-        }
+        Self::with_policy(duration, BucketPolicy::Heuristic)
     }
 
-    fn calculate_max_drawup(&self) -> f64 {
-        let mut trough = f64::MAX;
-        let mut max_drawup = 0.0;
-
-        for &(_, value) in &self.window {
-            if value < trough {
-                trough = value;
-            }
-            let drawup = (value - trough) / trough;
-            if drawup > max_drawup {
-                max_drawup = drawup;
-            }
-        }
-
-        100.0 * max_drawup
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            excursion: ExcursionWindow::new(duration, policy, Extreme::Min)?,
+        })
     }
 
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // Change: Convert std::time::Duration to chrono::Duration for date arithmetic
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time < current_time - chrono_duration)
-        {
-            self.window.pop_front();
+    fn output(&self) -> MaxDrawupOutput {
+        let largest = self
+            .excursion
+            .largest()
+            .expect("the window holds the latest value");
+        MaxDrawupOutput {
+            max_drawup: 100.0 * largest.size,
+            trough: largest.start.value,
+            trough_time: largest.start.time,
+            peak: largest.end.value,
+            peak_time: largest.end.time,
+            current_drawup: 100.0 * self.excursion.current(),
+            retracement_time: self.excursion.recovery().map(|retracement| {
+                (retracement.time - largest.end.time)
+                    .to_std()
+                    .unwrap_or_default()
+            }),
         }
     }
 }
 
 impl Next<f64> for MaxDrawup {
-    type Output = f64;
+    type Output = MaxDrawupOutput;
 
     fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
-        // Check if we should replace the last value (same time bucket)
-        let should_replace = self.detector.should_replace(timestamp);
+        // The window takes care of bucket replacement and old data removal
+        self.excursion.push(timestamp, value);
+        self.output()
+    }
+}
 
-        // ALWAYS remove old data first, regardless of replace/add
-        self.remove_old_data(timestamp);
+impl<T: Close> Next<&T> for MaxDrawup {
+    type Output = MaxDrawupOutput;
 
-        if should_replace && !self.window.is_empty() {
-            // Replace the last value in the same time bucket
-            self.window.pop_back();
-        }
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
 
-        // Add the new data point
-        self.window.push_back((timestamp, value));
+impl Validate for MaxDrawup {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.excursion.window().latest_time()
+    }
 
-        // Calculate the maximum drawup within the current window
-        self.calculate_max_drawup()
+    fn requires_positive(&self) -> bool {
+        true
+    }
+}
+
+impl Peek for MaxDrawup {
+    type Output = MaxDrawupOutput;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.excursion.largest().map(|_| self.output())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.excursion.window().is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.excursion.window().bucket_count()
     }
 }
 
 impl Reset for MaxDrawup {
     fn reset(&mut self) {
-        self.window.clear();
-        self.detector.reset();
+        self.excursion.clear();
     }
 }
 
 impl fmt::Display for MaxDrawup {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Change: Use as_secs() instead of num_seconds()
-        write!(f, "MaxDrawup({}s)", self.duration.as_secs())
+        write!(
+            f,
+            "MaxDrawup({})",
+            format_duration(self.excursion.window().duration())
+        )
     }
 }
 
 impl Default for MaxDrawup {
     fn default() -> Self {
-        // Change: Use Duration::from_secs for 14 days
-        Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap()
+        Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap() // 14 days in seconds
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_helper::*;
     use chrono::TimeZone;
 
+    test_indicator!(MaxDrawup);
+
     #[test]
     fn test_new() {
         // Change: Use std::time::Duration constructors
@@ -123,73 +233,161 @@ mod tests {
 
     #[test]
     fn test_next() {
-        let duration = Duration::from_secs(2);
+        // Holds 3 values: the one 3 seconds old has left the window
+        let duration = Duration::from_secs(3);
         let mut max = MaxDrawup::new(duration).unwrap();
-        let start_time = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
 
         // Use chrono::Duration for date arithmetic
-        assert_eq!(max.next((start_time, 4.0)), 0.0);
+        assert_eq!(max.next((start_time, 4.0)).max_drawup, 0.0);
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(1), 2.0)),
+            max.next((start_time + chrono::Duration::seconds(1), 2.0))
+                .max_drawup,
             0.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(2), 1.0)),
+            max.next((start_time + chrono::Duration::seconds(2), 1.0))
+                .max_drawup,
             0.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(3), 3.0)),
+            max.next((start_time + chrono::Duration::seconds(3), 3.0))
+                .max_drawup,
             200.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(4), 4.0)),
+            max.next((start_time + chrono::Duration::seconds(4), 4.0))
+                .max_drawup,
             300.0
         );
         assert_eq!(
-            crate::test_helper::round(max.next((start_time + chrono::Duration::seconds(5), 3.0))),
+            round(
+                max.next((start_time + chrono::Duration::seconds(5), 3.0))
+                    .max_drawup
+            ),
             33.333
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(6), 6.0)),
+            max.next((start_time + chrono::Duration::seconds(6), 6.0))
+                .max_drawup,
             100.0
         );
         assert_eq!(
-            max.next((start_time + chrono::Duration::seconds(7), 9.0)),
+            max.next((start_time + chrono::Duration::seconds(7), 9.0))
+                .max_drawup,
             200.0
         );
     }
 
+    #[test]
+    fn test_trough_peak_and_retracement() {
+        let mut max = MaxDrawup::new(DAY * 10).unwrap();
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let day = |n: i64| start_time + chrono::Duration::days(n);
+
+        max.next((day(0), 10.0));
+        max.next((day(1), 8.0));
+        max.next((day(2), 10.0));
+        let out = max.next((day(3), 12.0));
+        assert_eq!(out.max_drawup, 50.0);
+        assert_eq!((out.trough, out.trough_time), (8.0, day(1)));
+        assert_eq!((out.peak, out.peak_time), (12.0, day(3)));
+        assert_eq!(out.current_drawup, 50.0);
+        assert_eq!(out.retracement_time, None);
+
+        let out = max.next((day(4), 10.0));
+        assert_eq!(out.current_drawup, 25.0);
+        assert_eq!(out.retracement_time, None);
+
+        let out = max.next((day(5), 8.0));
+        assert_eq!(out.max_drawup, 50.0);
+        assert_eq!(out.current_drawup, 0.0);
+        assert_eq!(out.retracement_time, Some(DAY * 2));
+    }
+
+    #[test]
+    fn test_replacement_within_bucket() {
+        let mut max = MaxDrawup::new(DAY * 10).unwrap();
+        let open = Utc.with_ymd_and_hms(2020, 1, 1, 9, 30, 0).unwrap();
+        let next_open = open + chrono::Duration::days(1);
+
+        max.next((open, 10.0));
+        assert_eq!(max.next((next_open, 12.0)).max_drawup, 20.0);
+
+        // An intraday spike is revised away by later updates of the same session
+        let out = max.next((next_open + chrono::Duration::hours(1), 15.0));
+        assert_eq!(out.max_drawup, 50.0);
+        let out = max.next((next_open + chrono::Duration::hours(2), 11.0));
+        assert_eq!(out.max_drawup, 10.0);
+        assert_eq!(out.peak, 11.0);
+        assert_eq!(max.get_window().len(), 2);
+    }
+
+    #[test]
+    fn test_matches_window_scan() {
+        let duration = Duration::from_secs(30 * 60); // minute buckets
+        let mut max = MaxDrawup::new(duration).unwrap();
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+
+        for i in 0..2_000i64 {
+            let value = 1.0 + ((i * 7919) % 1000) as f64;
+            let out = max.next((start_time + chrono::Duration::seconds(i * 20), value));
+
+            let mut trough = f64::MAX;
+            let mut expected = 0.0;
+            for &(_, val) in max.get_window().iter() {
+                trough = trough.min(val);
+                expected = f64::max(expected, (val - trough) / trough);
+            }
+            assert_eq!(out.max_drawup, 100.0 * expected);
+            assert_eq!(out.current_drawup, 100.0 * ((value - trough) / trough));
+        }
+    }
+
     #[test]
     fn test_reset() {
-        let duration = Duration::from_secs(100);
+        let duration = Duration::from_secs(150);
         let mut max_drawup = MaxDrawup::new(duration).unwrap();
-        let start_time = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
 
-        assert_eq!(max_drawup.next((start_time, 4.0)), 0.0);
+        assert_eq!(max_drawup.next((start_time, 4.0)).max_drawup, 0.0);
 
         assert_eq!(
-            max_drawup.next((start_time + chrono::Duration::seconds(50), 10.0)),
+            max_drawup
+                .next((start_time + chrono::Duration::seconds(50), 10.0))
+                .max_drawup,
             150.0
         );
 
         assert_eq!(
-            max_drawup.next((start_time + chrono::Duration::seconds(100), 2.0)),
+            max_drawup
+                .next((start_time + chrono::Duration::seconds(100), 2.0))
+                .max_drawup,
             150.0
         );
 
         max_drawup.reset();
 
         assert_eq!(
-            max_drawup.next((start_time + chrono::Duration::seconds(150), 4.0)),
+            max_drawup
+                .next((start_time + chrono::Duration::seconds(150), 4.0))
+                .max_drawup,
             0.0
         );
 
         assert_eq!(
-            max_drawup.next((start_time + chrono::Duration::seconds(200), 8.0)),
+            max_drawup
+                .next((start_time + chrono::Duration::seconds(200), 8.0))
+                .max_drawup,
             100.0
         );
     }
 
+    #[test]
+    fn test_default() {
+        MaxDrawup::default();
+    }
+
     #[test]
     fn test_display() {
         let indicator = MaxDrawup::new(Duration::from_secs(7)).unwrap();
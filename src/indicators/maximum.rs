@@ -2,56 +2,66 @@ use std::collections::VecDeque;
 use std::fmt;
 use std::time::Duration; // Change: Use std::time::Duration
 
-use crate::errors::{Result, TaError};
-use crate::indicators::AdaptiveTimeDetector;
-use crate::{Next, Reset};
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, Highest, TimeWindow};
+use crate::{Close, Next, Peek, Reset, Validate};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Returns the highest value over a time window.
+///
+/// The candidates for the highest value are kept in a monotonic deque, so each update is
+/// amortised O(1) instead of a scan over the window. Replacing the last value of a time bucket
+/// restores the values it had pushed out of the deque.
 #[derive(Debug, Clone)]
 pub struct Maximum {
-    duration: Duration, // Now std::time::Duration
+    window: TimeWindow<Highest>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Maximum")]
+struct StateV1 {
+    window: TimeWindow<Highest>,
+}
+
+// The unversioned layout of ta 0.5
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LegacyState {
+    duration: Duration,
     window: VecDeque<(DateTime<Utc>, f64)>,
-    detector: AdaptiveTimeDetector,
+    detector: super::adaptive::LegacyDetector,
 }
 
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<Maximum> for LegacyState {
+    fn migrate(self) -> Result<Maximum> {
+        Ok(Maximum {
+            window: TimeWindow::from_legacy(self.duration, self.window, self.detector)?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(Maximum, StateV1, 1, 0 => LegacyState);
+
 impl Maximum {
     pub fn get_window(&self) -> VecDeque<(DateTime<Utc>, f64)> {
-        self.window.clone()
+        self.window.values().clone()
     }
 
     pub fn new(duration: Duration) -> Result<Self> {
-        // Change: Check for zero duration (std::time::Duration can't be negative)
-        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-            Err(TaError::InvalidParameter)
-        } else {
-            Ok(Self {
-                duration,
-                window: VecDeque::new(),
-                detector: AdaptiveTimeDetector::new(duration),
-            })
-        }
-    }
-
-    fn find_max_value(&self) -> f64 {
-        self.window
-            .iter()
-            .map(|&(_, val)| val)
-            .fold(f64::NEG_INFINITY, f64::max)
+        Self::with_policy(duration, BucketPolicy::Heuristic)
     }
 
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // Change: Convert std::time::Duration to chrono::Duration for date arithmetic
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time <= current_time - chrono_duration)
-        {
-            self.window.pop_front();
-        }
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            window: TimeWindow::with_policy(duration, policy)?,
+        })
     }
 }
 
@@ -66,36 +76,53 @@ impl Next<f64> for Maximum {
     type Output = f64;
 
     fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
-        // Check if we should replace the last value (same time bucket)
-        let should_replace = self.detector.should_replace(timestamp);
+        // The window takes care of bucket replacement and old data removal
+        self.window.push(timestamp, value);
 
-        // ALWAYS remove old data first, regardless of replace/add
-        self.remove_old_data(timestamp);
+        // The highest value in the current window
+        self.window.aggregate().value().unwrap_or(f64::NEG_INFINITY)
+    }
+}
 
-        if should_replace && !self.window.is_empty() {
-            // Replace the last value in the same time bucket
-            self.window.pop_back();
-        }
+impl<T: Close> Next<&T> for Maximum {
+    type Output = f64;
 
-        // Add the new data point
-        self.window.push_back((timestamp, value));
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
 
-        // Find the maximum value in the current window
-        self.find_max_value()
+impl Validate for Maximum {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.window.latest_time()
+    }
+}
+
+impl Peek for Maximum {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.window.aggregate().value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.window.bucket_count()
     }
 }
 
 impl Reset for Maximum {
     fn reset(&mut self) {
-        self.window.clear();
-        self.detector.reset();
+        self.window.reset();
     }
 }
 
 impl fmt::Display for Maximum {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Change: Use as_secs() instead of num_seconds()
-        write!(f, "MAX({}s)", self.duration.as_secs())
+        write!(f, "MAX({})", format_duration(self.window.duration()))
     }
 }
 
@@ -115,7 +142,7 @@ mod tests {
     fn test_next() {
         let duration = Duration::from_secs(2);
         let mut max = Maximum::new(duration).unwrap();
-        let start_time = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
 
         // Use chrono::Duration for date arithmetic
         assert_eq!(max.next((start_time, 4.0)), 4.0);
@@ -153,11 +180,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_replacement_restores_previous_values() {
+        let duration = Duration::from_secs(10 * 60); // minute buckets
+        let mut max = Maximum::new(duration).unwrap();
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let minute = |n: i64| start_time + chrono::Duration::minutes(n);
+
+        assert_eq!(max.next((minute(0), 3.0)), 3.0);
+        assert_eq!(max.next((minute(1), 5.0)), 5.0);
+        assert_eq!(max.next((minute(2), 4.0)), 5.0);
+
+        // A spike within the minute is revised away
+        assert_eq!(max.next((minute(3), 9.0)), 9.0);
+        assert_eq!(
+            max.next((minute(3) + chrono::Duration::seconds(30), 1.0)),
+            5.0
+        );
+
+        // Once 5.0 leaves the window, 4.0 is the highest again
+        assert_eq!(max.next((minute(11), 2.0)), 4.0);
+        assert_eq!(max.next((minute(12), 0.0)), 2.0);
+    }
+
+    #[test]
+    fn test_matches_window_scan() {
+        let duration = Duration::from_secs(30 * 60); // minute buckets
+        let mut max = Maximum::new(duration).unwrap();
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+
+        // Several updates per minute with a deterministic but irregular pattern
+        for i in 0..2_000i64 {
+            let value = ((i * 7919) % 1000) as f64;
+            let out = max.next((start_time + chrono::Duration::seconds(i * 20), value));
+            let expected = max
+                .get_window()
+                .iter()
+                .map(|&(_, val)| val)
+                .fold(f64::NEG_INFINITY, f64::max);
+            assert_eq!(out, expected);
+        }
+    }
+
     #[test]
     fn test_reset() {
         let duration = Duration::from_secs(100);
         let mut max = Maximum::new(duration).unwrap();
-        let start_time = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
 
         assert_eq!(max.next((start_time, 4.0)), 4.0);
         assert_eq!(
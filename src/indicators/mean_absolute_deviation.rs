@@ -6,49 +6,110 @@ use std::time::Duration;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::errors::{Result, TaError};
-use crate::indicators::AdaptiveTimeDetector;
-use crate::{Next, Reset};
+use crate::errors::Result;
+use crate::helpers::{format_duration, round_deviation};
+#[cfg(feature = "serde")]
+use crate::indicators::Sum;
+use crate::indicators::{BucketPolicy, TimeWindow};
+use crate::{Close, Next, Peek, Reset, Validate};
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct MeanAbsoluteDeviation {
-    duration: Duration, // Now std::time::Duration
+    window: TimeWindow<()>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MeanAbsoluteDeviation")]
+struct StateV2 {
+    window: TimeWindow<()>,
+}
+
+// The layout of version 1, with a running sum for the mean
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct StateV1 {
+    window: TimeWindow<Sum>,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<MeanAbsoluteDeviation> for StateV1 {
+    fn migrate(self) -> Result<MeanAbsoluteDeviation> {
+        Ok(MeanAbsoluteDeviation {
+            window: self.window.rebuild(()),
+        })
+    }
+}
+
+// The unversioned layout of ta 0.5
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LegacyState {
+    duration: Duration,
+    #[allow(dead_code)] // recomputed from the window
     sum: f64,
     window: VecDeque<(DateTime<Utc>, f64)>,
-    detector: AdaptiveTimeDetector,
+    detector: super::adaptive::LegacyDetector,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<MeanAbsoluteDeviation> for LegacyState {
+    fn migrate(self) -> Result<MeanAbsoluteDeviation> {
+        Ok(MeanAbsoluteDeviation {
+            window: TimeWindow::from_legacy(self.duration, self.window, self.detector)?,
+        })
+    }
 }
 
+#[cfg(feature = "serde")]
+versioned_serde!(MeanAbsoluteDeviation, StateV2, 2, 0 => LegacyState, 1 => StateV1);
+
 impl MeanAbsoluteDeviation {
     pub fn get_window(&self) -> VecDeque<(DateTime<Utc>, f64)> {
-        self.window.clone()
+        self.window.values().clone()
     }
     pub fn new(duration: Duration) -> Result<Self> {
-        // std::time::Duration can't be negative, so just check if it's zero
-        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-            Err(TaError::InvalidParameter)
-        } else {
-            Ok(Self {
-                duration,
-                sum: 0.0,
-                window: VecDeque::new(),
-                detector: AdaptiveTimeDetector::new(duration),
-            })
-        }
+        Self::with_policy(duration, BucketPolicy::Heuristic)
     }
 
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // Convert std::time::Duration to chrono::Duration for the subtraction
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time <= current_time - chrono_duration)
-        {
-            if let Some((_, value)) = self.window.pop_front() {
-                self.sum -= value;
-            }
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            window: TimeWindow::with_policy(duration, policy)?,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.window.duration()
+    }
+
+    /// The latest value of the window.
+    pub(super) fn latest(&self) -> Option<f64> {
+        self.window.latest()
+    }
+
+    /// Adds a value without computing the deviation, for indicators reading the mean too.
+    pub(super) fn push(&mut self, timestamp: DateTime<Utc>, value: f64) {
+        self.window.push(timestamp, value);
+    }
+
+    // Mean of the window and mean absolute deviation from it, both 0 while it is empty.
+    // The mean is summed over the window like the deviation: a running sum drifts once a
+    // large value has passed through it, and a flat window would deviate from it.
+    pub(super) fn mean_and_deviation(&self) -> (f64, f64) {
+        if self.window.is_empty() {
+            return (0.0, 0.0);
         }
+        let len = self.window.len();
+        let values = || self.window.values().iter().map(|&(_, val)| val);
+        let mean = values().sum::<f64>() / len as f64;
+
+        let mad = values().map(|val| (val - mean).abs()).sum::<f64>() / len as f64;
+        (mean, round_deviation(mad, len, mean))
+    }
+
+    fn deviation(&self) -> f64 {
+        self.mean_and_deviation().1
     }
 }
 
@@ -56,42 +117,49 @@ impl Next<f64> for MeanAbsoluteDeviation {
     type Output = f64;
 
     fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
-        // Check if we should replace the last value (same time bucket)
-        let should_replace = self.detector.should_replace(timestamp);
-
-        // ALWAYS remove old data first, regardless of replace/add
-        self.remove_old_data(timestamp);
+        // The window takes care of bucket replacement and old data removal
+        self.window.push(timestamp, value);
+        self.deviation()
+    }
+}
 
-        if should_replace && !self.window.is_empty() {
-            // Replace the last value in the same time bucket
-            if let Some((_, old_value)) = self.window.pop_back() {
-                self.sum -= old_value;
-            }
-        }
+impl<T: Close> Next<&T> for MeanAbsoluteDeviation {
+    type Output = f64;
 
-        self.window.push_back((timestamp, value));
-        self.sum += value;
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
 
-        let mean = self.sum / self.window.len() as f64;
+impl Validate for MeanAbsoluteDeviation {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.window.latest_time()
+    }
+}
 
-        let mut mad = 0.0;
-        for &(_, val) in &self.window {
-            mad += (val - mean).abs();
-        }
+impl Peek for MeanAbsoluteDeviation {
+    type Output = f64;
 
+    fn current(&self) -> Option<Self::Output> {
         if self.window.is_empty() {
-            0.0
+            None
         } else {
-            mad / self.window.len() as f64
+            Some(self.deviation())
         }
     }
+
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.window.bucket_count()
+    }
 }
 
 impl Reset for MeanAbsoluteDeviation {
     fn reset(&mut self) {
-        self.sum = 0.0;
-        self.window.clear();
-        self.detector.reset();
+        self.window.reset();
     }
 }
 
@@ -104,8 +172,7 @@ impl Default for MeanAbsoluteDeviation {
 
 impl fmt::Display for MeanAbsoluteDeviation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Use as_secs() instead of Debug format
-        write!(f, "MAD({}s)", self.duration.as_secs())
+        write!(f, "MAD({})", format_duration(self.window.duration()))
     }
 }
 
@@ -117,7 +184,7 @@ mod tests {
 
     // Helper function to create a Utc DateTime from a timestamp
     fn to_utc_datetime(timestamp: i64) -> DateTime<Utc> {
-        Utc.timestamp(timestamp, 0)
+        Utc.timestamp_opt(timestamp, 0).unwrap()
     }
 
     #[test]
@@ -3,92 +3,115 @@ use std::fmt;
 use std::time::Duration; // Change: Use std::time::Duration
 
 use crate::errors::Result;
-use crate::indicators::AdaptiveTimeDetector;
-use crate::{Next, Reset};
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, Lowest, TimeWindow};
+use crate::{Close, Next, Peek, Reset, Validate};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Returns the lowest value over a time window.
+///
+/// The candidates for the lowest value are kept in a monotonic deque, so each update is
+/// amortised O(1) instead of a scan over the window. Replacing the last value of a time bucket
+/// restores the values it had pushed out of the deque.
 #[derive(Debug, Clone)]
 pub struct Minimum {
-    duration: Duration, // Now std::time::Duration
+    window: TimeWindow<Lowest>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Minimum")]
+struct StateV1 {
+    window: TimeWindow<Lowest>,
+}
+
+// The unversioned layout of ta 0.5
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LegacyState {
+    duration: Duration,
     window: VecDeque<(DateTime<Utc>, f64)>,
+    #[allow(dead_code)] // recomputed from the window
     min_value: f64,
-    detector: AdaptiveTimeDetector,
+    detector: super::adaptive::LegacyDetector,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<Minimum> for LegacyState {
+    fn migrate(self) -> Result<Minimum> {
+        Ok(Minimum {
+            window: TimeWindow::from_legacy(self.duration, self.window, self.detector)?,
+        })
+    }
 }
 
+#[cfg(feature = "serde")]
+versioned_serde!(Minimum, StateV1, 1, 0 => LegacyState);
+
 impl Minimum {
     pub fn get_window(&self) -> VecDeque<(DateTime<Utc>, f64)> {
-        self.window.clone()
+        self.window.values().clone()
     }
 
     pub fn new(duration: Duration) -> Result<Self> {
-        // Change: Check for zero duration (std::time::Duration can't be negative)
-        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-            return Err(crate::errors::TaError::InvalidParameter);
-        }
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
         Ok(Self {
-            duration,
-            window: VecDeque::new(),
-            min_value: f64::INFINITY,
-            detector: AdaptiveTimeDetector::new(duration),
+            window: TimeWindow::with_policy(duration, policy)?,
         })
     }
+}
 
-    fn update_min(&mut self) {
-        self.min_value = self
-            .window
-            .iter()
-            .map(|&(_, val)| val)
-            .fold(f64::INFINITY, f64::min);
-    }
+impl Next<f64> for Minimum {
+    type Output = f64;
 
-    fn remove_old(&mut self, current_time: DateTime<Utc>) {
-        // Change: Convert std::time::Duration to chrono::Duration for date arithmetic
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |&(time, _)| time < current_time - chrono_duration)
-        {
-            self.window.pop_front();
-        }
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        // The window takes care of bucket replacement and old data removal
+        self.window.push(timestamp, value);
+
+        // The lowest value in the current window
+        self.window.aggregate().value().unwrap_or(f64::INFINITY)
     }
 }
 
-impl Next<f64> for Minimum {
+impl<T: Close> Next<&T> for Minimum {
     type Output = f64;
 
-    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
-        // Check if we should replace the last value (same time bucket)
-        let should_replace = self.detector.should_replace(timestamp);
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
 
-        if should_replace && !self.window.is_empty() {
-            // Replace the last value in the same time bucket
-            self.window.pop_back();
-        } else {
-            // New time period - remove old data first
-            self.remove_old(timestamp);
-        }
+impl Validate for Minimum {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.window.latest_time()
+    }
+}
 
-        self.window.push_back((timestamp, value));
+impl Peek for Minimum {
+    type Output = f64;
 
-        if value < self.min_value {
-            self.min_value = value;
-        } else {
-            self.update_min();
-        }
+    fn current(&self) -> Option<Self::Output> {
+        self.window.aggregate().value()
+    }
 
-        self.min_value
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.window.bucket_count()
     }
 }
 
 impl Reset for Minimum {
     fn reset(&mut self) {
-        self.window.clear();
-        self.min_value = f64::INFINITY;
-        self.detector.reset();
+        self.window.reset();
     }
 }
 
@@ -101,20 +124,20 @@ impl Default for Minimum {
 
 impl fmt::Display for Minimum {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Change: Calculate days from seconds
-        let days = self.duration.as_secs() / 86400;
-        write!(f, "MIN({} days)", days)
+        write!(f, "MIN({})", format_duration(self.window.duration()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::{TimeZone, Utc};
+    use chrono::NaiveDateTime;
 
     // Helper function to create a DateTime<Utc> from a date string for testing
     fn datetime(s: &str) -> DateTime<Utc> {
-        Utc.datetime_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_utc()
     }
 
     #[test]
@@ -132,15 +155,31 @@ mod tests {
         assert_eq!(min.next((datetime("2023-01-01 00:00:00"), 4.0)), 4.0);
         assert_eq!(min.next((datetime("2023-01-02 00:00:00"), 1.2)), 1.2);
         assert_eq!(min.next((datetime("2023-01-03 00:00:00"), 5.0)), 1.2);
-        assert_eq!(min.next((datetime("2023-01-04 00:00:00"), 3.0)), 1.2);
+        assert_eq!(min.next((datetime("2023-01-04 00:00:00"), 3.0)), 3.0);
         assert_eq!(min.next((datetime("2023-01-05 00:00:00"), 4.0)), 3.0);
-        assert_eq!(min.next((datetime("2023-01-06 00:00:00"), 6.0)), 3.0);
-        assert_eq!(min.next((datetime("2023-01-07 00:00:00"), 7.0)), 4.0);
-        assert_eq!(min.next((datetime("2023-01-08 00:00:00"), 8.0)), 6.0);
+        assert_eq!(min.next((datetime("2023-01-06 00:00:00"), 6.0)), 4.0);
+        assert_eq!(min.next((datetime("2023-01-07 00:00:00"), 7.0)), 6.0);
+        assert_eq!(min.next((datetime("2023-01-08 00:00:00"), 8.0)), 7.0);
         assert_eq!(min.next((datetime("2023-01-09 00:00:00"), -9.0)), -9.0);
         assert_eq!(min.next((datetime("2023-01-10 00:00:00"), 0.0)), -9.0);
     }
 
+    #[test]
+    fn test_replacement_restores_previous_values() {
+        let duration = Duration::from_secs(2 * 86400); // 2 days
+        let mut min = Minimum::new(duration).unwrap();
+
+        assert_eq!(min.next((datetime("2023-01-01 09:30:00"), 4.0)), 4.0);
+        assert_eq!(min.next((datetime("2023-01-02 09:30:00"), 3.0)), 3.0);
+        assert_eq!(min.next((datetime("2023-01-03 09:30:00"), 5.0)), 3.0);
+
+        // An intraday dip is revised away later in the session
+        assert_eq!(min.next((datetime("2023-01-04 09:30:00"), 1.0)), 1.0);
+        assert_eq!(min.next((datetime("2023-01-04 10:30:00"), 6.0)), 5.0);
+
+        assert_eq!(min.next((datetime("2023-01-05 09:30:00"), 7.0)), 6.0);
+    }
+
     #[test]
     fn test_reset() {
         let duration = Duration::from_secs(10 * 86400); // 10 days
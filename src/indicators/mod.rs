@@ -4,32 +4,83 @@ pub use self::exponential_moving_average::ExponentialMovingAverage;
 mod simple_moving_average;
 pub use self::simple_moving_average::SimpleMovingAverage;
 
+mod weighted_moving_average;
+pub use self::weighted_moving_average::WeightedMovingAverage;
+
+mod kaufman_adaptive_moving_average;
+pub use self::kaufman_adaptive_moving_average::KaufmanAdaptiveMovingAverage;
+
 mod standard_deviation;
 pub use self::standard_deviation::StandardDeviation;
 
 mod mean_absolute_deviation;
 pub use self::mean_absolute_deviation::MeanAbsoluteDeviation;
 
+mod commodity_channel_index;
+pub use self::commodity_channel_index::CommodityChannelIndex;
+
 mod relative_strength_index;
 pub use self::relative_strength_index::RelativeStrengthIndex;
 
+mod money_flow_index;
+pub use self::money_flow_index::MoneyFlowIndex;
+
+mod on_balance_volume;
+pub use self::on_balance_volume::OnBalanceVolume;
+
 mod minimum;
 pub use self::minimum::Minimum;
 
 mod maximum;
 pub use self::maximum::Maximum;
 
+mod fast_stochastic;
+pub use self::fast_stochastic::FastStochastic;
+
+mod slow_stochastic;
+pub use self::slow_stochastic::SlowStochastic;
+
+mod true_range;
+pub use self::true_range::TrueRange;
+
+mod average_true_range;
+pub use self::average_true_range::AverageTrueRange;
+
+mod excursion;
+
 mod max_drawdown;
-pub use self::max_drawdown::MaxDrawdown;
+pub use self::max_drawdown::{MaxDrawdown, MaxDrawdownOutput};
 
 mod max_drawup;
-pub use self::max_drawup::MaxDrawup;
+pub use self::max_drawup::{MaxDrawup, MaxDrawupOutput};
 
 mod bollinger_bands;
 pub use self::bollinger_bands::{BollingerBands, BollingerBandsOutput};
 
+mod keltner_channel;
+pub use self::keltner_channel::{KeltnerChannel, KeltnerChannelOutput};
+
+mod chandelier_exit;
+pub use self::chandelier_exit::{ChandelierExit, ChandelierExitOutput};
+
+mod moving_average_convergence_divergence;
+pub use self::moving_average_convergence_divergence::{
+    MovingAverageConvergenceDivergence, MovingAverageConvergenceDivergenceOutput,
+};
+
+mod percentage_price_oscillator;
+pub use self::percentage_price_oscillator::{
+    PercentagePriceOscillator, PercentagePriceOscillatorOutput,
+};
+
 mod rate_of_change;
 pub use self::rate_of_change::RateOfChange;
 
+mod efficiency_ratio;
+pub use self::efficiency_ratio::EfficiencyRatio;
+
 mod adaptive;
-pub use self::adaptive::{AdaptiveTimeDetector, DetectedFrequency};
+pub use self::adaptive::{AdaptiveTimeDetector, BucketPolicy, DetectedFrequency};
+
+mod time_window;
+pub use self::time_window::{Aggregate, Highest, Lowest, Sum, SumOfSquares, TimeWindow};
@@ -0,0 +1,367 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{AdaptiveTimeDetector, Aggregate, BucketPolicy, TimeWindow};
+use crate::traits::check_with_volume;
+use crate::{Close, High, Low, Next, Peek, Reset, TryNext, Volume};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Money Flow Index (MFI).
+///
+/// A volume weighted RSI. The money flow of a period is its typical price
+/// `(high + low + close) / 3` times its volume. It is positive when the typical price rose
+/// against the previous period and negative when it fell:
+///
+/// `MFI = 100 - 100 / (1 + positive money flow / negative money flow)`
+///
+/// with the money flows summed over the time window. A period whose typical price equals the
+/// previous one counts as neither, as does the first period. Without any positive or negative
+/// money flow in the window, e.g. for a flat price, the MFI is 50.
+///
+/// Updates within the same time bucket replace the money flow of the bucket, measured against
+/// the typical price of the previous bucket, so the volume of a bar in progress is counted once.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::MoneyFlowIndex;
+/// use ta::{DataItem, Next};
+///
+/// let mut mfi = MoneyFlowIndex::new(Duration::from_secs(3 * 86400)).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+/// let bar = |high: f64, low: f64, close: f64, volume: f64| {
+///     DataItem::builder()
+///         .open(close)
+///         .high(high)
+///         .low(low)
+///         .close(close)
+///         .volume(volume)
+///         .build()
+///         .unwrap()
+/// };
+///
+/// assert_eq!(mfi.next((start, &bar(12.0, 9.0, 12.0, 100.0))), 50.0);
+/// // The typical price rose from 11 to 12
+/// let day = start + ChronoDuration::days(1);
+/// assert_eq!(mfi.next((day, &bar(13.0, 11.0, 12.0, 200.0))), 100.0);
+/// // A revision of the day replaces its money flow
+/// let revision = day + ChronoDuration::hours(1);
+/// assert_eq!(mfi.next((revision, &bar(11.0, 9.0, 10.0, 250.0))), 0.0);
+/// ```
+#[doc(alias = "MFI")]
+#[derive(Debug, Clone)]
+pub struct MoneyFlowIndex {
+    prev_typical_price: Option<f64>,
+    typical_price: Option<f64>,
+    detector: AdaptiveTimeDetector,
+    money_flows: TimeWindow<MoneyFlows>,
+}
+
+// Sums of the positive and negative money flows of the window, which holds the flows signed.
+// The counts bring a sum back to exactly 0 once its last flow has left the window.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+struct MoneyFlows {
+    positive: f64,
+    negative: f64,
+    positive_count: usize,
+    negative_count: usize,
+}
+
+impl MoneyFlows {
+    fn add(&mut self, flow: f64) {
+        if flow > 0.0 {
+            self.positive += flow;
+            self.positive_count += 1;
+        } else if flow < 0.0 {
+            self.negative -= flow;
+            self.negative_count += 1;
+        }
+    }
+
+    fn remove(&mut self, flow: f64) {
+        if flow > 0.0 {
+            self.positive_count -= 1;
+            self.positive = if self.positive_count == 0 {
+                0.0
+            } else {
+                self.positive - flow
+            };
+        } else if flow < 0.0 {
+            self.negative_count -= 1;
+            self.negative = if self.negative_count == 0 {
+                0.0
+            } else {
+                self.negative + flow
+            };
+        }
+    }
+
+    fn index(&self) -> f64 {
+        if self.negative == 0.0 {
+            if self.positive == 0.0 {
+                50.0
+            } else {
+                100.0
+            }
+        } else {
+            100.0 - 100.0 / (1.0 + self.positive / self.negative)
+        }
+    }
+}
+
+impl Aggregate for MoneyFlows {
+    fn push(&mut self, (_, flow): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.add(flow);
+    }
+
+    fn evict(&mut self, (_, flow): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.remove(flow);
+    }
+
+    fn pop_back(&mut self, (_, flow): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.remove(flow);
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MoneyFlowIndex")]
+struct StateV1 {
+    prev_typical_price: Option<f64>,
+    typical_price: Option<f64>,
+    detector: AdaptiveTimeDetector,
+    money_flows: TimeWindow<MoneyFlows>,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(MoneyFlowIndex, StateV1, 1);
+
+impl MoneyFlowIndex {
+    pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            prev_typical_price: None,
+            typical_price: None,
+            money_flows: TimeWindow::with_policy(duration, policy.clone())?,
+            detector: AdaptiveTimeDetector::with_policy(duration, policy)?,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.money_flows.duration()
+    }
+}
+
+impl<T: High + Low + Close + Volume> Next<&T> for MoneyFlowIndex {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        // When replacing, the previous typical price stays the one of the previous period
+        if !self.detector.should_replace(timestamp) {
+            self.prev_typical_price = self.typical_price;
+        }
+
+        let typical_price = (input.high() + input.low() + input.close()) / 3.0;
+        let money_flow = typical_price * input.volume();
+        let flow = match self.prev_typical_price {
+            Some(prev) if typical_price > prev => money_flow,
+            Some(prev) if typical_price < prev => -money_flow,
+            _ => 0.0,
+        };
+
+        self.typical_price = Some(typical_price);
+        self.money_flows.push(timestamp, flow);
+        self.money_flows.aggregate().index()
+    }
+}
+
+/// Checks the high, low, close and volume of the bars, see [TryNext](../trait.TryNext.html).
+impl<'a, T: High + Low + Close + Volume> TryNext<&'a T> for MoneyFlowIndex {
+    fn try_next(&mut self, (timestamp, input): (DateTime<Utc>, &'a T)) -> Result<Self::Output> {
+        let prices = [input.high(), input.low(), input.close()];
+        let latest = self.detector.latest();
+        check_with_volume(self, latest, timestamp, &prices, input.volume())?;
+        Ok(self.next((timestamp, input)))
+    }
+}
+
+impl Peek for MoneyFlowIndex {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        if self.money_flows.is_empty() {
+            None
+        } else {
+            Some(self.money_flows.aggregate().index())
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.money_flows.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.detector.bucket_count()
+    }
+}
+
+impl Reset for MoneyFlowIndex {
+    fn reset(&mut self) {
+        self.prev_typical_price = None;
+        self.typical_price = None;
+        self.detector.reset();
+        self.money_flows.reset();
+    }
+}
+
+impl Default for MoneyFlowIndex {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap()
+    }
+}
+
+impl fmt::Display for MoneyFlowIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MFI({})", format_duration(self.duration()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::TaError;
+    use crate::test_helper::*;
+
+    fn bar(high: f64, low: f64, close: f64, volume: f64) -> Bar {
+        Bar::new().high(high).low(low).close(close).volume(volume)
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(MoneyFlowIndex::new(Duration::from_secs(0)).is_err());
+        assert!(MoneyFlowIndex::new(DAY).is_ok());
+    }
+
+    #[test]
+    fn test_next() {
+        let mut mfi = MoneyFlowIndex::new(DAY * 3).unwrap();
+
+        let bars = [
+            (bar(12.0, 9.0, 10.0, 100.0), 50.0),
+            (bar(13.0, 10.0, 12.0, 100.0), 100.0),
+            (bar(12.0, 10.0, 11.0, 200.0), 34.653),
+            // The same typical price, and day 0 has left the window
+            (bar(12.0, 10.0, 11.0, 300.0), 34.653),
+            (bar(11.0, 9.0, 10.0, 100.0), 0.0),
+        ];
+        for (n, (bar, expected)) in bars.iter().enumerate() {
+            assert_eq!(round(mfi.next((day(n as i64), bar))), *expected);
+        }
+    }
+
+    #[test]
+    fn test_equal_typical_prices() {
+        let mut mfi = MoneyFlowIndex::new(DAY * 2).unwrap();
+
+        mfi.next((day(0), &bar(12.0, 9.0, 10.0, 300.0)));
+        assert_eq!(mfi.next((day(1), &bar(13.0, 10.0, 12.0, 700.0))), 100.0);
+        // Once the rise has left the window, a flat price is neutral again
+        assert_eq!(mfi.next((day(2), &bar(13.0, 10.0, 12.0, 500.0))), 100.0);
+        assert_eq!(mfi.next((day(3), &bar(12.0, 11.0, 12.0, 100.0))), 50.0);
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut mfi = MoneyFlowIndex::new(DAY * 3).unwrap();
+
+        mfi.next((day(0), &bar(12.0, 9.0, 10.0, 100.0)));
+        mfi.next((day(1), &bar(13.0, 10.0, 12.0, 100.0)));
+        // The revision replaces the rise of day 1 with a fall from day 0
+        let revision = day(1) + chrono::Duration::hours(1);
+        assert_eq!(mfi.next((revision, &bar(11.0, 9.0, 10.0, 200.0))), 0.0);
+        assert_eq!(mfi.sample_count(), 2);
+
+        assert_eq!(
+            round(mfi.next((day(2), &bar(12.0, 10.0, 11.0, 100.0)))),
+            35.484
+        );
+    }
+
+    #[test]
+    fn test_try_next() {
+        let mut mfi = MoneyFlowIndex::new(DAY * 3).unwrap();
+
+        mfi.try_next((day(0), &bar(12.0, 9.0, 10.0, 100.0)))
+            .unwrap();
+        assert!(matches!(
+            mfi.try_next((day(1), &bar(13.0, 10.0, 12.0, f64::NAN))),
+            Err(TaError::NotFinite { .. })
+        ));
+        assert!(matches!(
+            mfi.try_next((day(1), &bar(13.0, 10.0, 12.0, -1.0))),
+            Err(TaError::NotPositive { value, .. }) if value == -1.0
+        ));
+        assert!(matches!(
+            mfi.try_next((day(1), &bar(f64::NAN, 10.0, 12.0, 100.0))),
+            Err(TaError::NotFinite { .. })
+        ));
+
+        let mut expected = MoneyFlowIndex::new(DAY * 3).unwrap();
+        expected.next((day(0), &bar(12.0, 9.0, 10.0, 100.0)));
+        assert_eq!(
+            mfi.try_next((day(1), &bar(13.0, 10.0, 12.0, 200.0))),
+            Ok(expected.next((day(1), &bar(13.0, 10.0, 12.0, 200.0))))
+        );
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut mfi = MoneyFlowIndex::new(DAY * 2).unwrap();
+        assert_eq!(mfi.current(), None);
+
+        mfi.next((day(0), &bar(12.0, 9.0, 10.0, 100.0)));
+        let value = mfi.next((day(1), &bar(13.0, 10.0, 12.0, 100.0)));
+        assert_eq!(mfi.current(), Some(value));
+        assert_eq!(mfi.sample_count(), 2);
+        assert!(!mfi.is_ready());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut mfi = MoneyFlowIndex::new(DAY * 3).unwrap();
+
+        mfi.next((day(0), &bar(12.0, 9.0, 10.0, 100.0)));
+        mfi.next((day(1), &bar(13.0, 10.0, 12.0, 100.0)));
+        mfi.reset();
+        assert_eq!(mfi.current(), None);
+        assert_eq!(mfi.sample_count(), 0);
+        assert_eq!(mfi.next((day(2), &bar(13.0, 10.0, 12.0, 100.0))), 50.0);
+    }
+
+    #[test]
+    fn test_default() {
+        MoneyFlowIndex::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let mfi = MoneyFlowIndex::new(DAY * 14).unwrap();
+        assert_eq!(format!("{}", mfi), "MFI(14 days)");
+    }
+}
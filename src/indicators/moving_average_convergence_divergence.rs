@@ -0,0 +1,279 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, ExponentialMovingAverage as Ema};
+use crate::{Close, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Moving average convergence divergence (MACD).
+///
+/// The MACD line is the difference between a fast and a slow
+/// [EMA](struct.ExponentialMovingAverage.html), the signal line is an EMA of the MACD line
+/// and the histogram is the difference between the two.
+///
+/// All three averages are duration based [ExponentialMovingAverage](struct.ExponentialMovingAverage.html)s,
+/// so an update that falls into the same time bucket as the previous one revises the current
+/// MACD, signal and histogram instead of appending a new period.
+///
+/// # Parameters
+///
+/// * `fast_duration` - window of the fast EMA (e.g. 12 days)
+/// * `slow_duration` - window of the slow EMA (e.g. 26 days)
+/// * `signal_duration` - window of the signal EMA (e.g. 9 days)
+///
+/// # Example
+///
+/// ```
+/// use chrono::Utc;
+/// use std::time::Duration;
+/// use ta::indicators::MovingAverageConvergenceDivergence as Macd;
+/// use ta::Next;
+///
+/// let day = Duration::from_secs(86400);
+/// let mut macd = Macd::new(day * 3, day * 6, day * 4).unwrap();
+/// let now = Utc::now();
+///
+/// let out = macd.next((now, 2.0));
+/// assert_eq!(out.macd, 0.0);
+/// assert_eq!(out.signal, 0.0);
+/// assert_eq!(out.histogram, 0.0);
+/// ```
+#[doc(alias = "MACD")]
+#[derive(Debug, Clone)]
+pub struct MovingAverageConvergenceDivergence {
+    fast_ema: Ema,
+    slow_ema: Ema,
+    signal_ema: Ema,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MovingAverageConvergenceDivergence")]
+struct StateV1 {
+    fast_ema: Ema,
+    slow_ema: Ema,
+    signal_ema: Ema,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(MovingAverageConvergenceDivergence, StateV1, 1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovingAverageConvergenceDivergenceOutput {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+impl From<MovingAverageConvergenceDivergenceOutput> for (f64, f64, f64) {
+    fn from(mo: MovingAverageConvergenceDivergenceOutput) -> Self {
+        (mo.macd, mo.signal, mo.histogram)
+    }
+}
+
+impl MovingAverageConvergenceDivergence {
+    pub fn new(
+        fast_duration: Duration,
+        slow_duration: Duration,
+        signal_duration: Duration,
+    ) -> Result<Self> {
+        Self::with_policy(
+            fast_duration,
+            slow_duration,
+            signal_duration,
+            BucketPolicy::Heuristic,
+        )
+    }
+
+    /// Like [new](#method.new), with the time buckets of all three EMAs set by `policy`.
+    pub fn with_policy(
+        fast_duration: Duration,
+        slow_duration: Duration,
+        signal_duration: Duration,
+        policy: BucketPolicy,
+    ) -> Result<Self> {
+        Ok(Self {
+            fast_ema: Ema::with_policy(fast_duration, policy.clone())?,
+            slow_ema: Ema::with_policy(slow_duration, policy.clone())?,
+            signal_ema: Ema::with_policy(signal_duration, policy)?,
+        })
+    }
+}
+
+impl Next<f64> for MovingAverageConvergenceDivergence {
+    type Output = MovingAverageConvergenceDivergenceOutput;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        // Each EMA reverses its previous step on its own when the timestamp
+        // falls into the bucket it has already seen, so the whole output is revised
+        let fast_val = self.fast_ema.next((timestamp, value));
+        let slow_val = self.slow_ema.next((timestamp, value));
+
+        let macd = fast_val - slow_val;
+        let signal = self.signal_ema.next((timestamp, macd));
+        let histogram = macd - signal;
+
+        MovingAverageConvergenceDivergenceOutput {
+            macd,
+            signal,
+            histogram,
+        }
+    }
+}
+
+impl<T: Close> Next<&T> for MovingAverageConvergenceDivergence {
+    type Output = MovingAverageConvergenceDivergenceOutput;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for MovingAverageConvergenceDivergence {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.fast_ema.latest_time()
+    }
+}
+
+impl Peek for MovingAverageConvergenceDivergence {
+    type Output = MovingAverageConvergenceDivergenceOutput;
+
+    fn current(&self) -> Option<Self::Output> {
+        let macd = self.fast_ema.current()? - self.slow_ema.current()?;
+        let signal = self.signal_ema.current()?;
+        Some(MovingAverageConvergenceDivergenceOutput {
+            macd,
+            signal,
+            histogram: macd - signal,
+        })
+    }
+
+    fn is_ready(&self) -> bool {
+        self.fast_ema.is_ready() && self.slow_ema.is_ready() && self.signal_ema.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.fast_ema.sample_count()
+    }
+}
+
+impl Reset for MovingAverageConvergenceDivergence {
+    fn reset(&mut self) {
+        self.fast_ema.reset();
+        self.slow_ema.reset();
+        self.signal_ema.reset();
+    }
+}
+
+impl Default for MovingAverageConvergenceDivergence {
+    fn default() -> Self {
+        let day = Duration::from_secs(24 * 60 * 60);
+        Self::new(day * 12, day * 26, day * 9).unwrap()
+    }
+}
+
+impl fmt::Display for MovingAverageConvergenceDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "MACD({}, {}, {})",
+            format_duration(self.fast_ema.duration()),
+            format_duration(self.slow_ema.duration()),
+            format_duration(self.signal_ema.duration())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+    use chrono::TimeZone;
+
+    type Macd = MovingAverageConvergenceDivergence;
+
+    test_indicator!(Macd);
+
+    fn round(nums: (f64, f64, f64)) -> (f64, f64, f64) {
+        let n0 = (nums.0 * 100.0).round() / 100.0;
+        let n1 = (nums.1 * 100.0).round() / 100.0;
+        let n2 = (nums.2 * 100.0).round() / 100.0;
+        (n0, n1, n2)
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(Macd::new(Duration::from_secs(0), DAY, DAY).is_err());
+        assert!(Macd::new(DAY, Duration::from_secs(0), DAY).is_err());
+        assert!(Macd::new(DAY, DAY, Duration::from_secs(0)).is_err());
+        assert!(Macd::new(DAY * 2, DAY, DAY).is_ok());
+    }
+
+    #[test]
+    fn test_macd() {
+        let mut macd = Macd::new(DAY * 3, DAY * 6, DAY * 4).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let day = |n: i64| start + chrono::Duration::days(n);
+
+        assert_eq!(round(macd.next((day(0), 2.0)).into()), (0.0, 0.0, 0.0));
+        assert_eq!(round(macd.next((day(1), 3.0)).into()), (0.21, 0.09, 0.13));
+        assert_eq!(round(macd.next((day(2), 4.2)).into()), (0.52, 0.26, 0.26));
+        assert_eq!(round(macd.next((day(3), 7.0)).into()), (1.15, 0.62, 0.54));
+        assert_eq!(round(macd.next((day(4), 6.7)).into()), (1.15, 0.83, 0.32));
+        assert_eq!(round(macd.next((day(5), 6.5)).into()), (0.94, 0.87, 0.07));
+    }
+
+    #[test]
+    fn test_replacement_within_bucket() {
+        let mut macd = Macd::new(DAY * 3, DAY * 6, DAY * 4).unwrap();
+        let mut expected = macd.clone();
+        let open = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
+        let next_day = open + chrono::Duration::days(1);
+
+        macd.next((open, 2.0));
+        expected.next((open, 2.0));
+
+        // Intraday updates on the next day revise the same slot
+        macd.next((next_day, 3.5));
+        macd.next((next_day + chrono::Duration::minutes(30), 2.5));
+        let revised = macd.next((next_day + chrono::Duration::hours(1), 3.0));
+
+        assert_eq!(
+            round(revised.into()),
+            round(expected.next((next_day, 3.0)).into())
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut macd = Macd::new(DAY * 3, DAY * 6, DAY * 4).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(round(macd.next((start, 2.0)).into()), (0.0, 0.0, 0.0));
+        macd.next((start + chrono::Duration::days(1), 3.0));
+        macd.next((start + chrono::Duration::days(2), 4.2));
+
+        macd.reset();
+
+        assert_eq!(round(macd.next((start, 2.0)).into()), (0.0, 0.0, 0.0));
+        assert_eq!(
+            round(macd.next((start + chrono::Duration::days(1), 3.0)).into()),
+            (0.21, 0.09, 0.13)
+        );
+    }
+
+    #[test]
+    fn test_default() {
+        Macd::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let indicator = Macd::new(DAY * 13, DAY * 30, DAY * 10).unwrap();
+        assert_eq!(format!("{}", indicator), "MACD(13 days, 30 days, 10 days)");
+    }
+}
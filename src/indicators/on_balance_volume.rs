@@ -0,0 +1,273 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{AdaptiveTimeDetector, BucketPolicy, Sum, TimeWindow};
+use crate::traits::check_with_volume;
+use crate::{Close, Next, Peek, Reset, TryNext, Volume};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// On Balance Volume (OBV).
+///
+/// The volume of the time window, added for the periods that closed higher than the previous
+/// one and subtracted for the periods that closed lower. Periods that closed unchanged, and the
+/// first period, count as 0.
+///
+/// Updates within the same time bucket replace the volume of the bucket, signed against the
+/// close of the previous bucket, so the volume of a bar in progress is counted once.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::OnBalanceVolume;
+/// use ta::{DataItem, Next};
+///
+/// let mut obv = OnBalanceVolume::new(Duration::from_secs(3 * 86400)).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+/// let bar = |close: f64, volume: f64| {
+///     DataItem::builder()
+///         .open(close)
+///         .high(close)
+///         .low(close)
+///         .close(close)
+///         .volume(volume)
+///         .build()
+///         .unwrap()
+/// };
+///
+/// assert_eq!(obv.next((start, &bar(10.0, 1000.0))), 0.0);
+/// let day = start + ChronoDuration::days(1);
+/// assert_eq!(obv.next((day, &bar(11.0, 400.0))), 400.0);
+/// // A revision of the day replaces its volume
+/// let revision = day + ChronoDuration::hours(1);
+/// assert_eq!(obv.next((revision, &bar(11.5, 600.0))), 600.0);
+/// assert_eq!(obv.next((day + ChronoDuration::days(1), &bar(11.0, 200.0))), 400.0);
+/// ```
+#[doc(alias = "OBV")]
+#[derive(Debug, Clone)]
+pub struct OnBalanceVolume {
+    prev_close: Option<f64>,
+    close: Option<f64>,
+    detector: AdaptiveTimeDetector,
+    volumes: TimeWindow<Sum>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "OnBalanceVolume")]
+struct StateV1 {
+    prev_close: Option<f64>,
+    close: Option<f64>,
+    detector: AdaptiveTimeDetector,
+    volumes: TimeWindow<Sum>,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(OnBalanceVolume, StateV1, 1);
+
+impl OnBalanceVolume {
+    pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            prev_close: None,
+            close: None,
+            volumes: TimeWindow::with_policy(duration, policy.clone())?,
+            detector: AdaptiveTimeDetector::with_policy(duration, policy)?,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.volumes.duration()
+    }
+}
+
+impl<T: Close + Volume> Next<&T> for OnBalanceVolume {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        // When replacing, the previous close stays the close of the previous period
+        if !self.detector.should_replace(timestamp) {
+            self.prev_close = self.close;
+        }
+
+        let close = input.close();
+        let volume = match self.prev_close {
+            Some(prev) if close > prev => input.volume(),
+            Some(prev) if close < prev => -input.volume(),
+            _ => 0.0,
+        };
+
+        self.close = Some(close);
+        self.volumes.push(timestamp, volume);
+        self.volumes.aggregate().value()
+    }
+}
+
+/// Checks the close and the volume of the bars, see [TryNext](../trait.TryNext.html).
+impl<'a, T: Close + Volume> TryNext<&'a T> for OnBalanceVolume {
+    fn try_next(&mut self, (timestamp, input): (DateTime<Utc>, &'a T)) -> Result<Self::Output> {
+        let latest = self.detector.latest();
+        check_with_volume(self, latest, timestamp, &[input.close()], input.volume())?;
+        Ok(self.next((timestamp, input)))
+    }
+}
+
+impl Peek for OnBalanceVolume {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        if self.volumes.is_empty() {
+            None
+        } else {
+            Some(self.volumes.aggregate().value())
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.volumes.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.detector.bucket_count()
+    }
+}
+
+impl Reset for OnBalanceVolume {
+    fn reset(&mut self) {
+        self.prev_close = None;
+        self.close = None;
+        self.detector.reset();
+        self.volumes.reset();
+    }
+}
+
+impl Default for OnBalanceVolume {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(20 * 24 * 60 * 60)).unwrap()
+    }
+}
+
+impl fmt::Display for OnBalanceVolume {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OBV({})", format_duration(self.duration()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::TaError;
+    use crate::test_helper::*;
+
+    fn bar(close: f64, volume: f64) -> Bar {
+        Bar::new().close(close).volume(volume)
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(OnBalanceVolume::new(Duration::from_secs(0)).is_err());
+        assert!(OnBalanceVolume::new(DAY).is_ok());
+    }
+
+    #[test]
+    fn test_next() {
+        let mut obv = OnBalanceVolume::new(DAY * 3).unwrap();
+
+        let bars = [
+            (bar(10.0, 100.0), 0.0),
+            (bar(12.0, 200.0), 200.0),
+            (bar(11.0, 150.0), 50.0),
+            // Unchanged, and day 0 has left the window
+            (bar(11.0, 300.0), 50.0),
+            // Day 1 has left the window
+            (bar(12.0, 100.0), -50.0),
+        ];
+        for (n, (bar, expected)) in bars.iter().enumerate() {
+            assert_eq!(obv.next((day(n as i64), bar)), *expected);
+        }
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut obv = OnBalanceVolume::new(DAY * 3).unwrap();
+
+        obv.next((day(0), &bar(10.0, 100.0)));
+        assert_eq!(obv.next((day(1), &bar(12.0, 200.0))), 200.0);
+        // The revision is signed against day 0 and its volume replaces the day's
+        let revision = day(1) + chrono::Duration::hours(1);
+        assert_eq!(obv.next((revision, &bar(9.0, 250.0))), -250.0);
+        assert_eq!(obv.sample_count(), 2);
+
+        assert_eq!(obv.next((day(2), &bar(9.5, 100.0))), -150.0);
+    }
+
+    #[test]
+    fn test_try_next() {
+        let mut obv = OnBalanceVolume::new(DAY * 3).unwrap();
+
+        obv.try_next((day(0), &bar(10.0, 100.0))).unwrap();
+        assert!(matches!(
+            obv.try_next((day(1), &bar(12.0, f64::NAN))),
+            Err(TaError::NotFinite { .. })
+        ));
+        assert!(matches!(
+            obv.try_next((day(1), &bar(12.0, -200.0))),
+            Err(TaError::NotPositive { value, .. }) if value == -200.0
+        ));
+        assert!(matches!(
+            obv.try_next((day(1), &bar(f64::INFINITY, 200.0))),
+            Err(TaError::NotFinite { .. })
+        ));
+        assert!(matches!(
+            obv.try_next((day(-1), &bar(12.0, 200.0))),
+            Err(TaError::OutOfOrder { .. })
+        ));
+
+        // The rejected bars left the sum alone, and a zero volume is fine
+        assert_eq!(obv.try_next((day(1), &bar(12.0, 200.0))), Ok(200.0));
+        assert_eq!(obv.try_next((day(2), &bar(11.0, 0.0))), Ok(200.0));
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut obv = OnBalanceVolume::new(DAY * 2).unwrap();
+        assert_eq!(obv.current(), None);
+
+        obv.next((day(0), &bar(10.0, 100.0)));
+        obv.next((day(1), &bar(12.0, 200.0)));
+        assert_eq!(obv.current(), Some(200.0));
+        assert_eq!(obv.sample_count(), 2);
+        assert!(!obv.is_ready());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut obv = OnBalanceVolume::new(DAY * 3).unwrap();
+
+        obv.next((day(0), &bar(10.0, 100.0)));
+        obv.next((day(1), &bar(12.0, 200.0)));
+        obv.reset();
+        assert_eq!(obv.current(), None);
+        assert_eq!(obv.next((day(2), &bar(14.0, 300.0))), 0.0);
+    }
+
+    #[test]
+    fn test_default() {
+        OnBalanceVolume::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let obv = OnBalanceVolume::new(DAY * 20).unwrap();
+        assert_eq!(format!("{}", obv), "OBV(20 days)");
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, ExponentialMovingAverage as Ema};
+use crate::{Close, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Percentage price oscillator (PPO).
+///
+/// The [MACD](struct.MovingAverageConvergenceDivergence.html) line in percent of the slow EMA,
+/// which makes it comparable across prices:
+///
+/// * `ppo` - `(fast EMA - slow EMA) / slow EMA * 100`
+/// * `signal` - EMA of the PPO line
+/// * `histogram` - `ppo - signal`
+///
+/// All three averages are duration based [ExponentialMovingAverage](struct.ExponentialMovingAverage.html)s,
+/// so an update that falls into the same time bucket as the previous one revises the current
+/// PPO, signal and histogram instead of appending a new period. The PPO is 0 while the slow EMA
+/// is 0.
+///
+/// # Parameters
+///
+/// * `fast_duration` - window of the fast EMA (e.g. 12 days)
+/// * `slow_duration` - window of the slow EMA (e.g. 26 days)
+/// * `signal_duration` - window of the signal EMA (e.g. 9 days)
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::PercentagePriceOscillator as Ppo;
+/// use ta::Next;
+///
+/// let day = Duration::from_secs(86400);
+/// let mut ppo = Ppo::new(day * 3, day * 6, day * 4).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+///
+/// assert_eq!(ppo.next((start, 2.0)).ppo, 0.0);
+/// let out = ppo.next((start + ChronoDuration::days(1), 3.0));
+/// assert_eq!(out.ppo.round(), 9.0);
+/// assert_eq!(out.histogram, out.ppo - out.signal);
+/// ```
+#[doc(alias = "PPO")]
+#[derive(Debug, Clone)]
+pub struct PercentagePriceOscillator {
+    fast_ema: Ema,
+    slow_ema: Ema,
+    signal_ema: Ema,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "PercentagePriceOscillator")]
+struct StateV1 {
+    fast_ema: Ema,
+    slow_ema: Ema,
+    signal_ema: Ema,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(PercentagePriceOscillator, StateV1, 1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PercentagePriceOscillatorOutput {
+    pub ppo: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+impl From<PercentagePriceOscillatorOutput> for (f64, f64, f64) {
+    fn from(po: PercentagePriceOscillatorOutput) -> Self {
+        (po.ppo, po.signal, po.histogram)
+    }
+}
+
+impl PercentagePriceOscillator {
+    pub fn new(
+        fast_duration: Duration,
+        slow_duration: Duration,
+        signal_duration: Duration,
+    ) -> Result<Self> {
+        Self::with_policy(
+            fast_duration,
+            slow_duration,
+            signal_duration,
+            BucketPolicy::Heuristic,
+        )
+    }
+
+    /// Like [new](#method.new), with the time buckets of all three EMAs set by `policy`.
+    pub fn with_policy(
+        fast_duration: Duration,
+        slow_duration: Duration,
+        signal_duration: Duration,
+        policy: BucketPolicy,
+    ) -> Result<Self> {
+        Ok(Self {
+            fast_ema: Ema::with_policy(fast_duration, policy.clone())?,
+            slow_ema: Ema::with_policy(slow_duration, policy.clone())?,
+            signal_ema: Ema::with_policy(signal_duration, policy)?,
+        })
+    }
+
+    fn ppo(fast: f64, slow: f64) -> f64 {
+        if slow == 0.0 {
+            0.0
+        } else {
+            (fast - slow) / slow * 100.0
+        }
+    }
+}
+
+impl Next<f64> for PercentagePriceOscillator {
+    type Output = PercentagePriceOscillatorOutput;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        // A revision of the bucket revises both EMAs, and the signal gets the revised PPO
+        let fast_val = self.fast_ema.next((timestamp, value));
+        let slow_val = self.slow_ema.next((timestamp, value));
+
+        let ppo = Self::ppo(fast_val, slow_val);
+        let signal = self.signal_ema.next((timestamp, ppo));
+
+        PercentagePriceOscillatorOutput {
+            ppo,
+            signal,
+            histogram: ppo - signal,
+        }
+    }
+}
+
+impl<T: Close> Next<&T> for PercentagePriceOscillator {
+    type Output = PercentagePriceOscillatorOutput;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for PercentagePriceOscillator {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.fast_ema.latest_time()
+    }
+
+    fn requires_positive(&self) -> bool {
+        true
+    }
+}
+
+impl Peek for PercentagePriceOscillator {
+    type Output = PercentagePriceOscillatorOutput;
+
+    fn current(&self) -> Option<Self::Output> {
+        let ppo = Self::ppo(self.fast_ema.current()?, self.slow_ema.current()?);
+        let signal = self.signal_ema.current()?;
+        Some(PercentagePriceOscillatorOutput {
+            ppo,
+            signal,
+            histogram: ppo - signal,
+        })
+    }
+
+    fn is_ready(&self) -> bool {
+        self.fast_ema.is_ready() && self.slow_ema.is_ready() && self.signal_ema.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.fast_ema.sample_count()
+    }
+}
+
+impl Reset for PercentagePriceOscillator {
+    fn reset(&mut self) {
+        self.fast_ema.reset();
+        self.slow_ema.reset();
+        self.signal_ema.reset();
+    }
+}
+
+impl Default for PercentagePriceOscillator {
+    fn default() -> Self {
+        let day = Duration::from_secs(24 * 60 * 60);
+        Self::new(day * 12, day * 26, day * 9).unwrap()
+    }
+}
+
+impl fmt::Display for PercentagePriceOscillator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "PPO({}, {}, {})",
+            format_duration(self.fast_ema.duration()),
+            format_duration(self.slow_ema.duration()),
+            format_duration(self.signal_ema.duration())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+    use chrono::TimeZone;
+
+    type Ppo = PercentagePriceOscillator;
+
+    test_indicator!(Ppo);
+
+    fn round(nums: (f64, f64, f64)) -> (f64, f64, f64) {
+        let n0 = (nums.0 * 100.0).round() / 100.0;
+        let n1 = (nums.1 * 100.0).round() / 100.0;
+        let n2 = (nums.2 * 100.0).round() / 100.0;
+        (n0, n1, n2)
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(Ppo::new(Duration::from_secs(0), DAY, DAY).is_err());
+        assert!(Ppo::new(DAY, Duration::from_secs(0), DAY).is_err());
+        assert!(Ppo::new(DAY, DAY, Duration::from_secs(0)).is_err());
+        assert!(Ppo::new(DAY * 2, DAY, DAY).is_ok());
+    }
+
+    #[test]
+    fn test_ppo() {
+        let mut ppo = Ppo::new(DAY * 3, DAY * 6, DAY * 4).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let day = |n: i64| start + chrono::Duration::days(n);
+
+        assert_eq!(round(ppo.next((day(0), 2.0)).into()), (0.0, 0.0, 0.0));
+        assert_eq!(round(ppo.next((day(1), 3.0)).into()), (9.38, 3.75, 5.63));
+        assert_eq!(round(ppo.next((day(2), 4.2)).into()), (18.26, 9.56, 8.71));
+        assert_eq!(round(ppo.next((day(3), 7.0)).into()), (28.62, 17.18, 11.44));
+        assert_eq!(round(ppo.next((day(4), 6.7)).into()), (24.01, 19.91, 4.09));
+        assert_eq!(round(ppo.next((day(5), 6.5)).into()), (17.84, 19.08, -1.24));
+    }
+
+    #[test]
+    fn test_zero_slow_average() {
+        let mut ppo = Ppo::new(DAY, DAY * 2, DAY).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(round(ppo.next((start, 0.0)).into()), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_replacement_within_bucket() {
+        let mut ppo = Ppo::new(DAY * 3, DAY * 6, DAY * 4).unwrap();
+        let mut expected = ppo.clone();
+        let open = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
+        let next_day = open + chrono::Duration::days(1);
+
+        ppo.next((open, 2.0));
+        expected.next((open, 2.0));
+
+        // Intraday updates on the next day revise the same slot
+        ppo.next((next_day, 3.5));
+        ppo.next((next_day + chrono::Duration::minutes(30), 2.5));
+        let revised = ppo.next((next_day + chrono::Duration::hours(1), 3.2));
+
+        assert_eq!(
+            round(revised.into()),
+            round(expected.next((next_day, 3.2)).into())
+        );
+        assert_eq!(ppo.sample_count(), 2);
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut ppo = Ppo::new(DAY * 3, DAY * 6, DAY * 4).unwrap();
+        assert_eq!(ppo.current(), None);
+
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        ppo.next((start, 2.0));
+        let output = ppo.next((start + chrono::Duration::days(1), 3.0));
+        assert_eq!(ppo.current(), Some(output));
+        assert!(!ppo.is_ready());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut ppo = Ppo::new(DAY * 3, DAY * 6, DAY * 4).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        ppo.next((start, 2.0));
+        ppo.next((start + chrono::Duration::days(1), 3.0));
+        ppo.reset();
+
+        assert_eq!(round(ppo.next((start, 2.0)).into()), (0.0, 0.0, 0.0));
+        assert_eq!(
+            round(ppo.next((start + chrono::Duration::days(1), 3.0)).into()),
+            (9.38, 3.75, 5.63)
+        );
+    }
+
+    #[test]
+    fn test_default() {
+        Ppo::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let indicator = Ppo::new(DAY * 12, DAY * 26, DAY * 9).unwrap();
+        assert_eq!(format!("{}", indicator), "PPO(12 days, 26 days, 9 days)");
+    }
+}
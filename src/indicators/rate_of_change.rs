@@ -2,49 +2,102 @@ use std::collections::VecDeque;
 use std::fmt;
 use std::time::Duration;
 
-use crate::errors::{Result, TaError};
-use crate::indicators::AdaptiveTimeDetector;
-use crate::traits::{Next, Reset};
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{Aggregate, BucketPolicy, TimeWindow};
+use crate::traits::{Close, Next, Peek, Reset, Validate};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// Rate of change (ROC) of the latest value against the value `duration` ago, in percent.
+///
+/// The value `duration` ago is the latest one that has left the window. Until a value has, the
+/// oldest value of the window is used.
 #[doc(alias = "ROC")]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct RateOfChange {
-    duration: Duration,  // Now std::time::Duration
+    window: TimeWindow<LastEvicted>,
+}
+
+// Keeps the latest value evicted from the window
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+struct LastEvicted {
+    value: Option<f64>,
+}
+
+impl Aggregate for LastEvicted {
+    fn push(&mut self, _: (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {}
+
+    fn evict(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.value = Some(value);
+    }
+
+    fn pop_back(&mut self, _: (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {}
+
+    fn clear(&mut self) {
+        self.value = None;
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "RateOfChange")]
+struct StateV1 {
+    window: TimeWindow<LastEvicted>,
+}
+
+// The unversioned layout of ta 0.5
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LegacyState {
+    duration: Duration,
     window: VecDeque<(DateTime<Utc>, f64)>,
-    detector: AdaptiveTimeDetector,
+    detector: super::adaptive::LegacyDetector,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<RateOfChange> for LegacyState {
+    fn migrate(self) -> Result<RateOfChange> {
+        Ok(RateOfChange {
+            window: TimeWindow::from_legacy(self.duration, self.window, self.detector)?,
+        })
+    }
 }
 
+#[cfg(feature = "serde")]
+versioned_serde!(RateOfChange, StateV1, 1, 0 => LegacyState);
+
 impl RateOfChange {
     pub fn get_window(&self) -> VecDeque<(DateTime<Utc>, f64)> {
-        self.window.clone()
+        self.window.values().clone()
     }
     pub fn new(duration: Duration) -> Result<Self> {
-        // std::time::Duration can't be negative, so just check if it's zero
-        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-            Err(TaError::InvalidParameter)
-        } else {
-            Ok(Self {
-                duration,
-                window: VecDeque::new(),
-                detector: AdaptiveTimeDetector::new(duration),
-            })
-        }
+        Self::with_policy(duration, BucketPolicy::Heuristic)
     }
 
-    // Add a method to remove old data points outside the duration
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // Convert std::time::Duration to chrono::Duration for the subtraction
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time < current_time - chrono_duration)
-        {
-            self.window.pop_front();
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Ok(Self {
+            window: TimeWindow::with_policy(duration, policy)?,
+        })
+    }
+
+    // Change from the value `duration` ago to the newest value, in percent
+    fn rate(&self) -> f64 {
+        let reference = match self.window.aggregate().value {
+            Some(evicted) => evicted,
+            None if self.window.len() > 1 => self.window.values()[0].1,
+            None => return 0.0,
+        };
+        let newest_value = self.window.latest().expect("Window has at least one item");
+
+        // Ensure we do not divide by zero
+        if reference != 0.0 {
+            (newest_value - reference) / reference * 100.0
+        } else {
+            0.0
         }
     }
 }
@@ -53,57 +106,66 @@ impl Next<f64> for RateOfChange {
     type Output = f64;
 
     fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
-        // Check if we should replace the last value (same time bucket)
-        let should_replace = self.detector.should_replace(timestamp);
-
-        // ALWAYS remove old data first, regardless of replace/add
-        self.remove_old_data(timestamp);
+        // The window takes care of bucket replacement and old data removal
+        self.window.push(timestamp, value);
+        self.rate()
+    }
+}
 
-        if should_replace && !self.window.is_empty() {
-            // Replace the last value in the same time bucket
-            self.window.pop_back();
-        }
+impl<T: Close> Next<&T> for RateOfChange {
+    type Output = f64;
 
-        // Add the new data point
-        self.window.push_back((timestamp, value));
-
-        // Calculate the rate of change if we have at least two data points
-        if self.window.len() > 1 {
-            let (oldest_time, oldest_value) =
-                self.window.front().expect("Window has at least one item");
-            let (newest_time, newest_value) =
-                self.window.back().expect("Window has at least one item");
-
-            // Ensure we do not divide by zero
-            if oldest_value.clone() != 0.0 {
-                (newest_value - oldest_value) / oldest_value * 100.0
-            } else {
-                0.0
-            }
-        } else {
-            0.0
-        }
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
     }
 }
 
 impl Default for RateOfChange {
     fn default() -> Self {
         // Use std::time::Duration constructor
-        Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap()  // 14 days in seconds
+        Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap() // 14 days in seconds
     }
 }
 
 impl fmt::Display for RateOfChange {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Use as_secs() instead of Debug format
-        write!(f, "ROC({}s)", self.duration.as_secs())
+        write!(f, "ROC({})", format_duration(self.window.duration()))
+    }
+}
+
+impl Validate for RateOfChange {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.window.latest_time()
+    }
+
+    fn requires_positive(&self) -> bool {
+        true
+    }
+}
+
+impl Peek for RateOfChange {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        if self.window.is_empty() {
+            None
+        } else {
+            Some(self.rate())
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.window.bucket_count()
     }
 }
 
 impl Reset for RateOfChange {
     fn reset(&mut self) {
-        self.window.clear();
-        self.detector.reset();
+        self.window.reset();
     }
 }
 #[cfg(test)]
@@ -113,7 +175,6 @@ mod tests {
     use chrono::{TimeZone, Utc};
 
     test_indicator!(RateOfChange);
-    const EPSILON: f64 = 1e-10;
 
     #[test]
     fn test_new() {
@@ -125,7 +186,7 @@ mod tests {
     #[test]
     fn test_next_f64() {
         let mut roc = RateOfChange::new(Duration::from_secs(3)).unwrap();
-        let start_time = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
 
         assert_eq!(round(roc.next((start_time, 10.0))), 0.0);
         assert_eq!(
@@ -150,10 +211,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_peek() {
+        let mut roc = RateOfChange::new(Duration::from_secs(2 * 86400)).unwrap();
+        let timestamp = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(roc.next((timestamp, 10.0)), 0.0);
+        assert_eq!(roc.current(), Some(0.0));
+        assert!(!roc.is_ready());
+
+        roc.next((timestamp + chrono::Duration::days(1), 11.0));
+        roc.next((timestamp + chrono::Duration::days(2), 12.0));
+        assert_eq!(roc.current(), Some(20.0));
+        assert!(roc.is_ready());
+    }
+
     #[test]
     fn test_reset() {
         let mut roc = RateOfChange::new(Duration::from_secs(3)).unwrap();
-        let start_time = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let start_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
 
         roc.next((start_time, 12.3));
         roc.next((start_time + chrono::Duration::seconds(1), 15.0));
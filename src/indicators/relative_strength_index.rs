@@ -1,48 +1,200 @@
-use std::collections::VecDeque;
 use std::fmt;
 use std::time::Duration; // Change: Use std::time::Duration
 
 use crate::errors::Result;
-use crate::indicators::{AdaptiveTimeDetector, ExponentialMovingAverage as Ema};
-use crate::{Next, Reset};
+use crate::helpers::format_duration;
+use crate::indicators::{
+    AdaptiveTimeDetector, BucketPolicy, ExponentialMovingAverage as Ema, SimpleMovingAverage as Sma,
+};
+use crate::{Close, Next, Peek, Reset, Validate};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::collections::VecDeque;
 
+/// Relative strength index (RSI).
+///
+/// The gains and losses against the prior period's close are averaged with one of:
+///
+/// * [new](#method.new) - EMA with the factor `2 / (days + 1)`
+/// * [wilder](#method.wilder) - Wilder's RMA, seeded with the simple mean of the first changes,
+///   one per time bucket of the duration, as used by most charting platforms
+/// * [cutler](#method.cutler) - simple moving average over the duration (Cutler's RSI)
+///
+/// Updates within the same time bucket revise the current value against the same prior close.
+/// The EMA smoothing counts the first bucket as a change of zero, the other two start averaging
+/// from the first actual change.
 #[doc(alias = "RSI")]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct RelativeStrengthIndex {
     duration: Duration, // Now std::time::Duration
-    up_ema_indicator: Ema,
-    down_ema_indicator: Ema,
-    window: VecDeque<(DateTime<Utc>, f64)>,
+    up_average: Average,
+    down_average: Average,
     prev_val: Option<f64>,
+    close: Option<f64>,
+    current: Option<f64>,
     detector: AdaptiveTimeDetector,
 }
 
+// How the gains and losses are averaged
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+enum Average {
+    Ema(Ema),
+    Wilder(Ema),
+    Cutler(Sma),
+}
+
+impl Average {
+    fn next(&mut self, input: (DateTime<Utc>, f64)) -> f64 {
+        match self {
+            Average::Ema(ema) | Average::Wilder(ema) => ema.next(input),
+            Average::Cutler(sma) => sma.next(input),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Average::Ema(ema) | Average::Wilder(ema) => ema.reset(),
+            Average::Cutler(sma) => sma.reset(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "RelativeStrengthIndex")]
+struct StateV1 {
+    duration: Duration,
+    up_average: Average,
+    down_average: Average,
+    prev_val: Option<f64>,
+    close: Option<f64>,
+    current: Option<f64>,
+    detector: AdaptiveTimeDetector,
+}
+
+// The unversioned layout of ta 0.5
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LegacyState {
+    duration: Duration,
+    up_ema_indicator: super::exponential_moving_average::LegacyState,
+    down_ema_indicator: super::exponential_moving_average::LegacyState,
+    window: VecDeque<(DateTime<Utc>, f64)>,
+    #[allow(dead_code)] // the first value of the bucket, not the previous close
+    prev_val: Option<f64>,
+    detector: super::adaptive::LegacyDetector,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<RelativeStrengthIndex> for LegacyState {
+    fn migrate(self) -> Result<RelativeStrengthIndex> {
+        let up: Ema = self.up_ema_indicator.migrate()?;
+        let down: Ema = self.down_ema_indicator.migrate()?;
+        let current = up
+            .current()
+            .zip(down.current())
+            .map(|(avg_up, avg_down)| RelativeStrengthIndex::rsi(avg_up, avg_down));
+
+        let mut rsi = RelativeStrengthIndex::with_averages(
+            self.duration,
+            Average::Ema(up),
+            Average::Ema(down),
+            BucketPolicy::Heuristic,
+        )?;
+        // The window holds the close of each bucket, latest last
+        let mut closes = self.window.iter().rev().map(|&(_, value)| value);
+        rsi.close = closes.next();
+        rsi.prev_val = closes.next();
+        rsi.current = current;
+        rsi.detector = self
+            .detector
+            .restore(self.window.iter().map(|&(timestamp, _)| timestamp));
+        Ok(rsi)
+    }
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(RelativeStrengthIndex, StateV1, 1, 0 => LegacyState);
+
 impl RelativeStrengthIndex {
     pub fn new(duration: Duration) -> Result<Self> {
-        // Note: Ema::new() now also expects std::time::Duration
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Self::with_averages(
+            duration,
+            Average::Ema(Ema::with_policy(duration, policy.clone())?),
+            Average::Ema(Ema::with_policy(duration, policy.clone())?),
+            policy,
+        )
+    }
+
+    /// Creates an RSI with Wilder's smoothing, which matches the RSI of charting platforms.
+    ///
+    /// The number of periods is the duration over the width of the time buckets, e.g. 14 for
+    /// 14 minutes of 1 minute bars. Fails if the duration is shorter than one time bucket.
+    pub fn wilder(duration: Duration) -> Result<Self> {
+        Self::wilder_with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [wilder](#method.wilder), with the time buckets set by `policy` instead of `duration`.
+    pub fn wilder_with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Self::with_averages(
+            duration,
+            Average::Wilder(Ema::wilder_with_policy(duration, policy.clone())?),
+            Average::Wilder(Ema::wilder_with_policy(duration, policy.clone())?),
+            policy,
+        )
+    }
+
+    /// Creates Cutler's RSI, which averages the gains and losses over the duration with a
+    /// simple moving average.
+    pub fn cutler(duration: Duration) -> Result<Self> {
+        Self::cutler_with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [cutler](#method.cutler), with the time buckets set by `policy` instead of `duration`.
+    pub fn cutler_with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Self::with_averages(
+            duration,
+            Average::Cutler(Sma::with_policy(duration, policy.clone())?),
+            Average::Cutler(Sma::with_policy(duration, policy.clone())?),
+            policy,
+        )
+    }
+
+    fn with_averages(
+        duration: Duration,
+        up_average: Average,
+        down_average: Average,
+        policy: BucketPolicy,
+    ) -> Result<Self> {
         Ok(Self {
             duration,
-            up_ema_indicator: Ema::new(duration)?, // Assuming 14-period EMA
-            down_ema_indicator: Ema::new(duration)?,
-            window: VecDeque::new(),
+            up_average,
+            down_average,
             prev_val: None,
-            detector: AdaptiveTimeDetector::new(duration),
+            close: None,
+            current: None,
+            detector: AdaptiveTimeDetector::with_policy(duration, policy)?,
         })
     }
 
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // Change: Convert std::time::Duration to chrono::Duration for date arithmetic
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time < current_time - chrono_duration)
-        {
-            self.window.pop_front();
+    fn rsi(avg_up: f64, avg_down: f64) -> f64 {
+        if avg_down == 0.0 {
+            if avg_up == 0.0 {
+                50.0 // Neutral value when no movement
+            } else {
+                100.0 // Max value when only gains
+            }
+        } else {
+            let rs = avg_up / avg_down;
+            100.0 - (100.0 / (1.0 + rs))
         }
     }
 }
@@ -54,24 +206,15 @@ impl Next<f64> for RelativeStrengthIndex {
         // Check if we should replace the last value (same time bucket)
         let should_replace = self.detector.should_replace(timestamp);
 
-        // ALWAYS remove old data first, regardless of replace/add
-        self.remove_old_data(timestamp);
-
-        if should_replace && !self.window.is_empty() {
-            // For RSI, when replacing a value in the same time bucket,
-            // we don't change prev_val since it represents the previous period's close
-            // Just remove the last window entry to be replaced
-            self.window.pop_back();
-        } else {
-            // Update prev_val to the last complete period's value
-            // This is crucial: prev_val should be the closing value of the previous period
-            if !self.window.is_empty() {
-                self.prev_val = Some(self.window.back().unwrap().1);
-            }
+        // On a new period, prev_val becomes the closing value of the previous period. When
+        // replacing a value in the same time bucket, prev_val stays the previous period's close.
+        if !should_replace {
+            self.prev_val = self.close;
         }
 
         // Calculate gain and loss using the stable prev_val
-        let (gain, loss) = if let Some(prev_val) = self.prev_val {
+        let prev_close = self.prev_val;
+        let (gain, loss) = if let Some(prev_val) = prev_close {
             if value > prev_val {
                 (value - prev_val, 0.0)
             } else {
@@ -81,39 +224,61 @@ impl Next<f64> for RelativeStrengthIndex {
             (0.0, 0.0)
         };
 
-        // Add to window AFTER calculating gain/loss
-        self.window.push_back((timestamp, value));
+        // The latest value becomes the close of this period
+        self.close = Some(value);
 
-        // Only update prev_val for the NEXT period if this is not a replacement
-        // When replacing, prev_val stays as the previous period's close
-        if !should_replace {
-            self.prev_val = Some(value);
-        }
+        // Only the EMA counts the first bucket, which has no change, as a zero gain and loss
+        let rsi = if prev_close.is_none() && !matches!(self.up_average, Average::Ema(_)) {
+            50.0
+        } else {
+            // Update the averages
+            let avg_up = self.up_average.next((timestamp, gain));
+            let avg_down = self.down_average.next((timestamp, loss));
+            Self::rsi(avg_up, avg_down)
+        };
 
-        // Update EMAs
-        let avg_up = self.up_ema_indicator.next((timestamp, gain));
-        let avg_down = self.down_ema_indicator.next((timestamp, loss));
+        self.current = Some(rsi);
+        rsi
+    }
+}
 
-        // Calculate and return RSI
-        if avg_down == 0.0 {
-            if avg_up == 0.0 {
-                50.0 // Neutral value when no movement
-            } else {
-                100.0 // Max value when only gains
-            }
-        } else {
-            let rs = avg_up / avg_down;
-            100.0 - (100.0 / (1.0 + rs))
-        }
+impl<T: Close> Next<&T> for RelativeStrengthIndex {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for RelativeStrengthIndex {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.detector.latest()
+    }
+}
+
+impl Peek for RelativeStrengthIndex {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.current
+    }
+
+    fn is_ready(&self) -> bool {
+        self.detector.spans(self.duration)
+    }
+
+    fn sample_count(&self) -> usize {
+        self.detector.bucket_count()
     }
 }
 
 impl Reset for RelativeStrengthIndex {
     fn reset(&mut self) {
-        self.window.clear();
         self.prev_val = None;
-        self.up_ema_indicator.reset();
-        self.down_ema_indicator.reset();
+        self.close = None;
+        self.current = None;
+        self.up_average.reset();
+        self.down_average.reset();
         self.detector.reset();
     }
 }
@@ -127,9 +292,12 @@ impl Default for RelativeStrengthIndex {
 
 impl fmt::Display for RelativeStrengthIndex {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Change: Calculate days from seconds
-        let days = self.duration.as_secs() / 86400;
-        write!(f, "RSI({} days)", days)
+        let duration = format_duration(self.duration);
+        match self.up_average {
+            Average::Ema(_) => write!(f, "RSI({})", duration),
+            Average::Wilder(_) => write!(f, "RSI({}, wilder)", duration),
+            Average::Cutler(_) => write!(f, "RSI({}, cutler)", duration),
+        }
     }
 }
 
@@ -147,12 +315,14 @@ mod tests {
         assert!(RelativeStrengthIndex::new(Duration::from_secs(0)).is_err());
         assert!(RelativeStrengthIndex::new(Duration::from_secs(86400)).is_ok());
         // 1 day
+        assert!(RelativeStrengthIndex::wilder(Duration::from_secs(0)).is_err());
+        assert!(RelativeStrengthIndex::cutler(Duration::from_secs(0)).is_err());
     }
 
     #[test]
     fn test_next() {
         let mut rsi = RelativeStrengthIndex::new(Duration::from_secs(3 * 86400)).unwrap(); // 3 days
-        let timestamp = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let timestamp = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
 
         // First value: 10.0 (no previous value, so RSI = 50)
         assert_eq!(rsi.next((timestamp, 10.0)), 50.0);
@@ -182,10 +352,119 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_next_wilder() {
+        let mut rsi = RelativeStrengthIndex::wilder(Duration::from_secs(3 * 86400)).unwrap();
+        let timestamp = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let closes = [10.0, 11.0, 10.5, 11.5, 11.0, 12.0];
+
+        // The first 3 changes are averaged, then smoothed with 1/3
+        let expected = [50.0, 100.0, 66.667, 80.0, 61.538, 77.273];
+        for (day, (&close, &rsi_value)) in closes.iter().zip(expected.iter()).enumerate() {
+            let time = timestamp + chrono::Duration::days(day as i64);
+            assert_eq!(round(rsi.next((time, close))), rsi_value);
+        }
+    }
+
+    #[test]
+    fn test_next_wilder_intraday() {
+        let mut rsi = RelativeStrengthIndex::wilder(Duration::from_secs(14 * 60)).unwrap();
+        let timestamp = Utc.with_ymd_and_hms(2020, 1, 1, 14, 30, 0).unwrap();
+        let closes = [
+            44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03,
+            45.61, 46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64, 46.21, 46.25, 45.71, 46.45,
+            45.78, 45.35, 44.03, 44.18, 44.22, 44.57, 43.42, 42.66, 43.13,
+        ];
+
+        // Wilder's 14 period RSI of the closes, from the 14th change on
+        let expected = [
+            70.464, 66.25, 66.481, 69.347, 66.295, 57.915, 62.881, 63.209, 56.012, 62.34, 54.671,
+            50.387, 40.019, 41.493, 41.902, 45.499, 37.323, 33.09, 37.789,
+        ];
+        let values: Vec<f64> = closes
+            .iter()
+            .enumerate()
+            .map(|(minute, &close)| {
+                rsi.next((timestamp + chrono::Duration::minutes(minute as i64), close))
+            })
+            .collect();
+        for (&value, &rsi_value) in values[14..].iter().zip(expected.iter()) {
+            assert_eq!(round(value), rsi_value);
+        }
+
+        // Fewer than one bucket in the duration
+        assert!(RelativeStrengthIndex::wilder_with_policy(
+            Duration::from_secs(30 * 60),
+            BucketPolicy::Fixed(Duration::from_secs(3600))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_next_cutler() {
+        let mut rsi = RelativeStrengthIndex::cutler(Duration::from_secs(3 * 86400)).unwrap();
+        let timestamp = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let closes = [10.0, 11.0, 10.5, 11.5, 11.0, 12.0];
+
+        // Plain averages of the last 3 changes
+        let expected = [50.0, 100.0, 66.667, 80.0, 50.0, 80.0];
+        for (day, (&close, &rsi_value)) in closes.iter().zip(expected.iter()).enumerate() {
+            let time = timestamp + chrono::Duration::days(day as i64);
+            assert_eq!(round(rsi.next((time, close))), rsi_value);
+        }
+    }
+
+    #[test]
+    fn test_replacement_within_bucket() {
+        let duration = Duration::from_secs(3 * 86400);
+        let timestamp = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let day = chrono::Duration::days(1);
+
+        for rsi in [
+            RelativeStrengthIndex::new(duration).unwrap(),
+            RelativeStrengthIndex::wilder(duration).unwrap(),
+            RelativeStrengthIndex::cutler(duration).unwrap(),
+        ] {
+            let mut revised = rsi.clone();
+            let mut single = rsi;
+            for rsi in [&mut revised, &mut single] {
+                rsi.next((timestamp, 10.0));
+                rsi.next((timestamp + day, 11.0));
+            }
+
+            // Revisions within the day measure against the previous day's close
+            revised.next((timestamp + day * 2, 12.0));
+            revised.next((timestamp + day * 2 + chrono::Duration::hours(1), 10.5));
+            assert_eq!(
+                revised.next((timestamp + day * 2 + chrono::Duration::hours(2), 10.0)),
+                single.next((timestamp + day * 2, 10.0))
+            );
+        }
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut rsi = RelativeStrengthIndex::wilder(Duration::from_secs(3 * 86400)).unwrap();
+        let timestamp = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(rsi.current(), None);
+
+        // The neutral 50 on cold start is flagged as not ready
+        assert_eq!(rsi.next((timestamp, 10.0)), 50.0);
+        assert_eq!(rsi.current(), Some(50.0));
+        assert!(!rsi.is_ready());
+
+        for (day, close) in [11.0, 10.5, 11.5].into_iter().enumerate() {
+            rsi.next((timestamp + chrono::Duration::days(day as i64 + 1), close));
+        }
+        assert_eq!(round(rsi.current().unwrap()), 80.0);
+        assert_eq!(rsi.sample_count(), 4);
+        assert!(rsi.is_ready());
+    }
+
     #[test]
     fn test_reset() {
         let mut rsi = RelativeStrengthIndex::new(Duration::from_secs(3 * 86400)).unwrap(); // 3 days
-        let timestamp = Utc.ymd(2020, 1, 1).and_hms(0, 0, 0);
+        let timestamp = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
         assert_eq!(rsi.next((timestamp, 10.0)), 50.0);
         assert_eq!(
             rsi.next((timestamp + chrono::Duration::days(1), 10.5))
@@ -211,5 +490,11 @@ mod tests {
     fn test_display() {
         let rsi = RelativeStrengthIndex::new(Duration::from_secs(16 * 86400)).unwrap(); // 16 days
         assert_eq!(format!("{}", rsi), "RSI(16 days)");
+        let rsi = RelativeStrengthIndex::wilder(Duration::from_secs(14 * 86400)).unwrap();
+        assert_eq!(format!("{}", rsi), "RSI(14 days, wilder)");
+        let rsi = RelativeStrengthIndex::wilder(Duration::from_secs(14 * 60)).unwrap();
+        assert_eq!(format!("{}", rsi), "RSI(840s, wilder)");
+        let rsi = RelativeStrengthIndex::cutler(Duration::from_secs(14 * 86400)).unwrap();
+        assert_eq!(format!("{}", rsi), "RSI(14 days, cutler)");
     }
 }
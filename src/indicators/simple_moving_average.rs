@@ -2,56 +2,79 @@ use std::collections::VecDeque;
 use std::fmt;
 use std::time::Duration;
 
-use crate::indicators::AdaptiveTimeDetector;
-use crate::Next;
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, Sum, TimeWindow};
 use crate::{errors::Result, Reset};
+use crate::{Close, Next, Peek, Validate};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 #[doc(alias = "SMA")]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct SimpleMovingAverage {
-    duration: Duration, // Now std::time::Duration
+    window: TimeWindow<Sum>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "SimpleMovingAverage")]
+struct StateV1 {
+    window: TimeWindow<Sum>,
+}
+
+// The unversioned layout of ta 0.5
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LegacyState {
+    duration: Duration,
     window: VecDeque<(DateTime<Utc>, f64)>,
+    #[allow(dead_code)] // recomputed from the window
     sum: f64,
-    detector: AdaptiveTimeDetector,
+    detector: super::adaptive::LegacyDetector,
 }
 
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<SimpleMovingAverage> for LegacyState {
+    fn migrate(self) -> Result<SimpleMovingAverage> {
+        Ok(SimpleMovingAverage {
+            window: TimeWindow::from_legacy(self.duration, self.window, self.detector)?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(SimpleMovingAverage, StateV1, 1, 0 => LegacyState);
+
 impl SimpleMovingAverage {
     pub fn get_window(&self) -> VecDeque<(DateTime<Utc>, f64)> {
-        self.window.clone()
+        self.window.values().clone()
     }
     pub fn new(duration: Duration) -> Result<Self> {
-        // std::time::Duration can't be negative, so just check if it's zero
-        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-            return Err(crate::errors::TaError::InvalidParameter);
-        }
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
         Ok(Self {
-            duration,
-            window: VecDeque::new(),
-            sum: 0.0,
-            detector: AdaptiveTimeDetector::new(duration),
+            window: TimeWindow::with_policy(duration, policy)?,
         })
     }
 
+    pub fn duration(&self) -> Duration {
+        self.window.duration()
+    }
+
     pub fn get_internal_state(&self) -> (Duration, VecDeque<(DateTime<Utc>, f64)>, f64) {
-        (self.duration, self.window.clone(), self.sum)
-    }
-
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // Convert std::time::Duration to chrono::Duration for the subtraction
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time <= current_time - chrono_duration)
-        {
-            if let Some((_, value)) = self.window.pop_front() {
-                self.sum -= value;
-            }
-        }
+        (
+            self.window.duration(),
+            self.window.values().clone(),
+            self.window.aggregate().value(),
+        )
+    }
+
+    fn mean(&self) -> f64 {
+        self.window.aggregate().value() / self.window.len() as f64
     }
 }
 
@@ -59,37 +82,49 @@ impl Next<f64> for SimpleMovingAverage {
     type Output = f64;
 
     fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
-        // Check if we should replace the last value (same time bucket)
-        let should_replace = self.detector.should_replace(timestamp);
+        // The window takes care of bucket replacement and old data removal
+        self.window.push(timestamp, value);
+        self.mean()
+    }
+}
 
-        // ALWAYS remove old data first, regardless of replace/add
-        self.remove_old_data(timestamp);
+impl<T: Close> Next<&T> for SimpleMovingAverage {
+    type Output = f64;
 
-        if should_replace && !self.window.is_empty() {
-            // Replace the last value in the same time bucket
-            if let Some((_, old_value)) = self.window.pop_back() {
-                self.sum -= old_value;
-            }
-        }
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for SimpleMovingAverage {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.window.latest_time()
+    }
+}
 
-        // Add new data point
-        self.window.push_back((timestamp, value));
-        self.sum += value;
+impl Peek for SimpleMovingAverage {
+    type Output = f64;
 
-        // Calculate moving average
+    fn current(&self) -> Option<Self::Output> {
         if self.window.is_empty() {
-            0.0
+            None
         } else {
-            self.sum / self.window.len() as f64
+            Some(self.mean())
         }
     }
+
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.window.bucket_count()
+    }
 }
 
 impl Reset for SimpleMovingAverage {
     fn reset(&mut self) {
-        self.window.clear();
-        self.sum = 0.0;
-        self.detector.reset();
+        self.window.reset();
     }
 }
 
@@ -102,8 +137,7 @@ impl Default for SimpleMovingAverage {
 
 impl fmt::Display for SimpleMovingAverage {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Use as_secs() instead of Debug format
-        write!(f, "SMA({}s)", self.duration.as_secs())
+        write!(f, "SMA({})", format_duration(self.window.duration()))
     }
 }
 
@@ -139,6 +173,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_with_policy() {
+        let day = Duration::from_secs(86400);
+        assert!(
+            SimpleMovingAverage::with_policy(day, BucketPolicy::Fixed(Duration::ZERO)).is_err()
+        );
+
+        let mut heuristic = SimpleMovingAverage::new(day * 2).unwrap();
+        let mut calendar_day =
+            SimpleMovingAverage::with_policy(day * 2, BucketPolicy::CalendarDay).unwrap();
+        let midnight = chrono::DateTime::from_timestamp(1_704_067_200, 0).unwrap();
+        let noon = midnight + chrono::Duration::hours(12);
+
+        heuristic.next((midnight, 10.0));
+        calendar_day.next((midnight, 10.0));
+
+        // 12 hours apart is a new slot for the heuristic but the same UTC day
+        assert_eq!(heuristic.next((noon, 20.0)), 15.0);
+        assert_eq!(calendar_day.next((noon, 20.0)), 20.0);
+        assert_eq!(
+            calendar_day.next((midnight + chrono::Duration::days(1), 30.0)),
+            25.0
+        );
+    }
+
     #[test]
     fn test_reset() {
         let duration = Duration::from_secs(4);
@@ -153,6 +212,31 @@ mod tests {
         assert_eq!(sma.next((start_time + elapsed_time * 3, 99.0)), 99.0);
     }
 
+    #[test]
+    fn test_peek() {
+        let mut sma = SimpleMovingAverage::new(Duration::from_secs(2 * 86400)).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 16, 0, 0).unwrap();
+        let day = chrono::Duration::days(1);
+        assert_eq!(sma.current(), None);
+        assert_eq!(sma.sample_count(), 0);
+
+        sma.next((start, 4.0));
+        assert_eq!(sma.next((start + day, 6.0)), 5.0);
+        assert_eq!(sma.current(), Some(5.0));
+        assert!(!sma.is_ready());
+
+        // A revision within the day replaces the bucket instead of adding one
+        sma.next((start + day + chrono::Duration::hours(1), 8.0));
+        assert_eq!(sma.current(), Some(6.0));
+        assert_eq!(sma.sample_count(), 2);
+
+        // The first day has now left the window
+        assert_eq!(sma.next((start + day * 2, 2.0)), 5.0);
+        assert_eq!(sma.current(), Some(5.0));
+        assert_eq!(sma.sample_count(), 3);
+        assert!(sma.is_ready());
+    }
+
     #[test]
     fn test_default() {
         let _sma = SimpleMovingAverage::default();
@@ -0,0 +1,197 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{BucketPolicy, FastStochastic, SimpleMovingAverage as Sma};
+use crate::{Close, High, Low, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Slow stochastic oscillator (%D).
+///
+/// A [FastStochastic](struct.FastStochastic.html) (%K) smoothed by a
+/// [SimpleMovingAverage](struct.SimpleMovingAverage.html) over a time window.
+///
+/// Both the %K highs and lows and the %D average replace the value of the current time bucket
+/// when an update arrives within it, the same way the underlying indicators do.
+///
+/// # Parameters
+///
+/// * `stochastic_duration` - time window of the highs and lows of %K (e.g. 14 days)
+/// * `sma_duration` - time window of the %D smoothing (e.g. 3 days)
+#[derive(Debug, Clone)]
+pub struct SlowStochastic {
+    fast_stochastic: FastStochastic,
+    sma: Sma,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "SlowStochastic")]
+struct StateV1 {
+    fast_stochastic: FastStochastic,
+    sma: Sma,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(SlowStochastic, StateV1, 1);
+
+impl SlowStochastic {
+    pub fn new(stochastic_duration: Duration, sma_duration: Duration) -> Result<Self> {
+        Self::with_policy(stochastic_duration, sma_duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets of both %K and %D set by `policy`.
+    pub fn with_policy(
+        stochastic_duration: Duration,
+        sma_duration: Duration,
+        policy: BucketPolicy,
+    ) -> Result<Self> {
+        Ok(Self {
+            fast_stochastic: FastStochastic::with_policy(stochastic_duration, policy.clone())?,
+            sma: Sma::with_policy(sma_duration, policy)?,
+        })
+    }
+}
+
+impl Next<f64> for SlowStochastic {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        let k = self.fast_stochastic.next((timestamp, value));
+        self.sma.next((timestamp, k))
+    }
+}
+
+impl<T: High + Low + Close> Next<&T> for SlowStochastic {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        let k = self.fast_stochastic.next((timestamp, input));
+        self.sma.next((timestamp, k))
+    }
+}
+
+impl Validate for SlowStochastic {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.fast_stochastic.latest_time()
+    }
+}
+
+impl Peek for SlowStochastic {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.sma.current()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.fast_stochastic.is_ready() && self.sma.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.sma.sample_count()
+    }
+}
+
+impl Reset for SlowStochastic {
+    fn reset(&mut self) {
+        self.fast_stochastic.reset();
+        self.sma.reset();
+    }
+}
+
+impl Default for SlowStochastic {
+    fn default() -> Self {
+        let day = Duration::from_secs(24 * 60 * 60);
+        Self::new(day * 14, day * 3).unwrap()
+    }
+}
+
+impl fmt::Display for SlowStochastic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "SLOW_STOCH({}, {})",
+            format_duration(self.fast_stochastic.duration()),
+            format_duration(self.sma.duration())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+    use chrono::TimeZone;
+
+    test_indicator!(SlowStochastic);
+
+    #[test]
+    fn test_new() {
+        assert!(SlowStochastic::new(Duration::from_secs(0), DAY).is_err());
+        assert!(SlowStochastic::new(DAY, Duration::from_secs(0)).is_err());
+        assert!(SlowStochastic::new(DAY, DAY).is_ok());
+    }
+
+    #[test]
+    fn test_next_with_f64() {
+        let mut stoch = SlowStochastic::new(DAY * 3, DAY * 2).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let day = |n: i64| start + chrono::Duration::days(n);
+
+        // %K: 50, 100, 50, 20, 75
+        assert_eq!(stoch.next((day(0), 0.0)), 50.0);
+        assert_eq!(stoch.next((day(1), 200.0)), 75.0);
+        assert_eq!(stoch.next((day(2), 100.0)), 75.0);
+        assert_eq!(stoch.next((day(3), 120.0)), 35.0);
+        assert_eq!(stoch.next((day(4), 115.0)), 47.5);
+    }
+
+    #[test]
+    fn test_next_with_bars() {
+        let mut stoch = SlowStochastic::new(DAY * 3, DAY * 2).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let day = |n: i64| start + chrono::Duration::days(n);
+
+        // (high, low, close), %K: 50, 75, 20, 30
+        let bars = vec![
+            ((20.0, 20.0, 20.0), 50.0),
+            ((30.0, 10.0, 25.0), 62.5),
+            ((40.0, 20.0, 16.0), 47.5),
+            ((35.0, 15.0, 19.0), 25.0),
+        ];
+
+        for (i, ((high, low, close), expected)) in bars.into_iter().enumerate() {
+            let bar = Bar::new().high(high).low(low).close(close);
+            assert_eq!(round(stoch.next((day(i as i64), &bar))), expected);
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut stoch = SlowStochastic::new(DAY * 3, DAY * 2).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(stoch.next((start, 0.0)), 50.0);
+        assert_eq!(stoch.next((start + chrono::Duration::days(1), 200.0)), 75.0);
+
+        stoch.reset();
+
+        assert_eq!(stoch.next((start, 0.0)), 50.0);
+        assert_eq!(stoch.next((start + chrono::Duration::days(1), 200.0)), 75.0);
+    }
+
+    #[test]
+    fn test_default() {
+        SlowStochastic::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let indicator = SlowStochastic::new(DAY * 10, DAY * 2).unwrap();
+        assert_eq!(format!("{}", indicator), "SLOW_STOCH(10 days, 2 days)");
+    }
+}
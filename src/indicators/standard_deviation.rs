@@ -3,64 +3,187 @@ use std::fmt;
 use std::time::Duration;
 
 use crate::errors::Result;
-use crate::indicators::AdaptiveTimeDetector;
-use crate::{Next, Reset};
+use crate::helpers::{format_duration, round_deviation};
+use crate::indicators::{Aggregate, BucketPolicy, TimeWindow};
+#[cfg(feature = "serde")]
+use crate::indicators::{Sum, SumOfSquares};
+use crate::{Close, Next, Peek, Reset, Validate};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 #[doc(alias = "SD")]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct StandardDeviation {
-    duration: Duration, // Now std::time::Duration
+    window: TimeWindow<RunningSums>,
+}
+
+// Neumaier summation: `compensation` keeps what rounding drops from `sum`, so a large value
+// leaves nothing behind once it is subtracted again
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default)]
+struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    fn add(&mut self, value: f64) {
+        let sum = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - sum) + value;
+        } else {
+            self.compensation += (value - sum) + self.sum;
+        }
+        self.sum = sum;
+    }
+
+    fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+// Sums of the values and of their squares
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+struct RunningSums {
+    sum: CompensatedSum,
+    sum_sq: CompensatedSum,
+    // Values evicted since the sums were last summed again
+    evicted: usize,
+}
+
+impl RunningSums {
+    fn add(&mut self, value: f64) {
+        self.sum.add(value);
+        self.sum_sq.add(value * value);
+    }
+
+    fn remove(&mut self, value: f64) {
+        self.sum.add(-value);
+        self.sum_sq.add(-value * value);
+    }
+
+    // Sums the window again, dropping what the compensation could not keep
+    fn resum(&mut self, window: &VecDeque<(DateTime<Utc>, f64)>) {
+        *self = Self::default();
+        for &(_, value) in window {
+            self.add(value);
+        }
+    }
+}
+
+impl Aggregate for RunningSums {
+    fn push(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.add(value);
+    }
+
+    fn evict(&mut self, (_, value): (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.remove(value);
+
+        self.evicted += 1;
+        if self.evicted >= window.len() {
+            self.resum(window);
+        }
+    }
+
+    fn pop_back(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.remove(value);
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "StandardDeviation")]
+struct StateV2 {
+    window: TimeWindow<RunningSums>,
+}
+
+// Plain running sums, which drift once a large value has passed through them
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct StateV1 {
+    window: TimeWindow<(Sum, SumOfSquares)>,
+}
+
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<StandardDeviation> for StateV1 {
+    fn migrate(self) -> Result<StandardDeviation> {
+        Ok(StandardDeviation {
+            window: self.window.rebuild(RunningSums::default()),
+        })
+    }
+}
+
+// The unversioned layout of ta 0.5, also nested in its Bollinger Bands
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+pub(super) struct LegacyState {
+    duration: Duration,
     window: VecDeque<(DateTime<Utc>, f64)>,
+    #[allow(dead_code)] // recomputed from the window
     sum: f64,
+    #[allow(dead_code)] // recomputed from the window
     sum_sq: f64,
-    detector: AdaptiveTimeDetector,
+    detector: super::adaptive::LegacyDetector,
 }
 
+#[cfg(feature = "serde")]
+impl crate::state::Migrate<StandardDeviation> for LegacyState {
+    fn migrate(self) -> Result<StandardDeviation> {
+        Ok(StandardDeviation {
+            window: TimeWindow::from_legacy(self.duration, self.window, self.detector)?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(StandardDeviation, StateV2, 2, 0 => LegacyState, 1 => StateV1);
+
 impl StandardDeviation {
     pub fn get_window(&self) -> VecDeque<(DateTime<Utc>, f64)> {
-        self.window.clone()
+        self.window.values().clone()
     }
     pub fn new(duration: Duration) -> Result<Self> {
-        // std::time::Duration can't be negative, so just check if it's zero
-        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-            return Err(crate::errors::TaError::InvalidParameter);
-        }
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
         Ok(Self {
-            duration,
-            window: VecDeque::new(),
-            sum: 0.0,
-            sum_sq: 0.0,
-            detector: AdaptiveTimeDetector::new(duration),
+            window: TimeWindow::with_policy(duration, policy)?,
         })
     }
 
-    // Helper method to remove old data points
-    fn remove_old_data(&mut self, current_time: DateTime<Utc>) {
-        // Convert std::time::Duration to chrono::Duration for the subtraction
-        let chrono_duration = chrono::Duration::from_std(self.duration).unwrap();
-        while self
-            .window
-            .front()
-            .map_or(false, |(time, _)| *time <= current_time - chrono_duration)
-        {
-            if let Some((_, old_value)) = self.window.pop_front() {
-                self.sum -= old_value;
-                self.sum_sq -= old_value * old_value;
-            }
-        }
+    /// Adds a value without computing the deviation, for indicators reading the mean too.
+    pub(super) fn push(&mut self, timestamp: DateTime<Utc>, value: f64) {
+        self.window.push(timestamp, value);
     }
 
-    // Calculate the mean based on the current window
-    pub(super) fn mean(&self) -> f64 {
-        if !self.window.is_empty() {
-            self.sum / self.window.len() as f64
-        } else {
-            0.0
+    /// The latest value of the window.
+    pub(super) fn latest(&self) -> Option<f64> {
+        self.window.latest()
+    }
+
+    // Mean of the window and population standard deviation from it, both 0 while it is empty
+    pub(super) fn mean_and_deviation(&self) -> (f64, f64) {
+        if self.window.is_empty() {
+            return (0.0, 0.0);
         }
+        let len = self.window.len();
+        let sums = self.window.aggregate();
+        let mean = sums.sum.value() / len as f64;
+
+        let variance = ((sums.sum_sq.value() - sums.sum.value() * mean) / len as f64).max(0.0);
+        (mean, round_deviation(variance, len, mean * mean).sqrt())
+    }
+
+    fn deviation(&self) -> f64 {
+        self.mean_and_deviation().1
     }
 }
 
@@ -69,43 +192,45 @@ impl Next<f64> for StandardDeviation {
     fn next(&mut self, input: (DateTime<Utc>, f64)) -> Self::Output {
         let (timestamp, value) = input;
 
-        // Check if we should replace the last value (same time bucket)
-        let should_replace = self.detector.should_replace(timestamp);
+        // The window takes care of bucket replacement and old data removal
+        self.window.push(timestamp, value);
+        self.deviation()
+    }
+}
 
-        // ALWAYS remove old data first, regardless of replace/add
-        self.remove_old_data(timestamp);
+impl<T: Close> Next<&T> for StandardDeviation {
+    type Output = f64;
 
-        if should_replace && !self.window.is_empty() {
-            // Replace the last value in the same time bucket
-            if let Some((_, old_value)) = self.window.pop_back() {
-                self.sum -= old_value;
-                self.sum_sq -= old_value * old_value;
-            }
-        }
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
 
-        // Add new value to the window
-        self.window.push_back((timestamp, value));
-        self.sum += value;
-        self.sum_sq += value * value;
+impl Validate for StandardDeviation {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.window.latest_time()
+    }
+}
 
-        // Calculate the population standard deviation
-        let n = self.window.len() as f64;
-        if n == 0.0 {
-            0.0
-        } else {
-            let mean = self.sum / n;
-            let variance = (self.sum_sq - (self.sum * mean)) / n;
-            variance.sqrt()
-        }
+impl Peek for StandardDeviation {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.latest().map(|_| self.deviation())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.window.bucket_count()
     }
 }
 
 impl Reset for StandardDeviation {
     fn reset(&mut self) {
-        self.window.clear();
-        self.sum = 0.0;
-        self.sum_sq = 0.0;
-        self.detector.reset();
+        self.window.reset();
     }
 }
 
@@ -118,8 +243,7 @@ impl Default for StandardDeviation {
 
 impl fmt::Display for StandardDeviation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Use as_secs() instead of Debug format
-        write!(f, "SD({}s)", self.duration.as_secs())
+        write!(f, "SD({})", format_duration(self.window.duration()))
     }
 }
 
@@ -162,6 +286,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_flat_after_spike() {
+        let mut sd = StandardDeviation::new(Duration::from_secs(5 * 86400)).unwrap(); // 5 days
+        let now = Utc::now();
+        let values = [0.1, 0.7, 0.3, 1e6, 0.2];
+
+        for day in 0..20 {
+            let value = values.get(day).copied().unwrap_or(0.3);
+            let out = sd.next((now + chrono::Duration::days(day as i64), value));
+            // Only 0.3 is left in the window from day 9 on
+            if day >= 9 {
+                assert_eq!(out, 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_window_sum() {
+        // 100 minutes of 1 minute closes, with spikes passing through the window
+        let mut sd = StandardDeviation::new(Duration::from_secs(100 * 60)).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let mut window = VecDeque::new();
+
+        for n in 0..5000 {
+            let value = if n % 700 == 0 {
+                1e6
+            } else {
+                100.0 + (n as f64 * 0.7).sin()
+            };
+            window.push_back(value);
+            if window.len() > 100 {
+                window.pop_front();
+            }
+            let len = window.len() as f64;
+            let mean = window.iter().sum::<f64>() / len;
+            let variance = window.iter().map(|val| (val - mean).powi(2)).sum::<f64>() / len;
+
+            let out = sd.next((start + chrono::Duration::minutes(n), value));
+            assert!(
+                (out - variance.sqrt()).abs() <= 1e-9 * variance.sqrt(),
+                "{} != {} after {} minutes",
+                out,
+                variance.sqrt(),
+                n
+            );
+        }
+    }
+
     #[test]
     fn test_reset() {
         let duration = Duration::from_secs(4);
@@ -0,0 +1,555 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use super::adaptive::LegacyDetector;
+use crate::errors::{Result, TaError};
+use crate::helpers::{Extreme, MonotonicDeque};
+use crate::indicators::{AdaptiveTimeDetector, BucketPolicy};
+#[cfg(feature = "serde")]
+use crate::state::{self, Versioned};
+use crate::Reset;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A running statistic of the values of a [TimeWindow](struct.TimeWindow.html), updated as
+/// values enter and leave the window instead of scanning it.
+///
+/// Each method is called right after the window changed, with the `(time, value)` entry that was
+/// added or removed and the values of the window as they are now.
+pub trait Aggregate {
+    /// `entry` was pushed to the back of the window.
+    fn push(&mut self, entry: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>);
+
+    /// `entry` was evicted from the front of the window.
+    fn evict(&mut self, entry: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>);
+
+    /// `entry` was popped from the back of the window to be replaced by a newer value of the
+    /// same time bucket.
+    fn pop_back(&mut self, entry: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>);
+
+    /// The window was cleared.
+    fn clear(&mut self);
+}
+
+/// No aggregate, for indicators that only need the values.
+impl Aggregate for () {
+    fn push(&mut self, _: (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {}
+    fn evict(&mut self, _: (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {}
+    fn pop_back(&mut self, _: (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {}
+    fn clear(&mut self) {}
+}
+
+/// Two aggregates of the same window, e.g. `(Sum, SumOfSquares)` for a variance.
+impl<A: Aggregate, B: Aggregate> Aggregate for (A, B) {
+    fn push(&mut self, entry: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.0.push(entry, window);
+        self.1.push(entry, window);
+    }
+
+    fn evict(&mut self, entry: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.0.evict(entry, window);
+        self.1.evict(entry, window);
+    }
+
+    fn pop_back(&mut self, entry: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.0.pop_back(entry, window);
+        self.1.pop_back(entry, window);
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+        self.1.clear();
+    }
+}
+
+/// Sum of the values of the window.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct Sum {
+    sum: f64,
+}
+
+impl Sum {
+    pub fn value(&self) -> f64 {
+        self.sum
+    }
+}
+
+impl Aggregate for Sum {
+    fn push(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.sum += value;
+    }
+
+    fn evict(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.sum -= value;
+    }
+
+    fn pop_back(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.sum -= value;
+    }
+
+    fn clear(&mut self) {
+        self.sum = 0.0;
+    }
+}
+
+/// Sum of the squares of the values of the window.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct SumOfSquares {
+    sum_sq: f64,
+}
+
+impl SumOfSquares {
+    pub fn value(&self) -> f64 {
+        self.sum_sq
+    }
+}
+
+impl Aggregate for SumOfSquares {
+    fn push(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.sum_sq += value * value;
+    }
+
+    fn evict(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.sum_sq -= value * value;
+    }
+
+    fn pop_back(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.sum_sq -= value * value;
+    }
+
+    fn clear(&mut self) {
+        self.sum_sq = 0.0;
+    }
+}
+
+impl Aggregate for MonotonicDeque {
+    fn push(&mut self, (_, value): (DateTime<Utc>, f64), _: &VecDeque<(DateTime<Utc>, f64)>) {
+        MonotonicDeque::push(self, value);
+    }
+
+    fn evict(&mut self, _: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        MonotonicDeque::evict(self, window.len());
+    }
+
+    fn pop_back(&mut self, _: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        MonotonicDeque::pop_back(self, window);
+    }
+
+    fn clear(&mut self) {
+        MonotonicDeque::clear(self);
+    }
+}
+
+/// Highest value of the window, kept in a monotonic deque for amortised O(1) updates.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Highest {
+    deque: MonotonicDeque,
+}
+
+impl Highest {
+    /// `None` while the window is empty.
+    pub fn value(&self) -> Option<f64> {
+        self.deque.value()
+    }
+}
+
+impl Default for Highest {
+    fn default() -> Self {
+        Self {
+            deque: MonotonicDeque::new(Extreme::Max),
+        }
+    }
+}
+
+/// Lowest value of the window, kept in a monotonic deque for amortised O(1) updates.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Lowest {
+    deque: MonotonicDeque,
+}
+
+impl Lowest {
+    /// `None` while the window is empty.
+    pub fn value(&self) -> Option<f64> {
+        self.deque.value()
+    }
+}
+
+impl Default for Lowest {
+    fn default() -> Self {
+        Self {
+            deque: MonotonicDeque::new(Extreme::Min),
+        }
+    }
+}
+
+macro_rules! delegate_to_deque {
+    ($t:ty) => {
+        impl Aggregate for $t {
+            fn push(
+                &mut self,
+                entry: (DateTime<Utc>, f64),
+                window: &VecDeque<(DateTime<Utc>, f64)>,
+            ) {
+                Aggregate::push(&mut self.deque, entry, window);
+            }
+
+            fn evict(
+                &mut self,
+                entry: (DateTime<Utc>, f64),
+                window: &VecDeque<(DateTime<Utc>, f64)>,
+            ) {
+                Aggregate::evict(&mut self.deque, entry, window);
+            }
+
+            fn pop_back(
+                &mut self,
+                entry: (DateTime<Utc>, f64),
+                window: &VecDeque<(DateTime<Utc>, f64)>,
+            ) {
+                Aggregate::pop_back(&mut self.deque, entry, window);
+            }
+
+            fn clear(&mut self) {
+                Aggregate::clear(&mut self.deque);
+            }
+        }
+    };
+}
+
+delegate_to_deque!(Highest);
+delegate_to_deque!(Lowest);
+
+/// The values of the last `duration` together with an [Aggregate](trait.Aggregate.html) of them.
+///
+/// Owns the two rules every windowed indicator follows:
+///
+/// * A value is evicted once it is `duration` old or older, so the window covers
+///   `(latest - duration, latest]`. A 3 day window of daily closes holds 3 values.
+/// * A value that falls into the time bucket of the latest one, as decided by an
+///   [AdaptiveTimeDetector](struct.AdaptiveTimeDetector.html), replaces it.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::{Highest, Sum, TimeWindow};
+///
+/// let mut window = TimeWindow::<(Sum, Highest)>::new(Duration::from_secs(2 * 86400)).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 16, 0, 0).unwrap();
+///
+/// window.push(start, 3.0);
+/// window.push(start + ChronoDuration::days(1), 5.0);
+/// // A revision of the same day
+/// window.push(start + ChronoDuration::days(1) + ChronoDuration::hours(2), 4.0);
+/// assert_eq!(window.aggregate().0.value(), 7.0);
+///
+/// // The first day is now 2 days old
+/// window.push(start + ChronoDuration::days(2), 1.0);
+/// assert_eq!(window.len(), 2);
+/// assert_eq!(window.aggregate().1.value(), Some(4.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimeWindow<A = ()> {
+    duration: Duration,
+    values: VecDeque<(DateTime<Utc>, f64)>,
+    detector: AdaptiveTimeDetector,
+    aggregate: A,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TimeWindow")]
+struct StateV1<A> {
+    duration: Duration,
+    values: VecDeque<(DateTime<Utc>, f64)>,
+    detector: AdaptiveTimeDetector,
+    aggregate: A,
+}
+
+#[cfg(feature = "serde")]
+impl<A: Serialize + DeserializeOwned> Versioned for TimeWindow<A> {
+    const VERSION: u32 = 1;
+
+    fn serialize_state<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        StateV1::serialize(self, serializer)
+    }
+
+    fn deserialize_state<'de, D: Deserializer<'de>>(
+        version: u32,
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        match version {
+            1 => StateV1::deserialize(deserializer),
+            _ => Err(state::unsupported(version, Self::VERSION)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<A: Serialize + DeserializeOwned> Serialize for TimeWindow<A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        state::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, A: Serialize + DeserializeOwned> Deserialize<'de> for TimeWindow<A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        state::deserialize(deserializer)
+    }
+}
+
+impl<A: Aggregate + Default> TimeWindow<A> {
+    pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Self::with_aggregate(duration, policy, A::default())
+    }
+}
+
+impl<A: Aggregate> TimeWindow<A> {
+    /// Like [with_policy](#method.with_policy), for an aggregate without a default.
+    pub fn with_aggregate(duration: Duration, policy: BucketPolicy, aggregate: A) -> Result<Self> {
+        if duration.is_zero() {
+            return Err(TaError::InvalidParameter);
+        }
+        Ok(Self {
+            duration,
+            values: VecDeque::new(),
+            detector: AdaptiveTimeDetector::with_policy(duration, policy)?,
+            aggregate,
+        })
+    }
+
+    /// Adds `value` at `timestamp` after evicting the values that are `duration` old. Returns
+    /// the value it replaced if `timestamp` is in the time bucket of the latest value.
+    pub fn push(&mut self, timestamp: DateTime<Utc>, value: f64) -> Option<f64> {
+        // Check if we should replace the last value (same time bucket)
+        let should_replace = self.detector.should_replace(timestamp);
+
+        // ALWAYS remove old data first, regardless of replace/add
+        let cutoff = timestamp - chrono::Duration::from_std(self.duration).unwrap();
+        while let Some(&old) = self.values.front() {
+            if old.0 > cutoff {
+                break;
+            }
+            self.values.pop_front();
+            self.aggregate.evict(old, &self.values);
+        }
+
+        let replaced = if should_replace {
+            self.values.pop_back().map(|old| {
+                self.aggregate.pop_back(old, &self.values);
+                old.1
+            })
+        } else {
+            None
+        };
+
+        self.values.push_back((timestamp, value));
+        self.aggregate.push((timestamp, value), &self.values);
+        replaced
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// The values of the window with their times, oldest first.
+    pub fn values(&self) -> &VecDeque<(DateTime<Utc>, f64)> {
+        &self.values
+    }
+
+    pub fn aggregate(&self) -> &A {
+        &self.aggregate
+    }
+
+    /// The latest value, `None` while the window is empty.
+    pub fn latest(&self) -> Option<f64> {
+        self.values.back().map(|&(_, value)| value)
+    }
+
+    /// The time of the latest value, `None` while the window is empty.
+    pub fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.values.back().map(|&(time, _)| time)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Whether the updates since the last reset span the whole duration.
+    pub fn is_full(&self) -> bool {
+        self.detector.spans(self.duration)
+    }
+
+    /// Number of time buckets pushed since the last reset.
+    pub fn bucket_count(&self) -> usize {
+        self.detector.bucket_count()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<A: Aggregate + Default> TimeWindow<A> {
+    /// Rebuilds the window of an indicator serialized by ta 0.5 from the values it kept.
+    pub(crate) fn from_legacy(
+        duration: Duration,
+        values: VecDeque<(DateTime<Utc>, f64)>,
+        detector: LegacyDetector,
+    ) -> Result<Self> {
+        // The values are one per bucket already
+        let mut window = Self::with_policy(duration, BucketPolicy::NeverReplace)?;
+        for &(timestamp, value) in &values {
+            window.push(timestamp, value);
+        }
+        window.restore_detector(detector);
+        Ok(window)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<A: Aggregate> TimeWindow<A> {
+    /// Moves the values and the bucketing to a window with another aggregate, for a layout
+    /// that kept a different one.
+    pub(crate) fn rebuild<B: Aggregate>(self, aggregate: B) -> TimeWindow<B> {
+        let mut window = TimeWindow {
+            duration: self.duration,
+            values: VecDeque::with_capacity(self.values.len()),
+            detector: self.detector,
+            aggregate,
+        };
+        for entry in self.values {
+            window.values.push_back(entry);
+            window.aggregate.push(entry, &window.values);
+        }
+        window
+    }
+
+    /// Takes over the bucketing of a legacy detector once the values were pushed.
+    pub(crate) fn restore_detector(&mut self, detector: LegacyDetector) {
+        self.detector = detector.restore(self.values.iter().map(|&(timestamp, _)| timestamp));
+    }
+}
+
+impl<A: Aggregate> Reset for TimeWindow<A> {
+    fn reset(&mut self) {
+        self.values.clear();
+        self.aggregate.clear();
+        self.detector.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_new() {
+        assert!(TimeWindow::<Sum>::new(Duration::ZERO).is_err());
+        assert!(TimeWindow::<Sum>::with_policy(DAY, BucketPolicy::Fixed(Duration::ZERO)).is_err());
+        assert!(TimeWindow::<()>::new(DAY).is_ok());
+    }
+
+    #[test]
+    fn test_boundary() {
+        let mut window = TimeWindow::<Sum>::new(Duration::from_secs(3)).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let second = |n: i64| start + chrono::Duration::seconds(n);
+
+        for n in 0..3 {
+            window.push(second(n), n as f64);
+        }
+        assert_eq!(window.len(), 3);
+        assert!(!window.is_full());
+
+        // Exactly `duration` old is out
+        window.push(second(3), 3.0);
+        assert_eq!(window.values().front(), Some(&(second(1), 1.0)));
+        assert_eq!(window.aggregate().value(), 6.0);
+        assert!(window.is_full());
+
+        // A gap longer than the duration leaves only the new value
+        window.push(second(10), 10.0);
+        assert_eq!(window.len(), 1);
+        assert_eq!(window.aggregate().value(), 10.0);
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut window = TimeWindow::<(Sum, SumOfSquares)>::new(DAY * 2).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 14, 30, 0).unwrap();
+
+        assert_eq!(window.push(start, 2.0), None);
+        assert_eq!(
+            window.push(start + chrono::Duration::hours(1), 3.0),
+            Some(2.0)
+        );
+        assert_eq!(window.push(start + chrono::Duration::days(1), 4.0), None);
+
+        assert_eq!(window.len(), 2);
+        assert_eq!(window.bucket_count(), 2);
+        assert_eq!(window.latest(), Some(4.0));
+        assert_eq!(window.aggregate().0.value(), 7.0);
+        assert_eq!(window.aggregate().1.value(), 25.0);
+    }
+
+    #[test]
+    fn test_highest_and_lowest() {
+        let mut window =
+            TimeWindow::<(Highest, Lowest)>::new(Duration::from_secs(10 * 60)).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let minute = |n: i64| start + chrono::Duration::minutes(n);
+
+        window.push(minute(0), 3.0);
+        window.push(minute(1), 5.0);
+        window.push(minute(2), 1.0);
+        assert_eq!(window.aggregate().0.value(), Some(5.0));
+        assert_eq!(window.aggregate().1.value(), Some(1.0));
+
+        // Revising the minute restores the values the replaced one had pushed out
+        window.push(minute(3), 9.0);
+        window.push(minute(3) + chrono::Duration::seconds(30), 2.0);
+        assert_eq!(window.aggregate().0.value(), Some(5.0));
+        assert_eq!(window.aggregate().1.value(), Some(1.0));
+
+        window.push(minute(12), 4.0);
+        assert_eq!(window.aggregate().0.value(), Some(4.0));
+        assert_eq!(window.aggregate().1.value(), Some(2.0));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut window = TimeWindow::<(Sum, Highest)>::new(DAY).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        window.push(start, 5.0);
+        window.reset();
+        assert!(window.is_empty());
+        assert_eq!(window.bucket_count(), 0);
+        assert_eq!(window.aggregate().0.value(), 0.0);
+        assert_eq!(window.aggregate().1.value(), None);
+
+        // The first value after a reset is never a replacement
+        assert_eq!(window.push(start, 1.0), None);
+        assert_eq!(window.aggregate().1.value(), Some(1.0));
+    }
+}
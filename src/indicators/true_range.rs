@@ -0,0 +1,252 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::{Result, TaError};
+use crate::helpers::{format_duration, max3};
+use crate::indicators::{AdaptiveTimeDetector, BucketPolicy};
+use crate::{Close, High, Low, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The range of a day's trading is simply _high_ - _low_.
+/// The true range extends it to yesterday's closing price if it was outside of today's range.
+///
+/// The true range is the largest of the following:
+///
+/// * Most recent period's high minus the most recent period's low
+/// * Absolute value of the most recent period's high minus the previous close
+/// * Absolute value of the most recent period's low minus the previous close
+///
+/// True range has no window of its own; `duration` is the window of the indicator it feeds and
+/// only determines the time buckets. When an update falls into the same bucket as the previous
+/// one, the previous close stays the close of the prior period, like in
+/// [RelativeStrengthIndex](struct.RelativeStrengthIndex.html).
+#[derive(Debug, Clone)]
+pub struct TrueRange {
+    duration: Duration,
+    prev_close: Option<f64>,
+    close: Option<f64>,
+    current: Option<f64>,
+    detector: AdaptiveTimeDetector,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TrueRange")]
+struct StateV1 {
+    duration: Duration,
+    prev_close: Option<f64>,
+    close: Option<f64>,
+    current: Option<f64>,
+    detector: AdaptiveTimeDetector,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(TrueRange, StateV1, 1);
+
+impl TrueRange {
+    pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
+            Err(TaError::InvalidParameter)
+        } else {
+            Ok(Self {
+                duration,
+                prev_close: None,
+                close: None,
+                current: None,
+                detector: AdaptiveTimeDetector::with_policy(duration, policy)?,
+            })
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    // Returns the close of the last complete period
+    fn update_prev_close(&mut self, timestamp: DateTime<Utc>) -> Option<f64> {
+        let should_replace = self.detector.should_replace(timestamp);
+
+        // When replacing, prev_close stays as the previous period's close
+        if !should_replace || self.close.is_none() {
+            self.prev_close = self.close;
+        }
+        self.prev_close
+    }
+}
+
+impl Next<f64> for TrueRange {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        let distance = match self.update_prev_close(timestamp) {
+            Some(prev) => (value - prev).abs(),
+            None => 0.0,
+        };
+        self.close = Some(value);
+        self.current = Some(distance);
+        distance
+    }
+}
+
+impl<T: High + Low + Close> Next<&T> for TrueRange {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, bar): (DateTime<Utc>, &T)) -> Self::Output {
+        let max_dist = match self.update_prev_close(timestamp) {
+            Some(prev_close) => {
+                let dist1 = bar.high() - bar.low();
+                let dist2 = (bar.high() - prev_close).abs();
+                let dist3 = (bar.low() - prev_close).abs();
+                max3(dist1, dist2, dist3)
+            }
+            None => bar.high() - bar.low(),
+        };
+        self.close = Some(bar.close());
+        self.current = Some(max_dist);
+        max_dist
+    }
+}
+
+impl Validate for TrueRange {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.detector.latest()
+    }
+}
+
+impl Peek for TrueRange {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.current
+    }
+
+    fn is_ready(&self) -> bool {
+        self.prev_close.is_some()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.detector.bucket_count()
+    }
+}
+
+impl Reset for TrueRange {
+    fn reset(&mut self) {
+        self.prev_close = None;
+        self.close = None;
+        self.current = None;
+        self.detector.reset();
+    }
+}
+
+impl Default for TrueRange {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap()
+    }
+}
+
+impl fmt::Display for TrueRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TRUE_RANGE({})", format_duration(self.duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+    use chrono::TimeZone;
+
+    test_indicator!(TrueRange);
+
+    #[test]
+    fn test_new() {
+        assert!(TrueRange::new(Duration::from_secs(0)).is_err());
+        assert!(TrueRange::new(DAY).is_ok());
+    }
+
+    #[test]
+    fn test_next_f64() {
+        let mut tr = TrueRange::new(DAY * 3).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(round(tr.next((start, 2.5))), 0.0);
+        assert_eq!(
+            round(tr.next((start + chrono::Duration::days(1), 3.6))),
+            1.1
+        );
+        assert_eq!(
+            round(tr.next((start + chrono::Duration::days(2), 3.3))),
+            0.3
+        );
+    }
+
+    #[test]
+    fn test_next_bar() {
+        let mut tr = TrueRange::new(DAY * 3).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        let bar1 = Bar::new().high(10).low(7.5).close(9);
+        let bar2 = Bar::new().high(11).low(9).close(9.5);
+        let bar3 = Bar::new().high(9).low(5).close(8);
+
+        assert_eq!(tr.next((start, &bar1)), 2.5);
+        assert_eq!(tr.next((start + chrono::Duration::days(1), &bar2)), 2.0);
+        assert_eq!(tr.next((start + chrono::Duration::days(2), &bar3)), 4.5);
+    }
+
+    #[test]
+    fn test_replacement_keeps_prev_close() {
+        let mut tr = TrueRange::new(DAY * 3).unwrap();
+        let open = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
+        let next_open = open + chrono::Duration::days(1);
+
+        tr.next((open, &Bar::new().high(10).low(7.5).close(9)));
+
+        // Intraday revisions of the next session are measured against the
+        // close of the first session, not against the previous revision
+        let bar = Bar::new().high(9.5).low(9).close(9.2);
+        assert_eq!(tr.next((next_open, &bar)), 0.5);
+        let bar = Bar::new().high(12).low(9).close(11);
+        assert_eq!(tr.next((next_open + chrono::Duration::hours(1), &bar)), 3.0);
+        let bar = Bar::new().high(12).low(6).close(7);
+        assert_eq!(tr.next((next_open + chrono::Duration::hours(2), &bar)), 6.0);
+
+        // The next session uses the last revision as its previous close
+        let bar = Bar::new().high(8).low(7.5).close(7.5);
+        assert_eq!(tr.next((next_open + chrono::Duration::days(1), &bar)), 1.0);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut tr = TrueRange::new(DAY * 3).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        let bar1 = Bar::new().high(10).low(7.5).close(9);
+        let bar2 = Bar::new().high(11).low(9).close(9.5);
+
+        tr.next((start, &bar1));
+        tr.next((start + chrono::Duration::days(1), &bar2));
+
+        tr.reset();
+        let bar3 = Bar::new().high(60).low(15).close(51);
+        assert_eq!(tr.next((start, &bar3)), 45.0);
+    }
+
+    #[test]
+    fn test_default() {
+        TrueRange::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let indicator = TrueRange::new(DAY * 14).unwrap();
+        assert_eq!(format!("{}", indicator), "TRUE_RANGE(14 days)");
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::Result;
+use crate::helpers::format_duration;
+use crate::indicators::{Aggregate, BucketPolicy, TimeWindow};
+use crate::{Close, Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Weighted moving average (WMA) over a time window.
+///
+/// Two weightings are available:
+///
+/// * [new](#method.new) - linear weights by bucket position: the oldest bucket of the window
+///   weighs 1, the next one 2, up to `n` for the latest of `n` buckets
+/// * [time_weighted](#method.time_weighted) - weights growing linearly with time, from 0 at the
+///   start of the window to `duration` for a value of the latest time, so gaps in the data do not
+///   count as buckets
+///
+/// Both are updated in amortised O(1) from running sums, which are summed again from the window
+/// each time all of its values have been replaced, so rounding does not build up on long
+/// streams. An update within the same time bucket as the previous one replaces it.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::WeightedMovingAverage;
+/// use ta::Next;
+///
+/// let mut wma = WeightedMovingAverage::new(Duration::from_secs(3 * 86400)).unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 16, 0, 0).unwrap();
+///
+/// wma.next((start, 1.0));
+/// wma.next((start + ChronoDuration::days(1), 2.0));
+/// // (1 * 1 + 2 * 2 + 3 * 3) / (1 + 2 + 3)
+/// let value = wma.next((start + ChronoDuration::days(2), 3.0));
+/// assert!((value - 14.0 / 6.0).abs() < 1e-12);
+/// ```
+#[doc(alias = "WMA")]
+#[derive(Debug, Clone)]
+pub struct WeightedMovingAverage {
+    window: TimeWindow<WeightedSums>,
+    weighting: Weighting,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Weighting {
+    Linear,
+    Time,
+}
+
+// Running sums of the window for both weightings. Times are in seconds since `origin`, the front
+// of the window when the sums were last summed again, to keep the products small.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+struct WeightedSums {
+    sum: f64,
+    // Sum of the values weighted by their position in the window, 1 for the oldest
+    linear_sum: f64,
+    // Sum of the times, and of the values weighted by their time
+    time_sum: f64,
+    time_value_sum: f64,
+    origin: Option<DateTime<Utc>>,
+    // Values evicted since the sums were last summed again. Not serialized, to keep the layout
+    // of version 1: a restored window counts from 0 and sums again a little later.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    evicted: usize,
+}
+
+impl WeightedSums {
+    fn seconds(&self, time: DateTime<Utc>) -> f64 {
+        let origin = self.origin.unwrap_or(time);
+        (time - origin).num_milliseconds() as f64 / 1000.0
+    }
+
+    fn remove(&mut self, (time, value): (DateTime<Utc>, f64)) {
+        let seconds = self.seconds(time);
+        self.sum -= value;
+        self.time_sum -= seconds;
+        self.time_value_sum -= seconds * value;
+    }
+
+    // Sums the window again from its front, dropping the rounding of the running sums
+    fn resum(&mut self, window: &VecDeque<(DateTime<Utc>, f64)>) {
+        *self = Self::default();
+        for (position, &(time, value)) in window.iter().enumerate() {
+            self.origin.get_or_insert(time);
+            let seconds = self.seconds(time);
+            self.sum += value;
+            self.linear_sum += (position + 1) as f64 * value;
+            self.time_sum += seconds;
+            self.time_value_sum += seconds * value;
+        }
+    }
+}
+
+impl Aggregate for WeightedSums {
+    fn push(
+        &mut self,
+        (time, value): (DateTime<Utc>, f64),
+        window: &VecDeque<(DateTime<Utc>, f64)>,
+    ) {
+        self.origin.get_or_insert(time);
+        let seconds = self.seconds(time);
+        self.sum += value;
+        self.linear_sum += window.len() as f64 * value;
+        self.time_sum += seconds;
+        self.time_value_sum += seconds * value;
+    }
+
+    fn evict(&mut self, entry: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        // Every remaining value moves one position closer to the front
+        self.linear_sum -= self.sum;
+        self.remove(entry);
+
+        self.evicted += 1;
+        if self.evicted >= window.len() {
+            self.resum(window);
+        }
+    }
+
+    fn pop_back(&mut self, entry: (DateTime<Utc>, f64), window: &VecDeque<(DateTime<Utc>, f64)>) {
+        self.linear_sum -= (window.len() + 1) as f64 * entry.1;
+        self.remove(entry);
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "WeightedMovingAverage")]
+struct StateV1 {
+    window: TimeWindow<WeightedSums>,
+    weighting: Weighting,
+}
+
+#[cfg(feature = "serde")]
+versioned_serde!(WeightedMovingAverage, StateV1, 1);
+
+impl WeightedMovingAverage {
+    pub fn new(duration: Duration) -> Result<Self> {
+        Self::with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [new](#method.new), with the time buckets set by `policy` instead of `duration`.
+    pub fn with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Self::with_weighting(duration, policy, Weighting::Linear)
+    }
+
+    /// Creates a WMA weighting each value by its time: a value at the start of the window weighs
+    /// nothing and one of the latest time weighs `duration`. Unlike the linear weights, a day
+    /// without data lowers the weight of the days before it.
+    pub fn time_weighted(duration: Duration) -> Result<Self> {
+        Self::time_weighted_with_policy(duration, BucketPolicy::Heuristic)
+    }
+
+    /// Like [time_weighted](#method.time_weighted), with the time buckets set by `policy` instead
+    /// of `duration`.
+    pub fn time_weighted_with_policy(duration: Duration, policy: BucketPolicy) -> Result<Self> {
+        Self::with_weighting(duration, policy, Weighting::Time)
+    }
+
+    fn with_weighting(
+        duration: Duration,
+        policy: BucketPolicy,
+        weighting: Weighting,
+    ) -> Result<Self> {
+        Ok(Self {
+            window: TimeWindow::with_policy(duration, policy)?,
+            weighting,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.window.duration()
+    }
+
+    fn average(&self) -> f64 {
+        let sums = self.window.aggregate();
+        let n = self.window.len() as f64;
+        match self.weighting {
+            Weighting::Linear => sums.linear_sum / (n * (n + 1.0) / 2.0),
+            Weighting::Time => {
+                // Weights are the seconds since the start of the window
+                let latest = self.window.latest_time().expect("called after a push");
+                let start = sums.seconds(latest) - self.duration().as_secs_f64();
+                (sums.time_value_sum - start * sums.sum) / (sums.time_sum - start * n)
+            }
+        }
+    }
+}
+
+impl Next<f64> for WeightedMovingAverage {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Self::Output {
+        self.window.push(timestamp, value);
+        self.average()
+    }
+}
+
+impl<T: Close> Next<&T> for WeightedMovingAverage {
+    type Output = f64;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &T)) -> Self::Output {
+        self.next((timestamp, input.close()))
+    }
+}
+
+impl Validate for WeightedMovingAverage {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.window.latest_time()
+    }
+}
+
+impl Peek for WeightedMovingAverage {
+    type Output = f64;
+
+    fn current(&self) -> Option<Self::Output> {
+        if self.window.is_empty() {
+            None
+        } else {
+            Some(self.average())
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.window.bucket_count()
+    }
+}
+
+impl Reset for WeightedMovingAverage {
+    fn reset(&mut self) {
+        self.window.reset();
+    }
+}
+
+impl Default for WeightedMovingAverage {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(14 * 24 * 60 * 60)).unwrap()
+    }
+}
+
+impl fmt::Display for WeightedMovingAverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.weighting {
+            Weighting::Linear => write!(f, "WMA({})", format_duration(self.duration())),
+            Weighting::Time => write!(
+                f,
+                "WMA({}, time weighted)",
+                format_duration(self.duration())
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::*;
+
+    #[test]
+    fn test_new() {
+        assert!(WeightedMovingAverage::new(Duration::from_secs(0)).is_err());
+        assert!(WeightedMovingAverage::new(Duration::from_secs(1)).is_ok());
+        assert!(WeightedMovingAverage::time_weighted(Duration::from_secs(0)).is_err());
+    }
+
+    #[test]
+    fn test_next() {
+        let mut wma = WeightedMovingAverage::new(DAY * 3).unwrap();
+
+        assert_eq!(wma.next((day(0), 1.0)), 1.0);
+        assert_eq!(round(wma.next((day(1), 2.0))), 1.667);
+        assert_eq!(round(wma.next((day(2), 3.0))), 2.333);
+        // The first day has left the window: (2 * 1 + 3 * 2 + 4 * 3) / 6
+        assert_eq!(round(wma.next((day(3), 4.0))), 3.333);
+        assert_eq!(round(wma.next((day(4), 1.0))), 2.333);
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut wma = WeightedMovingAverage::new(DAY * 3).unwrap();
+
+        wma.next((day(0), 1.0));
+        wma.next((day(1), 2.0));
+        wma.next((day(2), 3.0));
+        // A revision of the latest day keeps the weight of the latest bucket
+        let revision = day(2) + chrono::Duration::hours(1);
+        assert_eq!(round(wma.next((revision, 6.0))), 3.833);
+        assert_eq!(wma.sample_count(), 3);
+        assert_eq!(round(wma.next((day(3), 4.0))), 4.333);
+    }
+
+    #[test]
+    fn test_time_weighted() {
+        let mut wma = WeightedMovingAverage::time_weighted(DAY * 3).unwrap();
+        let mut linear = WeightedMovingAverage::new(DAY * 3).unwrap();
+
+        assert_eq!(wma.next((day(0), 1.0)), 1.0);
+        // Day 0 weighs 1 day and day 2 weighs 3 days, the missing day 1 counts
+        assert_eq!(wma.next((day(2), 3.0)), 2.5);
+        linear.next((day(0), 1.0));
+        assert_eq!(round(linear.next((day(2), 3.0))), 2.333);
+
+        // Day 0 has left the window: day 2 weighs 2 days and day 3 weighs 3
+        assert_eq!(wma.next((day(3), 4.0)), 3.6);
+        // A revision an hour later moves the start of the window as well: (47h * 3 + 72h * 5) / 119h
+        let revision = day(3) + chrono::Duration::hours(1);
+        assert_eq!(round(wma.next((revision, 5.0))), 4.21);
+    }
+
+    #[test]
+    fn test_long_stream() {
+        for (mut wma, mut fresh) in [
+            (
+                WeightedMovingAverage::new(DAY * 3).unwrap(),
+                WeightedMovingAverage::new(DAY * 3).unwrap(),
+            ),
+            (
+                WeightedMovingAverage::time_weighted(DAY * 3).unwrap(),
+                WeightedMovingAverage::time_weighted(DAY * 3).unwrap(),
+            ),
+        ] {
+            let value = |n: i64| if n == 5 { 1e9 } else { 0.1 * (n % 7) as f64 };
+            let mut latest = 0.0;
+            for n in 0..2000 {
+                latest = wma.next((day(n), value(n)));
+            }
+
+            // The same as a WMA that only saw the values of the window, long after the spike
+            for n in 1997..2000 {
+                fresh.next((day(n), value(n)));
+            }
+            assert_eq!(latest, fresh.current().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_with_policy() {
+        let mut wma =
+            WeightedMovingAverage::with_policy(DAY * 2, BucketPolicy::NeverReplace).unwrap();
+
+        wma.next((day(0), 1.0));
+        assert_eq!(round(wma.next((day(0), 4.0))), 3.0);
+        assert_eq!(wma.sample_count(), 2);
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut wma = WeightedMovingAverage::new(DAY * 2).unwrap();
+        assert_eq!(wma.current(), None);
+
+        wma.next((day(0), 3.0));
+        assert!(!wma.is_ready());
+        wma.next((day(2), 6.0));
+        assert_eq!(wma.current(), Some(6.0));
+        assert!(wma.is_ready());
+    }
+
+    #[test]
+    fn test_bars() {
+        let mut wma = WeightedMovingAverage::new(DAY * 2).unwrap();
+
+        wma.next((day(0), &Bar::new().close(3)));
+        assert_eq!(wma.next((day(1), &Bar::new().close(6))), 5.0);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut wma = WeightedMovingAverage::time_weighted(DAY * 3).unwrap();
+
+        wma.next((day(0), 4.0));
+        wma.next((day(1), 8.0));
+        wma.reset();
+        assert_eq!(wma.current(), None);
+        assert_eq!(wma.next((day(5), 2.0)), 2.0);
+        assert_eq!(round(wma.next((day(6), 5.0))), 3.8);
+    }
+
+    #[test]
+    fn test_default() {
+        WeightedMovingAverage::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let wma = WeightedMovingAverage::new(DAY * 7).unwrap();
+        assert_eq!(format!("{}", wma), "WMA(7 days)");
+        let wma = WeightedMovingAverage::time_weighted(DAY).unwrap();
+        assert_eq!(format!("{}", wma), "WMA(1 days, time weighted)");
+    }
+}
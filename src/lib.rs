@@ -4,6 +4,10 @@ mod test_helper;
 
 mod helpers;
 
+#[cfg(feature = "serde")]
+#[macro_use]
+pub mod state;
+
 pub mod errors;
 pub mod indicators;
 
@@ -12,3 +16,18 @@ pub use crate::traits::*;
 
 mod data_item;
 pub use crate::data_item::DataItem;
+
+mod bar_builder;
+pub use crate::bar_builder::{BarBuilder, BarUpdate};
+
+mod warm_up;
+pub use crate::warm_up::WarmUp;
+
+mod ordered;
+pub use crate::ordered::{Ordered, OutOfOrderPolicy};
+
+mod batch;
+pub use crate::batch::{BarOutputs, Batch, Outputs};
+
+mod session_calendar;
+pub use crate::session_calendar::{DstRule, SessionCalendar};
@@ -0,0 +1,439 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use crate::errors::{Result, TaError};
+#[cfg(feature = "serde")]
+use crate::state::{self, Versioned};
+use crate::{Next, Peek, Reset};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+
+/// What [Ordered](struct.Ordered.html) does with an update older than the latest one.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutOfOrderPolicy {
+    /// Return a `TaError::OutOfOrder` and leave the indicator unchanged.
+    #[default]
+    Reject,
+    /// Ignore the update and return the output of the latest one.
+    Drop,
+    /// Insert the update at its place in time and recompute the indicator from there. Updates
+    /// older than the latest one by more than the given delay are rejected.
+    Insert(Duration),
+}
+
+/// Wraps an indicator so that updates arriving out of order follow an [OutOfOrderPolicy]
+/// instead of corrupting its window.
+///
+/// Updates with the same timestamp as the latest one are in order: they replace its time bucket
+/// as usual. The output is a `Result`, an error for the updates that are rejected.
+///
+/// For [Insert](enum.OutOfOrderPolicy.html#variant.Insert) the wrapper keeps a second copy of
+/// the indicator that has seen the updates older than the delay, and the updates since. A late
+/// update is inserted among those and they are fed again to that copy, so the result is the same
+/// as if the updates had arrived in order, at a cost proportional to the number of updates within
+/// the delay.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::SimpleMovingAverage;
+/// use ta::{Next, Ordered, OutOfOrderPolicy};
+///
+/// let sma = SimpleMovingAverage::new(Duration::from_secs(3 * 86400)).unwrap();
+/// let policy = OutOfOrderPolicy::Insert(Duration::from_secs(86400));
+/// let mut sma = Ordered::new(sma, policy);
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+///
+/// assert_eq!(sma.next((start, 2.0)), Ok(2.0));
+/// assert_eq!(sma.next((start + ChronoDuration::days(2), 6.0)), Ok(4.0));
+/// // The close of the day in between arrives late
+/// assert_eq!(sma.next((start + ChronoDuration::days(1), 7.0)), Ok(5.0));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Ordered<I, T = f64> {
+    indicator: I,
+    policy: OutOfOrderPolicy,
+    latest: Option<DateTime<Utc>>,
+    // Insert only: the indicator fed with the updates older than the delay, and the updates
+    // since in time order
+    settled: Option<I>,
+    pending: VecDeque<(DateTime<Utc>, T)>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Ordered")]
+struct StateV1<I, T> {
+    indicator: I,
+    policy: OutOfOrderPolicy,
+    latest: Option<DateTime<Utc>>,
+    settled: Option<I>,
+    pending: VecDeque<(DateTime<Utc>, T)>,
+}
+
+#[cfg(feature = "serde")]
+impl<I, T> Versioned for Ordered<I, T>
+where
+    I: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+{
+    const VERSION: u32 = 1;
+
+    fn serialize_state<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        StateV1::serialize(self, serializer)
+    }
+
+    fn deserialize_state<'de, D: Deserializer<'de>>(
+        version: u32,
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        match version {
+            1 => StateV1::deserialize(deserializer),
+            _ => Err(state::unsupported(version, Self::VERSION)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<I, T> Serialize for Ordered<I, T>
+where
+    I: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        state::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, I, T> Deserialize<'de> for Ordered<I, T>
+where
+    I: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        state::deserialize(deserializer)
+    }
+}
+
+impl<I: Clone, T> Ordered<I, T> {
+    pub fn new(indicator: I, policy: OutOfOrderPolicy) -> Self {
+        let settled = match policy {
+            OutOfOrderPolicy::Insert(_) => Some(indicator.clone()),
+            _ => None,
+        };
+        Self {
+            indicator,
+            policy,
+            latest: None,
+            settled,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<I, T> Ordered<I, T> {
+    /// The wrapped indicator.
+    pub fn inner(&self) -> &I {
+        &self.indicator
+    }
+
+    pub fn into_inner(self) -> I {
+        self.indicator
+    }
+
+    pub fn policy(&self) -> OutOfOrderPolicy {
+        self.policy
+    }
+
+    /// The latest timestamp fed, `None` before the first update.
+    pub fn latest(&self) -> Option<DateTime<Utc>> {
+        self.latest
+    }
+
+    // Updates before this time can no longer be inserted, `None` if all of them can
+    fn horizon(&self, max_delay: Duration) -> Option<DateTime<Utc>> {
+        let latest = self.latest?;
+        chrono::Duration::from_std(max_delay)
+            .ok()
+            .and_then(|delay| latest.checked_sub_signed(delay))
+    }
+
+    fn out_of_order(&self, timestamp: DateTime<Utc>) -> TaError
+    where
+        I: fmt::Display,
+    {
+        TaError::OutOfOrder {
+            indicator: self.indicator.to_string(),
+            timestamp,
+            latest: self.latest.expect("set by the first update"),
+        }
+    }
+
+    fn update<O>(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        input: T,
+        feed: impl Fn(&mut I, DateTime<Utc>, &T) -> O,
+    ) -> Result<O>
+    where
+        I: Peek<Output = O> + Clone + fmt::Display,
+    {
+        if self.latest.map_or(true, |latest| timestamp >= latest) {
+            self.latest = Some(timestamp);
+            let output = feed(&mut self.indicator, timestamp, &input);
+            if let OutOfOrderPolicy::Insert(max_delay) = self.policy {
+                self.pending.push_back((timestamp, input));
+                self.settle(max_delay, &feed);
+            }
+            return Ok(output);
+        }
+
+        match self.policy {
+            OutOfOrderPolicy::Reject => Err(self.out_of_order(timestamp)),
+            OutOfOrderPolicy::Drop => Ok(self.current_output()),
+            OutOfOrderPolicy::Insert(max_delay) => {
+                if self
+                    .horizon(max_delay)
+                    .is_some_and(|horizon| timestamp < horizon)
+                {
+                    return Err(self.out_of_order(timestamp));
+                }
+                // After the updates with the same timestamp, which arrived before it
+                let at = self.pending.partition_point(|&(time, _)| time <= timestamp);
+                self.pending.insert(at, (timestamp, input));
+
+                let mut indicator = self.settled.clone().expect("kept for Insert");
+                for (time, input) in &self.pending {
+                    feed(&mut indicator, *time, input);
+                }
+                self.indicator = indicator;
+                Ok(self.current_output())
+            }
+        }
+    }
+
+    // Feeds the settled copy with the updates that are too old to be preceded by a late one
+    fn settle<O>(&mut self, max_delay: Duration, feed: impl Fn(&mut I, DateTime<Utc>, &T) -> O) {
+        let Some(horizon) = self.horizon(max_delay) else {
+            return;
+        };
+        let settled = self.settled.as_mut().expect("kept for Insert");
+        while let Some((time, input)) = self.pending.front() {
+            if *time >= horizon {
+                break;
+            }
+            feed(settled, *time, input);
+            self.pending.pop_front();
+        }
+    }
+
+    fn current_output<O>(&self) -> O
+    where
+        I: Peek<Output = O>,
+    {
+        self.indicator
+            .current()
+            .expect("an update in order was fed before a late one")
+    }
+}
+
+impl<I, T, O> Next<T> for Ordered<I, T>
+where
+    I: Next<T, Output = O> + Peek<Output = O> + Clone + fmt::Display,
+    T: Clone,
+{
+    type Output = Result<O>;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, T)) -> Self::Output {
+        self.update(timestamp, input, |indicator, time, input| {
+            indicator.next((time, input.clone()))
+        })
+    }
+}
+
+/// Bars are fed by reference, like to the indicators, and kept as copies for `Insert`.
+impl<'a, I, T, O> Next<&'a T> for Ordered<I, T>
+where
+    I: for<'b> Next<&'b T, Output = O> + Peek<Output = O> + Clone + fmt::Display,
+    T: Clone,
+{
+    type Output = Result<O>;
+
+    fn next(&mut self, (timestamp, input): (DateTime<Utc>, &'a T)) -> Self::Output {
+        self.update(timestamp, input.clone(), |indicator, time, input| {
+            indicator.next((time, input))
+        })
+    }
+}
+
+impl<I: Peek, T> Peek for Ordered<I, T> {
+    type Output = I::Output;
+
+    fn current(&self) -> Option<Self::Output> {
+        self.indicator.current()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.indicator.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.indicator.sample_count()
+    }
+}
+
+impl<I: Reset, T> Reset for Ordered<I, T> {
+    fn reset(&mut self) {
+        self.indicator.reset();
+        if let Some(settled) = self.settled.as_mut() {
+            settled.reset();
+        }
+        self.latest = None;
+        self.pending.clear();
+    }
+}
+
+impl<I: fmt::Display, T> fmt::Display for Ordered<I, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.indicator.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::{
+        ExponentialMovingAverage as Ema, Maximum, RelativeStrengthIndex, SimpleMovingAverage,
+    };
+    use crate::test_helper::*;
+
+    #[test]
+    fn test_reject() {
+        let mut max = Ordered::new(Maximum::new(DAY * 3).unwrap(), OutOfOrderPolicy::Reject);
+
+        assert_eq!(max.next((day(0), 2.0)), Ok(2.0));
+        assert_eq!(max.next((day(2), 1.0)), Ok(2.0));
+        assert_eq!(
+            max.next((day(1), 5.0)),
+            Err(TaError::OutOfOrder {
+                indicator: "MAX(3 days)".to_string(),
+                timestamp: day(1),
+                latest: day(2),
+            })
+        );
+        assert_eq!(max.inner().get_window().len(), 2);
+        // The same timestamp replaces the latest bucket
+        assert_eq!(max.next((day(2), 3.0)), Ok(3.0));
+    }
+
+    #[test]
+    fn test_drop() {
+        let mut sma = Ordered::new(
+            SimpleMovingAverage::new(DAY * 3).unwrap(),
+            OutOfOrderPolicy::Drop,
+        );
+
+        sma.next((day(0), 2.0)).unwrap();
+        assert_eq!(sma.next((day(1), 4.0)), Ok(3.0));
+        assert_eq!(sma.next((day(0), 100.0)), Ok(3.0));
+        assert_eq!(sma.next((day(2), 6.0)), Ok(4.0));
+        assert_eq!(sma.latest(), Some(day(2)));
+    }
+
+    #[test]
+    fn test_insert() {
+        let policy = OutOfOrderPolicy::Insert(DAY * 2);
+        let mut rsi = Ordered::new(RelativeStrengthIndex::wilder(DAY * 3).unwrap(), policy);
+        let mut in_order = RelativeStrengthIndex::wilder(DAY * 3).unwrap();
+        let intraday = day(3) + chrono::Duration::hours(1);
+
+        let arrivals = [
+            (day(0), 10.0),
+            (day(1), 11.0),
+            (day(3), 11.5),
+            (day(2), 10.5),
+            (day(4), 11.0),
+            (intraday, 12.0),
+        ];
+        let outputs: Vec<_> = arrivals
+            .iter()
+            .map(|&input| rsi.next(input).map(round))
+            .collect();
+        assert_eq!(outputs[3], Ok(80.0));
+        assert_eq!(outputs[5], Ok(55.556));
+
+        for input in [
+            (day(0), 10.0),
+            (day(1), 11.0),
+            (day(2), 10.5),
+            (day(3), 11.5),
+            (intraday, 12.0),
+            (day(4), 11.0),
+        ] {
+            in_order.next(input);
+        }
+        assert_eq!(rsi.current(), in_order.current());
+        assert_eq!(rsi.sample_count(), in_order.sample_count());
+    }
+
+    #[test]
+    fn test_insert_too_late() {
+        let policy = OutOfOrderPolicy::Insert(DAY);
+        let mut ema = Ordered::new(Ema::new(DAY * 3).unwrap(), policy);
+
+        ema.next((day(0), 2.0)).unwrap();
+        ema.next((day(1), 5.0)).unwrap();
+        ema.next((day(3), 6.25)).unwrap();
+        assert!(matches!(
+            ema.next((day(1), 1.0)),
+            Err(TaError::OutOfOrder { .. })
+        ));
+        assert_eq!(ema.next((day(2), 1.0)), Ok(4.25));
+    }
+
+    #[test]
+    fn test_bars() {
+        let policy = OutOfOrderPolicy::Insert(DAY);
+        let mut max = Ordered::<_, Bar>::new(Maximum::new(DAY * 2).unwrap(), policy);
+
+        max.next((day(0), &Bar::new().close(3))).unwrap();
+        max.next((day(2), &Bar::new().close(1))).unwrap();
+        assert_eq!(max.next((day(1), &Bar::new().close(4))), Ok(4.0));
+    }
+
+    #[test]
+    fn test_reset() {
+        let policy = OutOfOrderPolicy::Insert(DAY);
+        let mut sma = Ordered::new(SimpleMovingAverage::new(DAY * 3).unwrap(), policy);
+
+        sma.next((day(5), 4.0)).unwrap();
+        sma.reset();
+        assert_eq!(sma.latest(), None);
+        assert_eq!(sma.next((day(0), 2.0)), Ok(2.0));
+        assert_eq!(sma.sample_count(), 1);
+    }
+
+    #[test]
+    fn test_default() {
+        let ordered = Ordered::<SimpleMovingAverage>::default();
+        assert_eq!(ordered.policy(), OutOfOrderPolicy::Reject);
+    }
+
+    #[test]
+    fn test_display() {
+        let sma: Ordered<_> = Ordered::new(
+            SimpleMovingAverage::new(DAY * 7).unwrap(),
+            OutOfOrderPolicy::Drop,
+        );
+        assert_eq!(format!("{}", sma), format!("{}", sma.inner()));
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::errors::{Result, TaError};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc, Weekday};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Daylight saving time rule of an exchange time zone.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DstRule {
+    /// The UTC offset never changes.
+    None,
+    /// +1 hour from the second Sunday of March to the first Sunday of November.
+    UnitedStates,
+    /// +1 hour from the last Sunday of March to the last Sunday of October.
+    Europe,
+}
+
+impl DstRule {
+    fn is_summer_time(self, date: NaiveDate) -> bool {
+        let year = date.year();
+        let (start, end) = match self {
+            DstRule::None => return false,
+            DstRule::UnitedStates => (
+                NaiveDate::from_weekday_of_month_opt(year, 3, Weekday::Sun, 2).unwrap(),
+                NaiveDate::from_weekday_of_month_opt(year, 11, Weekday::Sun, 1).unwrap(),
+            ),
+            DstRule::Europe => (last_sunday(year, 3), last_sunday(year, 10)),
+        };
+        start <= date && date < end
+    }
+}
+
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    let last_day = NaiveDate::from_ymd_opt(year, month + 1, 1)
+        .unwrap()
+        .pred_opt()
+        .unwrap();
+    last_day - chrono::Duration::days(last_day.weekday().num_days_from_sunday() as i64)
+}
+
+/// Trading sessions of an exchange: open and close times in the exchange time zone,
+/// trading weekdays, holidays and half days.
+///
+/// A session that closes at or before its open time runs overnight and belongs to the trading
+/// day it closes on, like futures opening at 18:00 the evening before.
+///
+/// Daylight saving time switches are applied per local date, which is exact as long as no
+/// session is open at 02:00 local time on the day of the switch.
+///
+/// # File format
+///
+/// One `key = value` pair per line, `#` starts a comment. `open` and `close` are required,
+/// `holiday` and `half_day` may be repeated.
+///
+/// ```text
+/// # New York Stock Exchange
+/// name = XNYS
+/// utc_offset = -05:00
+/// dst = us
+/// open = 09:30
+/// close = 16:00
+/// weekdays = mon tue wed thu fri
+/// holiday = 2024-07-04
+/// half_day = 2024-07-03 13:00
+/// ```
+///
+/// `utc_offset` is the standard (winter) offset and defaults to `+00:00`, `dst` is one of
+/// `none` (default), `us` or `eu` and `weekdays` defaults to Monday to Friday.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionCalendar {
+    name: String,
+    utc_offset: i32, // seconds east of UTC outside of daylight saving time
+    dst: DstRule,
+    open: NaiveTime,
+    close: NaiveTime,
+    weekdays: [bool; 7], // indexed by days from Monday
+    holidays: BTreeSet<NaiveDate>,
+    half_days: BTreeMap<NaiveDate, NaiveTime>,
+}
+
+// How far back to look for the previous session, enough for any run of holidays
+const MAX_DAYS_WITHOUT_SESSION: i64 = 40;
+
+impl SessionCalendar {
+    /// Loads a calendar from a file in the format described above. Fails with
+    /// [TaError::Io](errors/enum.TaError.html#variant.Io) if the file cannot be read.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|error| TaError::Io {
+            path: path.display().to_string(),
+            kind: error.kind(),
+            message: error.to_string(),
+        })?;
+        content.parse()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the exchange has a session on `date`.
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        self.weekdays[date.weekday().num_days_from_monday() as usize]
+            && !self.holidays.contains(&date)
+    }
+
+    /// Open and close of the session of the trading day `date`, `None` if it is not one.
+    pub fn session(&self, date: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if !self.is_trading_day(date) {
+            return None;
+        }
+        let close_time = self.half_days.get(&date).copied().unwrap_or(self.close);
+        let open_date = if self.close <= self.open {
+            date.pred_opt()?
+        } else {
+            date
+        };
+        Some((
+            self.to_utc(open_date.and_time(self.open)),
+            self.to_utc(date.and_time(close_time)),
+        ))
+    }
+
+    /// Whether `timestamp` falls within a session.
+    pub fn is_open(&self, timestamp: DateTime<Utc>) -> bool {
+        self.trading_day(timestamp)
+            .and_then(|date| self.session(date))
+            .is_some_and(|(_, close)| timestamp < close)
+    }
+
+    /// The trading day of the latest session that opened at or before `timestamp`.
+    ///
+    /// Updates after the close belong to the session that closed until the next one opens.
+    pub fn trading_day(&self, timestamp: DateTime<Utc>) -> Option<NaiveDate> {
+        // Local dates are at most a day off UTC, and overnight sessions add one more
+        let mut date = timestamp.date_naive() + chrono::Duration::days(2);
+        for _ in 0..MAX_DAYS_WITHOUT_SESSION {
+            if let Some((open, _)) = self.session(date) {
+                if open <= timestamp {
+                    return Some(date);
+                }
+            }
+            date = date.pred_opt()?;
+        }
+        None
+    }
+
+    fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let mut offset = self.utc_offset as i64;
+        if self.dst.is_summer_time(local.date()) {
+            offset += 3600;
+        }
+        (local - chrono::Duration::seconds(offset)).and_utc()
+    }
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M").ok()
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+// Parses `+HH:MM` or `-HH:MM` into seconds east of UTC
+fn parse_offset(value: &str) -> Option<i32> {
+    let (sign, rest) = match value.as_bytes().first() {
+        Some(b'+') => (1, &value[1..]),
+        Some(b'-') => (-1, &value[1..]),
+        _ => return None,
+    };
+    let time = parse_time(rest)?;
+    if time.hour() > 14 {
+        return None;
+    }
+    Some(sign * (time.hour() * 3600 + time.minute() * 60) as i32)
+}
+
+fn parse_weekdays(value: &str) -> Option<[bool; 7]> {
+    let mut weekdays = [false; 7];
+    for day in value.split(|c: char| c == ',' || c.is_whitespace()) {
+        if !day.is_empty() {
+            let day: Weekday = day.parse().ok()?;
+            weekdays[day.num_days_from_monday() as usize] = true;
+        }
+    }
+    Some(weekdays)
+}
+
+fn parse_half_day(value: &str) -> Option<(NaiveDate, NaiveTime)> {
+    let (date, time) = value.split_once(char::is_whitespace)?;
+    Some((parse_date(date)?, parse_time(time.trim())?))
+}
+
+impl FromStr for SessionCalendar {
+    type Err = TaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut calendar = SessionCalendar {
+            name: String::new(),
+            utc_offset: 0,
+            dst: DstRule::None,
+            open: NaiveTime::MIN,
+            close: NaiveTime::MIN,
+            weekdays: [true, true, true, true, true, false, false],
+            holidays: BTreeSet::new(),
+            half_days: BTreeMap::new(),
+        };
+        let (mut open, mut close) = (None, None);
+
+        for (number, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            // A line without `=` is reported with the whole line as its key
+            let (key, value) = line.split_once('=').unwrap_or((line, ""));
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || TaError::InvalidCalendar {
+                line: Some(number + 1),
+                key: key.to_string(),
+            };
+            match key {
+                "name" => calendar.name = value.to_string(),
+                "utc_offset" => calendar.utc_offset = parse_offset(value).ok_or_else(invalid)?,
+                "dst" => {
+                    calendar.dst = match value {
+                        "none" => DstRule::None,
+                        "us" => DstRule::UnitedStates,
+                        "eu" => DstRule::Europe,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "open" => open = Some(parse_time(value).ok_or_else(invalid)?),
+                "close" => close = Some(parse_time(value).ok_or_else(invalid)?),
+                "weekdays" => calendar.weekdays = parse_weekdays(value).ok_or_else(invalid)?,
+                "holiday" => {
+                    calendar
+                        .holidays
+                        .insert(parse_date(value).ok_or_else(invalid)?);
+                }
+                "half_day" => {
+                    let (date, time) = parse_half_day(value).ok_or_else(invalid)?;
+                    calendar.half_days.insert(date, time);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        let missing = |key: &str| TaError::InvalidCalendar {
+            line: None,
+            key: key.to_string(),
+        };
+        calendar.open = open.ok_or_else(|| missing("open"))?;
+        calendar.close = close.ok_or_else(|| missing("close"))?;
+        Ok(calendar)
+    }
+}
+
+impl fmt::Display for SessionCalendar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "SESSIONS({} {}-{})",
+            self.name,
+            self.open.format("%H:%M"),
+            self.close.format("%H:%M")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const NYSE: &str = "
+        # New York Stock Exchange
+        name = XNYS
+        utc_offset = -05:00
+        dst = us
+        open = 09:30
+        close = 16:00
+        holiday = 2024-07-04
+        half_day = 2024-07-03 13:00
+    ";
+
+    const CME: &str = "
+        name = CME Globex
+        utc_offset = -06:00
+        dst = us
+        open = 17:00   # the evening before
+        close = 16:00
+        weekdays = mon,tue,wed,thu,fri
+    ";
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let calendar: SessionCalendar = NYSE.parse().unwrap();
+        assert_eq!(calendar.name(), "XNYS");
+        assert_eq!(format!("{}", calendar), "SESSIONS(XNYS 09:30-16:00)");
+
+        let error = |line: Option<usize>, key: &str| {
+            Err(TaError::InvalidCalendar {
+                line,
+                key: key.to_string(),
+            })
+        };
+        assert_eq!(
+            "open = 09:30".parse::<SessionCalendar>(),
+            error(None, "close")
+        );
+        assert_eq!(
+            "open = 09:30\nclose = 25:00".parse::<SessionCalendar>(),
+            error(Some(2), "close")
+        );
+        assert_eq!(
+            "open = 09:30\n# comment\n\ndst = asia".parse::<SessionCalendar>(),
+            error(Some(4), "dst")
+        );
+        assert_eq!(
+            "timezone = UTC".parse::<SessionCalendar>(),
+            error(Some(1), "timezone")
+        );
+        assert_eq!(
+            "utc_offset = 05:00".parse::<SessionCalendar>(),
+            error(Some(1), "utc_offset")
+        );
+        assert_eq!(
+            "open 09:30".parse::<SessionCalendar>(),
+            error(Some(1), "open 09:30")
+        );
+        assert_eq!(
+            "half_day = 2024-07-03".parse::<SessionCalendar>(),
+            error(Some(1), "half_day")
+        );
+        match SessionCalendar::from_file("does/not/exist.txt") {
+            Err(TaError::Io { path, kind, .. }) => {
+                assert_eq!(path, "does/not/exist.txt");
+                assert_eq!(kind, std::io::ErrorKind::NotFound);
+            }
+            other => panic!("expected an I/O error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_dst_transitions() {
+        let calendar: SessionCalendar = NYSE.parse().unwrap();
+
+        // Winter: EST is UTC-5
+        assert_eq!(
+            calendar.session(date(2024, 3, 8)),
+            Some((utc(2024, 3, 8, 14, 30), utc(2024, 3, 8, 21, 0)))
+        );
+        // The clocks change on Sunday 2024-03-10: EDT is UTC-4
+        assert_eq!(
+            calendar.session(date(2024, 3, 11)),
+            Some((utc(2024, 3, 11, 13, 30), utc(2024, 3, 11, 20, 0)))
+        );
+        // And back on Sunday 2024-11-03
+        assert_eq!(
+            calendar.session(date(2024, 11, 1)),
+            Some((utc(2024, 11, 1, 13, 30), utc(2024, 11, 1, 20, 0)))
+        );
+        assert_eq!(
+            calendar.session(date(2024, 11, 4)),
+            Some((utc(2024, 11, 4, 14, 30), utc(2024, 11, 4, 21, 0)))
+        );
+
+        assert_eq!(last_sunday(2024, 3), date(2024, 3, 31));
+        assert_eq!(last_sunday(2024, 10), date(2024, 10, 27));
+        assert!(DstRule::Europe.is_summer_time(date(2024, 3, 31)));
+        assert!(!DstRule::Europe.is_summer_time(date(2024, 10, 27)));
+    }
+
+    #[test]
+    fn test_holidays_and_half_days() {
+        let calendar: SessionCalendar = NYSE.parse().unwrap();
+
+        assert!(!calendar.is_trading_day(date(2024, 7, 4)));
+        assert!(!calendar.is_trading_day(date(2024, 7, 6))); // Saturday
+        assert_eq!(calendar.session(date(2024, 7, 4)), None);
+        assert_eq!(
+            calendar.session(date(2024, 7, 3)),
+            Some((utc(2024, 7, 3, 13, 30), utc(2024, 7, 3, 17, 0)))
+        );
+
+        assert!(calendar.is_open(utc(2024, 7, 3, 16, 59)));
+        assert!(!calendar.is_open(utc(2024, 7, 3, 17, 0)));
+        assert!(!calendar.is_open(utc(2024, 7, 4, 15, 0)));
+    }
+
+    #[test]
+    fn test_trading_day() {
+        let calendar: SessionCalendar = NYSE.parse().unwrap();
+
+        assert_eq!(
+            calendar.trading_day(utc(2024, 7, 2, 13, 30)),
+            Some(date(2024, 7, 2))
+        );
+        // Pre-market still belongs to the previous session
+        assert_eq!(
+            calendar.trading_day(utc(2024, 7, 2, 13, 29)),
+            Some(date(2024, 7, 1))
+        );
+        // The holiday belongs to the half day before it
+        assert_eq!(
+            calendar.trading_day(utc(2024, 7, 4, 15, 0)),
+            Some(date(2024, 7, 3))
+        );
+        // Weekend after the Friday session
+        assert_eq!(
+            calendar.trading_day(utc(2024, 7, 7, 12, 0)),
+            Some(date(2024, 7, 5))
+        );
+    }
+
+    #[test]
+    fn test_overnight_session() {
+        let calendar: SessionCalendar = CME.parse().unwrap();
+
+        // Monday's session opens on Sunday evening, Chicago time
+        assert_eq!(
+            calendar.session(date(2024, 1, 8)),
+            Some((utc(2024, 1, 7, 23, 0), utc(2024, 1, 8, 22, 0)))
+        );
+        assert_eq!(
+            calendar.trading_day(utc(2024, 1, 7, 23, 0)),
+            Some(date(2024, 1, 8))
+        );
+        assert_eq!(
+            calendar.trading_day(utc(2024, 1, 8, 21, 59)),
+            Some(date(2024, 1, 8))
+        );
+        assert!(!calendar.is_open(utc(2024, 1, 8, 22, 30)));
+        assert_eq!(
+            calendar.trading_day(utc(2024, 1, 8, 23, 0)),
+            Some(date(2024, 1, 9))
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Versioned serde format of the indicator state.
+//!
+//! With the `serde` feature every indicator,
+//! [AdaptiveTimeDetector](../indicators/struct.AdaptiveTimeDetector.html),
+//! [TimeWindow](../indicators/struct.TimeWindow.html), [BarBuilder](../struct.BarBuilder.html)
+//! and [Ordered](../struct.Ordered.html) serializes as a struct of two fields: the `version` of
+//! its layout and the `state` itself. Nested indicators, e.g. the EMAs of a MACD, carry their own
+//! version.
+//!
+//! Deserializing reads the version first and migrates a state written by an older release to the
+//! current layout. A state with a version newer than the one of this release is an error instead
+//! of being misread.
+//!
+//! States written by ta 0.5 and earlier have no version. They can be read with [Legacy]:
+//!
+//! ```
+//! use ta::indicators::SimpleMovingAverage;
+//! use ta::state::{Legacy, Versioned};
+//!
+//! fn load(bytes: &[u8], versioned: bool) -> bincode::Result<SimpleMovingAverage> {
+//!     if versioned {
+//!         bincode::deserialize(bytes)
+//!     } else {
+//!         bincode::deserialize(bytes).map(|Legacy(sma)| sma)
+//!     }
+//! }
+//!
+//! assert_eq!(SimpleMovingAverage::VERSION, 1);
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A type with a versioned serde format.
+pub trait Versioned: Sized {
+    /// Version of the layout written by this release. Version 0 is the unversioned layout of
+    /// ta 0.5.
+    const VERSION: u32;
+
+    /// Writes the state without the version.
+    fn serialize_state<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+    /// Reads a state with the layout of `version`, which is at most [VERSION](#associatedconstant.VERSION).
+    fn deserialize_state<'de, D: Deserializer<'de>>(
+        version: u32,
+        deserializer: D,
+    ) -> Result<Self, D::Error>;
+}
+
+/// A state serialized by ta 0.5 or earlier, before states had a version.
+///
+/// Deserializing migrates it to the current layout. Types that did not exist in ta 0.5 return
+/// an error.
+#[derive(Debug, Clone)]
+pub struct Legacy<T>(pub T);
+
+impl<'de, T: Versioned> Deserialize<'de> for Legacy<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize_state(0, deserializer).map(Legacy)
+    }
+}
+
+// A layout of an older version, converted to the current one after it is read
+pub(crate) trait Migrate<T>: DeserializeOwned {
+    fn migrate(self) -> crate::errors::Result<T>;
+}
+
+pub(crate) fn migrate<'de, L, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    L: Migrate<T>,
+    D: Deserializer<'de>,
+{
+    L::deserialize(deserializer)?
+        .migrate()
+        .map_err(|e| de::Error::custom(format_args!("cannot migrate state: {}", e)))
+}
+
+pub(crate) fn unsupported<E: de::Error>(version: u32, latest: u32) -> E {
+    if version == 0 {
+        E::custom("no unversioned layout to migrate from")
+    } else {
+        E::custom(format_args!(
+            "unsupported state version {}, the latest is {}",
+            version, latest
+        ))
+    }
+}
+
+pub(crate) fn serialize<T: Versioned, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut envelope = serializer.serialize_struct("Versioned", 2)?;
+    envelope.serialize_field("version", &T::VERSION)?;
+    envelope.serialize_field("state", &State(value))?;
+    envelope.end()
+}
+
+pub(crate) fn deserialize<'de, T: Versioned, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    deserializer.deserialize_struct("Versioned", FIELDS, EnvelopeVisitor(PhantomData))
+}
+
+const FIELDS: &[&str] = &["version", "state"];
+
+struct State<'a, T>(&'a T);
+
+impl<T: Versioned> Serialize for State<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_state(serializer)
+    }
+}
+
+// Reads the state once the version is known
+struct StateSeed<T> {
+    version: u32,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T: Versioned> DeserializeSeed<'de> for StateSeed<T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        T::deserialize_state(self.version, deserializer)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field {
+    Version,
+    State,
+}
+
+struct EnvelopeVisitor<T>(PhantomData<T>);
+
+impl<T: Versioned> EnvelopeVisitor<T> {
+    fn seed<E: de::Error>(version: u32) -> Result<StateSeed<T>, E> {
+        if version > T::VERSION {
+            return Err(unsupported(version, T::VERSION));
+        }
+        Ok(StateSeed {
+            version,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<'de, T: Versioned> Visitor<'de> for EnvelopeVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a versioned state")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let version = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        seq.next_element_seed(Self::seed(version)?)?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))
+    }
+
+    // The version has to come first, as it is written, to know how to read the state
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+        match map.next_key()? {
+            Some(Field::Version) => {}
+            Some(Field::State) => {
+                return Err(de::Error::custom("`version` must come before `state`"))
+            }
+            None => return Err(de::Error::missing_field("version")),
+        }
+        let version = map.next_value()?;
+        match map.next_key()? {
+            Some(Field::State) => map.next_value_seed(Self::seed(version)?),
+            Some(Field::Version) => Err(de::Error::duplicate_field("version")),
+            None => Err(de::Error::missing_field("state")),
+        }
+    }
+}
+
+/// Implements `Versioned`, `Serialize` and `Deserialize` for `$type` from `$state`, a serde remote
+/// definition of the layout of `$version`, and the layouts of older versions to migrate from.
+macro_rules! versioned_serde {
+    ($type:ty, $state:ident, $version:literal $(, $old:literal => $layout:ty)* $(,)?) => {
+        impl $crate::state::Versioned for $type {
+            const VERSION: u32 = $version;
+
+            fn serialize_state<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                $state::serialize(self, serializer)
+            }
+
+            fn deserialize_state<'de, D: serde::Deserializer<'de>>(
+                version: u32,
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                match version {
+                    $version => $state::deserialize(deserializer),
+                    $($old => $crate::state::migrate::<$layout, Self, D>(deserializer),)*
+                    _ => Err($crate::state::unsupported(version, $version)),
+                }
+            }
+        }
+
+        impl serde::Serialize for $type {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                $crate::state::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $type {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                $crate::state::deserialize(deserializer)
+            }
+        }
+    };
+}
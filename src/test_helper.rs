@@ -1,4 +1,9 @@
-#[derive(Debug, PartialEq)]
+use std::time::Duration;
+
+use super::{Close, High, Low, Open, Volume};
+use chrono::{DateTime, TimeZone, Utc};
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Bar {
     open: f64,
     high: f64,
@@ -18,6 +23,11 @@ impl Bar {
         }
     }
 
+    pub fn open<T: Into<f64>>(mut self, val: T) -> Self {
+        self.open = val.into();
+        self
+    }
+
     pub fn high<T: Into<f64>>(mut self, val: T) -> Self {
         self.high = val.into();
         self
@@ -39,20 +49,62 @@ impl Bar {
     }
 }
 
+impl Open for Bar {
+    fn open(&self) -> f64 {
+        self.open
+    }
+}
+
+impl Close for Bar {
+    fn close(&self) -> f64 {
+        self.close
+    }
+}
+
+impl Low for Bar {
+    fn low(&self) -> f64 {
+        self.low
+    }
+}
+
+impl High for Bar {
+    fn high(&self) -> f64 {
+        self.high
+    }
+}
+
+impl Volume for Bar {
+    fn volume(&self) -> f64 {
+        self.volume
+    }
+}
+
 pub fn round(num: f64) -> f64 {
     (num * 1000.0).round() / 1000.00
 }
 
+pub const DAY: Duration = Duration::from_secs(86400);
+
+// The `n`th day from 2024-01-01, at the 16:00 close
+pub fn day(n: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 16, 0, 0).unwrap() + chrono::Duration::days(n)
+}
+
 macro_rules! test_indicator {
     ($i:tt) => {
         #[test]
         fn test_indicator() {
-            use chrono::TimeZone; // Import TimeZone trait to use the Utc.ymd method
+            use chrono::TimeZone; // Import TimeZone trait to use the Utc.with_ymd_and_hms method
 
-            let bar = Bar::new();
+            let bar = Bar::new()
+                .open(12.0)
+                .high(13.0)
+                .low(11.5)
+                .close(12.3)
+                .volume(1000.0);
 
             // Create a fixed timestamp for testing
-            let timestamp = Utc.ymd(2023, 1, 1).and_hms(0, 0, 0);
+            let timestamp = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
 
             // ensure Default trait is implemented
             let mut indicator = $i::default();
@@ -62,17 +114,22 @@ macro_rules! test_indicator {
             let first_output = indicator.next((timestamp, 12.3));
 
             // ensure next accepts &DataItem as well
-            // You will need to modify the implementation of Next for &DataItem
-            // to accept a tuple with a timestamp as well
-            // For example:
-            // indicator.next((timestamp, &bar));
+            indicator.next((timestamp, &bar));
 
             // ensure Reset is implemented and works correctly
             indicator.reset();
             assert_eq!(indicator.next((timestamp, 12.3)), first_output);
 
+            // ensure Peek reads the latest output without feeding a new value
+            assert_eq!(crate::Peek::current(&indicator), Some(first_output));
+            assert_eq!(crate::Peek::sample_count(&indicator), 1);
+            assert!(!crate::Peek::is_ready(&indicator));
+            indicator.reset();
+            assert_eq!(crate::Peek::current(&indicator), None);
+            assert_eq!(crate::Peek::sample_count(&indicator), 0);
+
             // ensure Display is implemented
-            format!("{}", indicator);
+            let _ = format!("{}", indicator);
         }
     };
 }
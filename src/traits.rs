@@ -1,5 +1,8 @@
 // Indicator traits
 
+use std::fmt::Display;
+
+use crate::errors::{Result, TaError};
 use chrono::{DateTime, Utc};
 
 /// Resets an indicator to the initial state.
@@ -9,16 +12,285 @@ pub trait Reset {
 
 /// Consumes a data item of type `T` and returns `Output`.
 ///
-/// Typically `T` can be `f64` or a struct similar to [DataItem](struct.DataItem.html), that implements
-/// traits necessary to calculate value of a particular indicator.
+/// Typically `T` can be `f64` or a reference to a struct similar to [DataItem](struct.DataItem.html),
+/// that implements traits necessary to calculate value of a particular indicator (e.g. [Close]).
 ///
 /// In most cases `Output` is `f64`, but sometimes it can be different. For example for
-/// [MACD](indicators/struct.MovingAverageConvergenceDivergence.html) it is `(f64, f64, f64)` since
-/// MACD returns 3 values.
+/// [MACD](indicators/struct.MovingAverageConvergenceDivergence.html) it is
+/// [MovingAverageConvergenceDivergenceOutput](indicators/struct.MovingAverageConvergenceDivergenceOutput.html)
+/// since MACD returns 3 values.
 ///
 pub trait Next<T> {
     type Output;
     fn next(&mut self, input: (DateTime<Utc>, T)) -> Self::Output;
 }
 
+/// Reads the state of an indicator without feeding it a new value.
+pub trait Peek {
+    type Output;
+
+    /// The value returned by the latest call to `next`, `None` before the first one.
+    fn current(&self) -> Option<Self::Output>;
+
+    /// Whether the indicator has seen updates over its full duration, so the value no longer
+    /// depends on how it was started (e.g. RSI returning 50 before the first change).
+    fn is_ready(&self) -> bool;
+
+    /// Number of time buckets fed since the last reset. Updates that replace the value of the
+    /// current bucket are not counted.
+    fn sample_count(&self) -> usize;
+}
+
+/// What [TryNext] checks an input against, before feeding it to an indicator.
+pub trait Validate {
+    /// The timestamp of the latest update since the last reset. Older updates are rejected.
+    fn latest_time(&self) -> Option<DateTime<Utc>>;
+
+    /// Whether the indicator takes ratios of its inputs, so prices that are zero or negative
+    /// are rejected.
+    fn requires_positive(&self) -> bool {
+        false
+    }
+}
+
+/// Like [Next], but returns an error instead of feeding an input that would corrupt the state
+/// of the indicator: a value that is NaN or infinite and would stay in its sums, an update older
+/// than the latest one, or a price that is not positive for an indicator taking ratios of prices.
+///
+/// The error carries the indicator, the timestamp and the value, and the indicator is left
+/// unchanged. Implemented for every indicator, fed with `f64` values or with bars, of which the
+/// [High], [Low] and [Close] prices are checked. The indicators weighting bars by their [Volume],
+/// [OnBalanceVolume](indicators/struct.OnBalanceVolume.html) and
+/// [MoneyFlowIndex](indicators/struct.MoneyFlowIndex.html), check the volume too and reject it
+/// when negative.
+///
+/// # Example
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::errors::TaError;
+/// use ta::indicators::SimpleMovingAverage;
+/// use ta::TryNext;
+///
+/// let mut sma = SimpleMovingAverage::new(Duration::from_secs(3 * 86400)).unwrap();
+/// let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+///
+/// assert_eq!(sma.try_next((now, 2.0)), Ok(2.0));
+/// assert!(matches!(
+///     sma.try_next((now, f64::NAN)),
+///     Err(TaError::NotFinite { .. })
+/// ));
+/// ```
+pub trait TryNext<T>: Next<T> {
+    fn try_next(&mut self, input: (DateTime<Utc>, T)) -> Result<Self::Output>;
+}
+
+impl<I> TryNext<f64> for I
+where
+    I: Next<f64> + Validate + Display,
+{
+    fn try_next(&mut self, (timestamp, value): (DateTime<Utc>, f64)) -> Result<Self::Output> {
+        check(self, timestamp, &[value])?;
+        Ok(self.next((timestamp, value)))
+    }
+}
+
+impl<'a, I, T> TryNext<&'a T> for I
+where
+    I: Next<&'a T> + Validate + Display,
+    T: High + Low + Close,
+{
+    fn try_next(&mut self, (timestamp, bar): (DateTime<Utc>, &'a T)) -> Result<Self::Output> {
+        check(self, timestamp, &[bar.high(), bar.low(), bar.close()])?;
+        Ok(self.next((timestamp, bar)))
+    }
+}
+
+fn check<I: Validate + Display>(
+    indicator: &I,
+    timestamp: DateTime<Utc>,
+    values: &[f64],
+) -> Result<()> {
+    check_values(
+        indicator,
+        indicator.latest_time(),
+        indicator.requires_positive(),
+        timestamp,
+        values,
+    )
+}
+
+// The checks of `check` plus the volume, for the indicators implementing `TryNext` on bars
+// themselves: the blanket implementation cannot read the volume of any bar
+pub(crate) fn check_with_volume<I: Display>(
+    indicator: &I,
+    latest: Option<DateTime<Utc>>,
+    timestamp: DateTime<Utc>,
+    prices: &[f64],
+    volume: f64,
+) -> Result<()> {
+    check_values(indicator, latest, false, timestamp, prices)?;
+    check_values(indicator, None, false, timestamp, &[volume])?;
+    if volume < 0.0 {
+        return Err(TaError::NotPositive {
+            indicator: indicator.to_string(),
+            timestamp,
+            value: volume,
+        });
+    }
+    Ok(())
+}
+
+fn check_values<I: Display>(
+    indicator: &I,
+    latest: Option<DateTime<Utc>>,
+    requires_positive: bool,
+    timestamp: DateTime<Utc>,
+    values: &[f64],
+) -> Result<()> {
+    if let Some(latest) = latest.filter(|&latest| timestamp < latest) {
+        return Err(TaError::OutOfOrder {
+            indicator: indicator.to_string(),
+            timestamp,
+            latest,
+        });
+    }
+    for &value in values {
+        if !value.is_finite() {
+            return Err(TaError::NotFinite {
+                indicator: indicator.to_string(),
+                timestamp,
+                value,
+            });
+        }
+        if value <= 0.0 && requires_positive {
+            return Err(TaError::NotPositive {
+                indicator: indicator.to_string(),
+                timestamp,
+                value,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Open price of a particular period.
+pub trait Open {
+    fn open(&self) -> f64;
+}
+
+/// Close price of a particular period.
+pub trait Close {
+    fn close(&self) -> f64;
+}
+
+/// Lowest price of a particular period.
+pub trait Low {
+    fn low(&self) -> f64;
+}
+
+/// Highest price of a particular period.
+pub trait High {
+    fn high(&self) -> f64;
+}
+
+/// Trading volume of a particular trading period.
+pub trait Volume {
+    fn volume(&self) -> f64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::{RateOfChange, SimpleMovingAverage, TrueRange};
+    use crate::test_helper::*;
+    use crate::WarmUp;
+
+    #[test]
+    fn test_not_finite() {
+        let mut sma = SimpleMovingAverage::new(DAY * 3).unwrap();
+
+        assert_eq!(sma.try_next((day(0), 2.0)), Ok(2.0));
+        assert_eq!(
+            sma.try_next((day(1), f64::INFINITY)),
+            Err(TaError::NotFinite {
+                indicator: "SMA(3 days)".to_string(),
+                timestamp: day(1),
+                value: f64::INFINITY,
+            })
+        );
+        assert!(sma.try_next((day(1), f64::NAN)).is_err());
+        // The rejected inputs are not in the sum
+        assert_eq!(sma.try_next((day(1), 4.0)), Ok(3.0));
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        let mut sma = SimpleMovingAverage::new(DAY * 3).unwrap();
+
+        sma.try_next((day(1), 2.0)).unwrap();
+        assert_eq!(
+            sma.try_next((day(0), 4.0)),
+            Err(TaError::OutOfOrder {
+                indicator: "SMA(3 days)".to_string(),
+                timestamp: day(0),
+                latest: day(1),
+            })
+        );
+        assert_eq!(sma.try_next((day(2), 4.0)), Ok(3.0));
+    }
+
+    #[test]
+    fn test_not_positive() {
+        let mut roc = RateOfChange::new(DAY * 3).unwrap();
+        let mut sma = SimpleMovingAverage::new(DAY * 3).unwrap();
 
+        assert_eq!(
+            roc.try_next((day(0), 0.0)),
+            Err(TaError::NotPositive {
+                indicator: "ROC(3 days)".to_string(),
+                timestamp: day(0),
+                value: 0.0,
+            })
+        );
+        assert!(roc.try_next((day(0), -1.0)).is_err());
+        assert_eq!(sma.try_next((day(0), -1.0)), Ok(-1.0));
+    }
+
+    #[test]
+    fn test_bars() {
+        let mut tr = TrueRange::new(DAY).unwrap();
+        let bar = Bar::new().high(12).low(9).close(11);
+
+        assert_eq!(tr.try_next((day(0), &bar)), Ok(3.0));
+        let invalid = bar.clone().high(f64::NAN);
+        assert!(matches!(
+            tr.try_next((day(1), &invalid)),
+            Err(TaError::NotFinite { .. })
+        ));
+        assert_eq!(tr.try_next((day(1), &bar)), Ok(3.0));
+    }
+
+    #[test]
+    fn test_wrapped() {
+        let mut roc = WarmUp::new(RateOfChange::new(DAY * 2).unwrap());
+
+        assert_eq!(roc.try_next((day(0), 10.0)), Ok(None));
+        assert!(roc.try_next((day(1), -1.0)).is_err());
+        assert!(roc.try_next((day(-1), 11.0)).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let error = TaError::NotFinite {
+            indicator: "SMA(3 days)".to_string(),
+            timestamp: day(1),
+            value: f64::NAN,
+        };
+        assert_eq!(
+            error.to_string(),
+            "SMA(3 days): input NaN at 2024-01-02 16:00:00 UTC is not finite"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt;
+
+use crate::{Next, Peek, Reset, Validate};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Wraps an indicator so that it returns `None` until it has seen updates over its full
+/// duration, instead of cold start values like the 50 of an RSI or the 0 of a rate of change.
+///
+/// Works with every indicator, readiness is taken from [Peek::is_ready](trait.Peek.html#tymethod.is_ready).
+///
+/// # Example
+///
+/// ```
+/// use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+/// use std::time::Duration;
+/// use ta::indicators::RateOfChange;
+/// use ta::{Next, WarmUp};
+///
+/// let mut roc = WarmUp::new(RateOfChange::new(Duration::from_secs(2 * 86400)).unwrap());
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+///
+/// assert_eq!(roc.next((start, 10.0)), None);
+/// assert_eq!(roc.next((start + ChronoDuration::days(1), 11.0)), None);
+/// assert_eq!(roc.next((start + ChronoDuration::days(2), 12.0)), Some(20.0));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct WarmUp<I> {
+    indicator: I,
+}
+
+impl<I> WarmUp<I> {
+    pub fn new(indicator: I) -> Self {
+        Self { indicator }
+    }
+
+    /// The wrapped indicator.
+    pub fn inner(&self) -> &I {
+        &self.indicator
+    }
+
+    pub fn into_inner(self) -> I {
+        self.indicator
+    }
+}
+
+impl<I, T> Next<T> for WarmUp<I>
+where
+    I: Next<T> + Peek,
+{
+    type Output = Option<<I as Next<T>>::Output>;
+
+    fn next(&mut self, input: (DateTime<Utc>, T)) -> Self::Output {
+        let output = self.indicator.next(input);
+        self.indicator.is_ready().then_some(output)
+    }
+}
+
+impl<I: Peek> Peek for WarmUp<I> {
+    type Output = I::Output;
+
+    fn current(&self) -> Option<Self::Output> {
+        if self.indicator.is_ready() {
+            self.indicator.current()
+        } else {
+            None
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.indicator.is_ready()
+    }
+
+    fn sample_count(&self) -> usize {
+        self.indicator.sample_count()
+    }
+}
+
+impl<I: Validate> Validate for WarmUp<I> {
+    fn latest_time(&self) -> Option<DateTime<Utc>> {
+        self.indicator.latest_time()
+    }
+
+    fn requires_positive(&self) -> bool {
+        self.indicator.requires_positive()
+    }
+}
+
+impl<I: Reset> Reset for WarmUp<I> {
+    fn reset(&mut self) {
+        self.indicator.reset();
+    }
+}
+
+impl<I: fmt::Display> fmt::Display for WarmUp<I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.indicator.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::{
+        MaxDrawdown, MovingAverageConvergenceDivergence as Macd, RelativeStrengthIndex,
+        StandardDeviation,
+    };
+    use crate::test_helper::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_next() {
+        let mut rsi = WarmUp::new(RelativeStrengthIndex::wilder(DAY * 3).unwrap());
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let closes = [10.0, 11.0, 10.5, 11.5, 11.0];
+
+        let outputs: Vec<_> = closes
+            .iter()
+            .enumerate()
+            .map(|(day, &close)| {
+                let time = start + chrono::Duration::days(day as i64);
+                rsi.next((time, close)).map(round)
+            })
+            .collect();
+        assert_eq!(outputs, vec![None, None, None, Some(80.0), Some(61.538)]);
+        assert_eq!(rsi.current().map(round), Some(61.538));
+    }
+
+    #[test]
+    fn test_bars_and_other_outputs() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let bar = Bar::new().high(11).low(9).close(10);
+
+        let mut sd = WarmUp::new(StandardDeviation::new(DAY).unwrap());
+        assert_eq!(sd.next((start, &bar)), None);
+        assert_eq!(
+            sd.next((start + chrono::Duration::days(1), &bar)),
+            Some(0.0)
+        );
+
+        let mut macd = WarmUp::new(Macd::new(DAY, DAY * 2, DAY).unwrap());
+        assert!(macd.next((start, 10.0)).is_none());
+        assert!(macd
+            .next((start + chrono::Duration::days(2), 11.0))
+            .is_some());
+
+        let mut drawdown = WarmUp::new(MaxDrawdown::new(DAY).unwrap());
+        assert!(drawdown.next((start, 10.0)).is_none());
+        assert_eq!(drawdown.current(), None);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut sd = WarmUp::new(StandardDeviation::new(DAY * 2).unwrap());
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        sd.next((start, 1.0));
+        assert_eq!(sd.next((start + chrono::Duration::days(1), 3.0)), None);
+        assert_eq!(sd.next((start + chrono::Duration::days(2), 5.0)), Some(1.0));
+
+        sd.reset();
+        assert_eq!(sd.next((start + chrono::Duration::days(3), 3.0)), None);
+        assert_eq!(sd.sample_count(), 1);
+    }
+
+    #[test]
+    fn test_default() {
+        WarmUp::<RelativeStrengthIndex>::default();
+    }
+
+    #[test]
+    fn test_display() {
+        let sd = WarmUp::new(StandardDeviation::new(DAY * 7).unwrap());
+        assert_eq!(format!("{}", sd), format!("{}", sd.inner()));
+    }
+}
@@ -5,12 +5,192 @@ extern crate ta;
 
 #[cfg(test)]
 mod test {
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+    use ta::indicators::*;
+    use ta::{Batch, DataItem, Next, TryNext};
+
+    fn bar(open: f64, high: f64, low: f64, close: f64) -> DataItem {
+        DataItem::builder()
+            .open(open)
+            .high(high)
+            .low(low)
+            .close(close)
+            .volume(1000.0)
+            .build()
+            .unwrap()
+    }
+
+    // Feeding a bar must give the same result as feeding its close price
+    macro_rules! assert_close_path {
+        ($($indicator:expr),*) => {
+            $(
+                let bars = vec![
+                    bar(10.0, 11.0, 9.5, 10.5),
+                    bar(10.5, 12.0, 10.0, 11.5),
+                    bar(11.5, 11.8, 10.2, 10.4),
+                    bar(10.4, 10.9, 9.0, 9.2),
+                ];
+                let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+                let mut by_bar = $indicator;
+                let mut by_close = by_bar.clone();
+                for (i, item) in bars.iter().enumerate() {
+                    let timestamp = start + chrono::Duration::days(i as i64);
+                    assert_eq!(
+                        by_bar.next((timestamp, item)),
+                        by_close.next((timestamp, ta::Close::close(item)))
+                    );
+                }
+            )*
+        };
+    }
+
+    #[test]
+    fn test_data_item_input() {
+        let duration = Duration::from_secs(3 * 86400);
+        assert_close_path!(
+            SimpleMovingAverage::new(duration).unwrap(),
+            WeightedMovingAverage::new(duration).unwrap(),
+            WeightedMovingAverage::time_weighted(duration).unwrap(),
+            ExponentialMovingAverage::new(duration).unwrap(),
+            KaufmanAdaptiveMovingAverage::new(duration, duration / 2, duration * 2).unwrap(),
+            StandardDeviation::new(duration).unwrap(),
+            MeanAbsoluteDeviation::new(duration).unwrap(),
+            RelativeStrengthIndex::new(duration).unwrap(),
+            BollingerBands::new(duration, 2.0).unwrap(),
+            RateOfChange::new(duration).unwrap(),
+            EfficiencyRatio::new(duration).unwrap(),
+            Maximum::new(duration).unwrap(),
+            Minimum::new(duration).unwrap(),
+            MaxDrawdown::new(duration).unwrap(),
+            MaxDrawup::new(duration).unwrap(),
+            PercentagePriceOscillator::new(duration, duration * 2, duration).unwrap()
+        );
+    }
+
+    // Valid bars give the same results through `try_next`, invalid ones are rejected and leave
+    // the indicator as it was
+    macro_rules! assert_try_next {
+        ($($indicator:expr),*) => {
+            $(
+                let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+                let mut checked = $indicator;
+                let mut unchecked = checked.clone();
+                for i in 0..4 {
+                    let timestamp = start + chrono::Duration::days(i);
+                    let item = bar(10.0, 11.0 + i as f64, 9.0, 10.5);
+                    assert_eq!(checked.try_next((timestamp, &item)), Ok(unchecked.next((timestamp, &item))));
+                    assert!(checked.try_next((timestamp, f64::NAN)).is_err());
+                    assert!(checked.try_next((timestamp - chrono::Duration::days(1), 10.0)).is_err());
+                }
+                let timestamp = start + chrono::Duration::days(4);
+                assert_eq!(checked.try_next((timestamp, 10.0)), Ok(unchecked.next((timestamp, 10.0))));
+            )*
+        };
+    }
+
+    #[test]
+    fn test_try_next() {
+        let duration = Duration::from_secs(3 * 86400);
+        assert_try_next!(
+            SimpleMovingAverage::new(duration).unwrap(),
+            WeightedMovingAverage::new(duration).unwrap(),
+            WeightedMovingAverage::time_weighted(duration).unwrap(),
+            ExponentialMovingAverage::new(duration).unwrap(),
+            KaufmanAdaptiveMovingAverage::new(duration, duration / 2, duration * 2).unwrap(),
+            StandardDeviation::new(duration).unwrap(),
+            MeanAbsoluteDeviation::new(duration).unwrap(),
+            CommodityChannelIndex::new(duration).unwrap(),
+            RelativeStrengthIndex::new(duration).unwrap(),
+            BollingerBands::new(duration, 2.0).unwrap(),
+            KeltnerChannel::new(duration, 2.0).unwrap(),
+            ChandelierExit::new(duration, 3.0).unwrap(),
+            RateOfChange::new(duration).unwrap(),
+            EfficiencyRatio::new(duration).unwrap(),
+            Maximum::new(duration).unwrap(),
+            Minimum::new(duration).unwrap(),
+            MaxDrawdown::new(duration).unwrap(),
+            MaxDrawup::new(duration).unwrap(),
+            MovingAverageConvergenceDivergence::new(duration, duration * 2, duration).unwrap(),
+            PercentagePriceOscillator::new(duration, duration * 2, duration).unwrap()
+        );
+    }
+
+    // A batch of bars gives the same outputs as feeding them one at a time
+    macro_rules! assert_batch {
+        ($($indicator:expr),*) => {
+            $(
+                let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+                let bars: Vec<_> = (0..6)
+                    .map(|i| {
+                        let close = 10.0 + (i % 3) as f64;
+                        (start + chrono::Duration::days(i), bar(close, close + 1.0, close - 1.0, close))
+                    })
+                    .collect();
+                let mut batched = $indicator;
+                let mut one_by_one = batched.clone();
+                let expected: Vec<_> = bars
+                    .iter()
+                    .map(|(timestamp, item)| one_by_one.next((*timestamp, item)))
+                    .collect();
+                assert_eq!(batched.batch_bars(bars), expected);
+            )*
+        };
+    }
+
+    #[test]
+    fn test_batch() {
+        let duration = Duration::from_secs(3 * 86400);
+        assert_batch!(
+            SimpleMovingAverage::new(duration).unwrap(),
+            WeightedMovingAverage::new(duration).unwrap(),
+            WeightedMovingAverage::time_weighted(duration).unwrap(),
+            ExponentialMovingAverage::new(duration).unwrap(),
+            KaufmanAdaptiveMovingAverage::new(duration, duration / 2, duration * 2).unwrap(),
+            StandardDeviation::new(duration).unwrap(),
+            MeanAbsoluteDeviation::new(duration).unwrap(),
+            CommodityChannelIndex::new(duration).unwrap(),
+            RelativeStrengthIndex::new(duration).unwrap(),
+            BollingerBands::new(duration, 2.0).unwrap(),
+            KeltnerChannel::new(duration, 2.0).unwrap(),
+            ChandelierExit::new(duration, 3.0).unwrap(),
+            RateOfChange::new(duration).unwrap(),
+            EfficiencyRatio::new(duration).unwrap(),
+            Maximum::new(duration).unwrap(),
+            Minimum::new(duration).unwrap(),
+            MaxDrawdown::new(duration).unwrap(),
+            MaxDrawup::new(duration).unwrap(),
+            MovingAverageConvergenceDivergence::new(duration, duration * 2, duration).unwrap(),
+            PercentagePriceOscillator::new(duration, duration * 2, duration).unwrap(),
+            TrueRange::new(duration).unwrap(),
+            AverageTrueRange::new(duration).unwrap(),
+            FastStochastic::new(duration).unwrap(),
+            SlowStochastic::new(duration, duration).unwrap(),
+            MoneyFlowIndex::new(duration).unwrap(),
+            OnBalanceVolume::new(duration).unwrap()
+        );
+    }
+
     #[cfg(feature = "serde")]
     mod serde {
-        use chrono::Utc;
+        use super::bar;
+        use chrono::{DateTime, TimeZone, Utc};
+        use serde::{de::DeserializeOwned, Serialize};
+        use std::collections::VecDeque;
         use std::time::Duration;
-        use ta::indicators::SimpleMovingAverage;
-        use ta::Next;
+        use ta::indicators::*;
+        use ta::state::{Legacy, Versioned};
+        use ta::{BarBuilder, Next, Ordered, OutOfOrderPolicy, Peek};
+
+        const DAY: Duration = Duration::from_secs(86400);
+
+        fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+            bincode::deserialize(&bincode::serialize(value).unwrap()).unwrap()
+        }
+
+        fn day(n: i64) -> DateTime<Utc> {
+            Utc.with_ymd_and_hms(2024, 1, 1, 16, 0, 0).unwrap() + chrono::Duration::days(n)
+        }
 
         // Simple smoke test that serde works (not sure if this is really necessary)
         #[test]
@@ -22,5 +202,397 @@ mod test {
             let now = Utc::now();
             assert_eq!(deserialized.next((now, 2.0)), sma.next((now, 2.0)));
         }
+
+        // A restored indicator must continue exactly like the original, including the
+        // revision of its latest time bucket
+        macro_rules! assert_round_trip {
+            ($($indicator:expr),* $(,)?) => {
+                $(
+                    let bars = vec![
+                        bar(10.0, 11.0, 9.5, 10.5),
+                        bar(10.5, 12.0, 10.0, 11.5),
+                        bar(11.5, 11.8, 10.2, 10.4),
+                        bar(10.4, 10.9, 9.0, 9.2),
+                        bar(9.2, 10.1, 8.7, 9.9),
+                        bar(9.9, 10.6, 9.5, 10.2),
+                    ];
+                    let mut indicator = $indicator;
+                    for (n, item) in bars[..3].iter().enumerate() {
+                        indicator.next((day(n as i64), item));
+                    }
+                    let mut restored = round_trip(&indicator);
+                    assert_eq!(restored.sample_count(), indicator.sample_count());
+
+                    let revision = day(2) + chrono::Duration::hours(1);
+                    let later = [(revision, &bars[3]), (day(3), &bars[4]), (day(4), &bars[5])];
+                    for input in later {
+                        assert_eq!(restored.next(input), indicator.next(input));
+                    }
+                )*
+            };
+        }
+
+        #[test]
+        fn test_round_trip() {
+            let duration = DAY * 3;
+            assert_round_trip!(
+                SimpleMovingAverage::new(duration).unwrap(),
+                WeightedMovingAverage::new(duration).unwrap(),
+                WeightedMovingAverage::time_weighted(duration).unwrap(),
+                ExponentialMovingAverage::new(duration).unwrap(),
+                ExponentialMovingAverage::wilder(duration).unwrap(),
+                ExponentialMovingAverage::time_decay(duration).unwrap(),
+                KaufmanAdaptiveMovingAverage::new(duration, DAY * 2, duration * 2).unwrap(),
+                StandardDeviation::new(duration).unwrap(),
+                MeanAbsoluteDeviation::new(duration).unwrap(),
+                CommodityChannelIndex::new(duration).unwrap(),
+                RelativeStrengthIndex::new(duration).unwrap(),
+                RelativeStrengthIndex::cutler(duration).unwrap(),
+                BollingerBands::new(duration, 2.0).unwrap(),
+                KeltnerChannel::new(duration, 2.0).unwrap(),
+                ChandelierExit::new(duration, 3.0).unwrap(),
+                RateOfChange::new(duration).unwrap(),
+                EfficiencyRatio::new(duration).unwrap(),
+                Maximum::new(duration).unwrap(),
+                Minimum::with_policy(duration, BucketPolicy::CalendarDay).unwrap(),
+                MaxDrawdown::new(duration).unwrap(),
+                MaxDrawup::new(duration).unwrap(),
+                TrueRange::new(duration).unwrap(),
+                AverageTrueRange::wilder(duration).unwrap(),
+                FastStochastic::new(duration).unwrap(),
+                SlowStochastic::new(duration, DAY * 2).unwrap(),
+                MovingAverageConvergenceDivergence::new(DAY * 2, duration, DAY * 2).unwrap(),
+                PercentagePriceOscillator::new(DAY * 2, duration, DAY * 2).unwrap(),
+                MoneyFlowIndex::new(duration).unwrap(),
+                OnBalanceVolume::new(duration).unwrap(),
+            );
+        }
+
+        #[test]
+        fn test_round_trip_other_types() {
+            let mut detector = AdaptiveTimeDetector::new(DAY);
+            detector.should_replace(day(0));
+            let mut restored = round_trip(&detector);
+            assert_eq!(restored.bucket_count(), 1);
+            let revision = day(0) + chrono::Duration::hours(1);
+            assert_eq!(
+                restored.should_replace(revision),
+                detector.should_replace(revision)
+            );
+
+            let mut window = TimeWindow::<(Sum, Highest)>::new(DAY * 2).unwrap();
+            window.push(day(0), 3.0);
+            window.push(day(1), 5.0);
+            let mut restored = round_trip(&window);
+            assert_eq!(restored.values(), window.values());
+            restored.push(day(2), 1.0);
+            assert_eq!(restored.aggregate().0.value(), 6.0);
+            assert_eq!(restored.aggregate().1.value(), Some(5.0));
+
+            let sma = SimpleMovingAverage::new(DAY * 3).unwrap();
+            let mut ordered = Ordered::new(sma, OutOfOrderPolicy::Insert(DAY));
+            ordered.next((day(0), 2.0)).unwrap();
+            ordered.next((day(2), 6.0)).unwrap();
+            let mut restored = round_trip(&ordered);
+            assert_eq!(restored.next((day(1), 7.0)), ordered.next((day(1), 7.0)));
+
+            let mut builder = BarBuilder::ticks(2).unwrap();
+            builder.next((day(0), (10.0, 1.0))).unwrap();
+            let mut restored = round_trip(&builder);
+            assert_eq!(
+                restored.next((day(1), (11.0, 2.0))),
+                builder.next((day(1), (11.0, 2.0)))
+            );
+        }
+
+        #[test]
+        fn test_version() {
+            let sma = SimpleMovingAverage::new(Duration::from_secs(20)).unwrap();
+            let mut bytes = bincode::serialize(&sma).unwrap();
+            let version: u32 = bincode::deserialize(&bytes).unwrap();
+            assert_eq!(version, SimpleMovingAverage::VERSION);
+
+            // A state from a newer release is not misread
+            bytes[..4].copy_from_slice(&99u32.to_le_bytes());
+            let error = bincode::deserialize::<SimpleMovingAverage>(&bytes).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "unsupported state version 99, the latest is 1"
+            );
+        }
+
+        // The layouts of ta 0.5, serialized as tuples since bincode writes structs as their fields
+        type LegacyDetector = (DetectedFrequency, i64, Option<DateTime<Utc>>);
+        type LegacyEma = (
+            Duration,
+            f64,
+            VecDeque<(DateTime<Utc>, f64)>,
+            f64,
+            bool,
+            LegacyDetector,
+            f64,
+        );
+
+        // Daily closes of 10, 11 and 12 as ta 0.5 kept them
+        fn legacy_window() -> (VecDeque<(DateTime<Utc>, f64)>, LegacyDetector) {
+            let window: VecDeque<_> = (0..3).map(|n| (day(n), 10.0 + n as f64)).collect();
+            let detector = (DetectedFrequency::DailyOHLC, i64::MIN, Some(day(2)));
+            (window, detector)
+        }
+
+        fn legacy_ema(current: f64, last_value: f64) -> LegacyEma {
+            let (_, detector) = legacy_window();
+            (
+                DAY * 3,
+                0.5,
+                VecDeque::new(),
+                current,
+                false,
+                detector,
+                last_value,
+            )
+        }
+
+        fn migrate<T: Versioned>(legacy: &impl Serialize) -> T {
+            let bytes = bincode::serialize(legacy).unwrap();
+            let Legacy(migrated) = bincode::deserialize(&bytes).unwrap();
+            migrated
+        }
+
+        // A migrated indicator must continue like one fed the same closes from scratch
+        macro_rules! assert_migrated {
+            ($migrated:expr, $fresh:expr) => {
+                let (window, _) = legacy_window();
+                let mut migrated = $migrated;
+                let mut fresh = $fresh;
+                for &input in &window {
+                    fresh.next(input);
+                }
+                assert_eq!(migrated.sample_count(), fresh.sample_count());
+                assert_eq!(migrated.current(), fresh.current());
+                for input in [(day(2) + chrono::Duration::hours(1), 9.0), (day(3), 13.0)] {
+                    assert_eq!(migrated.next(input), fresh.next(input));
+                }
+            };
+        }
+
+        #[test]
+        fn test_legacy_migration() {
+            let duration = DAY * 3;
+            let (window, detector) = legacy_window();
+            let sum: f64 = window.iter().map(|&(_, value)| value).sum();
+            let sum_sq: f64 = window.iter().map(|&(_, value)| value * value).sum();
+            let sd = (duration, &window, sum, sum_sq, &detector);
+
+            assert_migrated!(
+                migrate::<SimpleMovingAverage>(&(duration, &window, sum, &detector)),
+                SimpleMovingAverage::new(duration).unwrap()
+            );
+            assert_migrated!(
+                migrate::<StandardDeviation>(&sd),
+                StandardDeviation::new(duration).unwrap()
+            );
+            assert_migrated!(
+                migrate::<MeanAbsoluteDeviation>(&(duration, sum, &window, &detector)),
+                MeanAbsoluteDeviation::new(duration).unwrap()
+            );
+            assert_migrated!(
+                migrate::<Minimum>(&(duration, &window, 10.0, &detector)),
+                Minimum::new(duration).unwrap()
+            );
+            assert_migrated!(
+                migrate::<Maximum>(&(duration, &window, &detector)),
+                Maximum::new(duration).unwrap()
+            );
+            assert_migrated!(
+                migrate::<RateOfChange>(&(duration, &window, &detector)),
+                RateOfChange::new(duration).unwrap()
+            );
+            assert_migrated!(
+                migrate::<MaxDrawdown>(&(duration, &window, &detector)),
+                MaxDrawdown::new(duration).unwrap()
+            );
+            assert_migrated!(
+                migrate::<MaxDrawup>(&(duration, &window, &detector)),
+                MaxDrawup::new(duration).unwrap()
+            );
+            assert_migrated!(
+                migrate::<BollingerBands>(&(duration, 2.0, sd, &window, &detector)),
+                BollingerBands::new(duration, 2.0).unwrap()
+            );
+        }
+
+        #[test]
+        fn test_legacy_migration_ema_and_rsi() {
+            let duration = DAY * 3;
+            let (window, detector) = legacy_window();
+
+            // 10, 10.5 and 11.25 with the factor 2 / (3 + 1)
+            let ema: ExponentialMovingAverage = migrate(&legacy_ema(11.25, 12.0));
+            let mut fresh = ExponentialMovingAverage::new(duration).unwrap();
+            for &input in &window {
+                fresh.next(input);
+            }
+            let mut migrated = ema;
+            assert_eq!(migrated.current(), fresh.current());
+            for input in [(day(2) + chrono::Duration::hours(1), 9.0), (day(3), 13.0)] {
+                assert_eq!(migrated.next(input), fresh.next(input));
+            }
+
+            // Gains of 0, 1 and 1 and no losses; the legacy RSI keeps the bucket's first close
+            let rsi = (
+                duration,
+                legacy_ema(0.75, 1.0),
+                legacy_ema(0.0, 0.0),
+                &window,
+                Some(12.0),
+                &detector,
+            );
+            assert_migrated!(
+                migrate::<RelativeStrengthIndex>(&rsi),
+                RelativeStrengthIndex::new(duration).unwrap()
+            );
+        }
+
+        // States written by earlier versions of the current format
+        #[test]
+        fn test_version_migration() {
+            let duration = DAY * 3;
+            let (values, _) = legacy_window();
+            let mut sums = TimeWindow::<(Sum, SumOfSquares)>::new(duration).unwrap();
+            for &(time, value) in &values {
+                sums.push(time, value);
+            }
+
+            let mut detector = AdaptiveTimeDetector::new(duration);
+            for &(time, _) in &values {
+                detector.should_replace(time);
+            }
+            let sd = (1u32, &sums);
+            assert_migrated!(
+                bincode::deserialize::<StandardDeviation>(&bincode::serialize(&sd).unwrap())
+                    .unwrap(),
+                StandardDeviation::new(duration).unwrap()
+            );
+            let bb = (1u32, (duration, 2.0, sd));
+            assert_migrated!(
+                bincode::deserialize::<BollingerBands>(&bincode::serialize(&bb).unwrap()).unwrap(),
+                BollingerBands::new(duration, 2.0).unwrap()
+            );
+
+            let mut sum = TimeWindow::<Sum>::new(duration).unwrap();
+            for &(time, value) in &values {
+                sum.push(time, value);
+            }
+            let mad = (1u32, &sum);
+            assert_migrated!(
+                bincode::deserialize::<MeanAbsoluteDeviation>(&bincode::serialize(&mad).unwrap())
+                    .unwrap(),
+                MeanAbsoluteDeviation::new(duration).unwrap()
+            );
+            let cci = (1u32, mad);
+            assert_migrated!(
+                bincode::deserialize::<CommodityChannelIndex>(&bincode::serialize(&cci).unwrap())
+                    .unwrap(),
+                CommodityChannelIndex::new(duration).unwrap()
+            );
+
+            // Steps of (2 / 3)^2 from 10 at an efficiency ratio of 1, reversed to the average
+            // before the last bucket
+            let mut er = EfficiencyRatio::new(duration).unwrap();
+            for &input in &values {
+                er.next(input);
+            }
+            let smoothing = (2.0_f64 / 3.0).powi(2);
+            let current = 10.0 + smoothing + smoothing * (2.0 - smoothing);
+            let kama = (
+                1u32,
+                (
+                    DAY * 2,
+                    DAY * 6,
+                    2.0 / 3.0,
+                    2.0 / 7.0,
+                    &er,
+                    &detector,
+                    current,
+                    12.0,
+                    smoothing,
+                ),
+            );
+            let bytes = bincode::serialize(&kama).unwrap();
+            let mut migrated: KaufmanAdaptiveMovingAverage = bincode::deserialize(&bytes).unwrap();
+            let mut fresh = KaufmanAdaptiveMovingAverage::new(duration, DAY * 2, DAY * 6).unwrap();
+            for &input in &values {
+                fresh.next(input);
+            }
+            assert_eq!(migrated.sample_count(), fresh.sample_count());
+            for input in [(day(2) + chrono::Duration::hours(1), 9.0), (day(3), 13.0)] {
+                assert!((migrated.next(input) - fresh.next(input)).abs() < 1e-9);
+            }
+
+            // 10, 10.5 and 11.25 with the factor 2 / (3 + 1), next to an empty window
+            let no_time: Option<DateTime<Utc>> = None;
+            let ema = (
+                1u32,
+                (
+                    duration,
+                    0.5,
+                    VecDeque::<(DateTime<Utc>, f64)>::new(),
+                    11.25,
+                    false,
+                    &detector,
+                    12.0,
+                    None::<usize>,
+                    0usize,
+                    None::<f64>,
+                    0.0,
+                    no_time,
+                    no_time,
+                ),
+            );
+            assert_migrated!(
+                bincode::deserialize::<ExponentialMovingAverage>(
+                    &bincode::serialize(&ema).unwrap()
+                )
+                .unwrap(),
+                ExponentialMovingAverage::new(duration).unwrap()
+            );
+
+            // `Highest` and `Lowest` wrap the monotonic deque of the version 1 layout; its
+            // largest move is found again from the values
+            let mut highest = TimeWindow::<Highest>::new(duration).unwrap();
+            let mut lowest = TimeWindow::<Lowest>::new(duration).unwrap();
+            for &(time, value) in &values {
+                highest.push(time, value);
+                lowest.push(time, value);
+            }
+            let no_state = (None::<u8>, None::<u8>);
+            let next_index = values.len() as u64;
+            let drawdown = (1u32, (0u32, &highest, next_index, no_state, no_state));
+            assert_migrated!(
+                bincode::deserialize::<MaxDrawdown>(&bincode::serialize(&drawdown).unwrap())
+                    .unwrap(),
+                MaxDrawdown::new(duration).unwrap()
+            );
+            let drawup = (1u32, (1u32, &lowest, next_index, no_state, no_state));
+            assert_migrated!(
+                bincode::deserialize::<MaxDrawup>(&bincode::serialize(&drawup).unwrap()).unwrap(),
+                MaxDrawup::new(duration).unwrap()
+            );
+        }
+
+        #[test]
+        fn test_legacy_detector() {
+            let (_, detector) = legacy_window();
+            let mut migrated: AdaptiveTimeDetector = migrate(&detector);
+            assert_eq!(migrated.frequency(), &DetectedFrequency::DailyOHLC);
+            assert_eq!(migrated.bucket_count(), 1);
+            assert!(migrated.should_replace(day(2) + chrono::Duration::hours(1)));
+            assert!(!migrated.should_replace(day(3)));
+
+            // Indicators added after ta 0.5 have no legacy layout
+            let bytes = bincode::serialize(&legacy_ema(1.0, 1.0)).unwrap();
+            assert!(bincode::deserialize::<Legacy<TrueRange>>(&bytes).is_err());
+        }
     }
 }
@@ -0,0 +1,71 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use ta::indicators::{BucketPolicy, SimpleMovingAverage};
+use ta::{Next, SessionCalendar};
+
+fn load(name: &str) -> SessionCalendar {
+    let path = format!("{}/tests/calendars/{}", env!("CARGO_MANIFEST_DIR"), name);
+    SessionCalendar::from_file(path).unwrap()
+}
+
+#[test]
+fn test_nyse_trading_days() {
+    let calendar = load("xnys.txt");
+    assert_eq!(calendar.name(), "XNYS");
+
+    let trading_days = NaiveDate::from_ymd_opt(2024, 1, 1)
+        .unwrap()
+        .iter_days()
+        .take_while(|date| *date < NaiveDate::from_ymd_opt(2025, 1, 1).unwrap())
+        .filter(|date| calendar.is_trading_day(*date))
+        .count();
+    assert_eq!(trading_days, 252);
+}
+
+#[test]
+fn test_xetra_dst() {
+    let calendar = load("xetr.txt");
+    let open = |y, m, d| calendar.session(NaiveDate::from_ymd_opt(y, m, d).unwrap());
+
+    // CET before the last Sunday of March, CEST after Easter Monday
+    assert_eq!(
+        open(2024, 3, 28).unwrap().0,
+        Utc.with_ymd_and_hms(2024, 3, 28, 8, 0, 0).unwrap()
+    );
+    assert_eq!(open(2024, 4, 1), None);
+    assert_eq!(
+        open(2024, 4, 2).unwrap().0,
+        Utc.with_ymd_and_hms(2024, 4, 2, 7, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn test_minute_data_bucketed_by_session() {
+    let policy = BucketPolicy::Session(Arc::new(load("xnys.txt")));
+    let mut sma =
+        SimpleMovingAverage::with_policy(Duration::from_secs(10 * 86400), policy).unwrap();
+
+    // Half day: 13:30 to 17:00 UTC, every minute revises the same session
+    let half_day_open = Utc.with_ymd_and_hms(2024, 7, 3, 13, 30, 0).unwrap();
+    for minute in 0..210 {
+        let value = 100.0 + minute as f64 * 0.1;
+        assert_eq!(
+            sma.next((half_day_open + chrono::Duration::minutes(minute), value)),
+            value
+        );
+    }
+
+    // A print on the holiday still revises the half day
+    let holiday = Utc.with_ymd_and_hms(2024, 7, 4, 15, 0, 0).unwrap();
+    assert_eq!(sma.next((holiday, 121.0)), 121.0);
+
+    // The next session is a new period
+    let open = Utc.with_ymd_and_hms(2024, 7, 5, 13, 30, 0).unwrap();
+    let mut last = 0.0;
+    for minute in 0..390 {
+        last = sma.next((open + chrono::Duration::minutes(minute), 130.0));
+    }
+    assert_eq!(last, 125.5);
+    assert_eq!(sma.get_window().len(), 2);
+}